@@ -0,0 +1,141 @@
+// This example shows how to change presets without the processing thread ever seeing a mix of
+// the old and the new preset, using util::PresetSwap. Presets here are a gain and a pan
+// parameter; hearing the new gain with the old pan for a block would already be audible as a
+// click in a real plugin with more parameters.
+
+#[macro_use]
+extern crate vst;
+
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+
+use vst::buffer::AudioBuffer;
+use vst::plugin::{Category, HostCallback, Info, Plugin, PluginParameters};
+use vst::util::PresetSwap;
+
+const GAIN: usize = 0;
+const PAN: usize = 1;
+const PARAMETER_COUNT: usize = 2;
+
+/// Name and `(gain, pan)` values of the built-in presets.
+const PRESETS: [(&str, [f32; PARAMETER_COUNT]); 3] = [
+    ("Center", [0.5, 0.5]),
+    ("Hard Left", [0.8, 0.0]),
+    ("Quiet Right", [0.2, 1.0]),
+];
+
+struct PresetParameters {
+    values: PresetSwap,
+    preset: AtomicI32,
+}
+
+struct PresetSwapPlugin {
+    params: Arc<PresetParameters>,
+}
+
+impl PluginParameters for PresetParameters {
+    fn change_preset(&self, preset: i32) {
+        // Load the whole preset into the inactive bank, then switch over at once. Inside the
+        // host's own preset change, the switch waits for the host's `end_set_preset`.
+        let values = match PRESETS.get(preset as usize) {
+            Some(&(_, ref values)) => values,
+            None => return,
+        };
+        self.values.begin_set_preset();
+        for (index, &value) in values.iter().enumerate() {
+            self.values.set_parameter(index, value);
+        }
+        self.values.end_set_preset();
+        self.preset.store(preset, Ordering::Relaxed);
+    }
+
+    fn get_preset_num(&self) -> i32 {
+        self.preset.load(Ordering::Relaxed)
+    }
+
+    fn get_preset_name(&self, preset: i32) -> String {
+        PRESETS.get(preset as usize).map_or("", |&(name, _)| name).to_string()
+    }
+
+    // Hosts bracket loading a preset parameter by parameter with these calls.
+    fn begin_set_preset(&self) {
+        self.values.begin_set_preset();
+    }
+
+    fn end_set_preset(&self) {
+        self.values.end_set_preset();
+    }
+
+    // Hosts call these directly, so the index may be out of range.
+    fn get_parameter(&self, index: i32) -> f32 {
+        if (index as usize) < PARAMETER_COUNT {
+            self.values.get_parameter(index as usize)
+        } else {
+            0.0
+        }
+    }
+
+    fn set_parameter(&self, index: i32, value: f32) {
+        if (index as usize) < PARAMETER_COUNT {
+            self.values.set_parameter(index as usize, value);
+        }
+    }
+
+    fn get_parameter_name(&self, index: i32) -> String {
+        match index as usize {
+            GAIN => "Gain",
+            PAN => "Pan",
+            _ => "",
+        }
+        .to_string()
+    }
+}
+
+impl Plugin for PresetSwapPlugin {
+    fn new(_host: HostCallback) -> Self {
+        let params = PresetParameters {
+            values: PresetSwap::new(PARAMETER_COUNT),
+            preset: AtomicI32::new(0),
+        };
+        params.change_preset(0);
+        PresetSwapPlugin {
+            params: Arc::new(params),
+        }
+    }
+
+    fn get_info(&self) -> Info {
+        Info {
+            name: "Preset Swap".to_string(),
+            vendor: "rust-vst".to_string(),
+            unique_id: 1_347_639_127,
+            inputs: 2,
+            outputs: 2,
+            parameters: PARAMETER_COUNT as i32,
+            presets: PRESETS.len() as i32,
+            category: Category::Effect,
+            ..Default::default()
+        }
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
+        // Read both parameters from the same preset for the whole block.
+        let (gain, pan) = {
+            let values = self.params.values.read();
+            (values.get_parameter(GAIN) * 2.0, values.get_parameter(PAN))
+        };
+        let gains = [gain * (1.0 - pan), gain * pan];
+
+        for (channel, input, output) in buffer.zip_channels() {
+            let gain = gains.get(channel).cloned().unwrap_or(gain);
+            for (in_sample, out_sample) in input.iter().zip(output) {
+                *out_sample = *in_sample * gain;
+            }
+        }
+    }
+
+    fn get_parameter_object(&mut self) -> Arc<dyn PluginParameters> {
+        Arc::clone(&self.params) as Arc<dyn PluginParameters>
+    }
+}
+
+plugin_main!(PresetSwapPlugin);
@@ -0,0 +1,211 @@
+#[macro_use]
+extern crate vst;
+
+use vst::buffer::AudioBuffer;
+use vst::plugin::{Category, HostCallback, Info, Plugin, PluginParameters};
+use vst::util::AtomicFloat;
+
+use std::sync::Arc;
+
+/// Comb filter delay lengths in samples at 44.1 kHz, from Freeverb.
+const COMB_LENGTHS: [usize; 4] = [1116, 1188, 1277, 1356];
+/// Allpass filter delay lengths in samples at 44.1 kHz, from Freeverb.
+const ALLPASS_LENGTHS: [usize; 2] = [556, 441];
+/// Extra delay of the right channel, for stereo width.
+const STEREO_SPREAD: usize = 23;
+
+/// A feedback delay line, the building block of the comb and allpass filters.
+struct DelayLine {
+    buffer: Vec<f32>,
+    position: usize,
+}
+
+impl DelayLine {
+    fn new(length: usize) -> DelayLine {
+        DelayLine {
+            buffer: vec![0.0; length.max(1)],
+            position: 0,
+        }
+    }
+
+    /// The sample written `length` samples ago.
+    fn read(&self) -> f32 {
+        self.buffer[self.position]
+    }
+
+    fn write(&mut self, sample: f32) {
+        self.buffer[self.position] = sample;
+        self.position = (self.position + 1) % self.buffer.len();
+    }
+
+    fn clear(&mut self) {
+        for sample in &mut self.buffer {
+            *sample = 0.0;
+        }
+    }
+}
+
+/// The filters of one channel.
+struct Channel {
+    combs: Vec<DelayLine>,
+    allpasses: Vec<DelayLine>,
+}
+
+impl Channel {
+    fn new(sample_rate: f32, spread: usize) -> Channel {
+        let scale = |length: usize| ((length + spread) as f32 * sample_rate / 44100.0) as usize;
+        Channel {
+            combs: COMB_LENGTHS
+                .iter()
+                .map(|&length| DelayLine::new(scale(length)))
+                .collect(),
+            allpasses: ALLPASS_LENGTHS
+                .iter()
+                .map(|&length| DelayLine::new(scale(length)))
+                .collect(),
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32) -> f32 {
+        let mut output = 0.0;
+        for comb in &mut self.combs {
+            let delayed = comb.read();
+            comb.write(input + delayed * feedback);
+            output += delayed;
+        }
+        output /= self.combs.len() as f32;
+
+        for allpass in &mut self.allpasses {
+            let delayed = allpass.read();
+            allpass.write(output + delayed * 0.5);
+            output = delayed - output * 0.5;
+        }
+        output
+    }
+
+    fn clear(&mut self) {
+        for line in self.combs.iter_mut().chain(&mut self.allpasses) {
+            line.clear();
+        }
+    }
+}
+
+/// A simple stereo reverb which clears its tail whenever processing is interrupted, so no stale
+/// audio from before is played when the host starts processing again.
+struct Reverb {
+    channels: [Channel; 2],
+    params: Arc<ReverbParameters>,
+}
+
+struct ReverbParameters {
+    decay: AtomicFloat,
+    dry_wet: AtomicFloat,
+}
+
+impl Reverb {
+    fn clear(&mut self) {
+        for channel in &mut self.channels {
+            channel.clear();
+        }
+    }
+}
+
+impl Plugin for Reverb {
+    fn new(_host: HostCallback) -> Self {
+        Reverb {
+            channels: [Channel::new(44100.0, 0), Channel::new(44100.0, STEREO_SPREAD)],
+            params: Arc::new(ReverbParameters {
+                decay: AtomicFloat::new(0.5),
+                dry_wet: AtomicFloat::new(0.3),
+            }),
+        }
+    }
+
+    fn get_info(&self) -> Info {
+        Info {
+            name: "Reverb".to_string(),
+            vendor: "rust-vst".to_string(),
+            unique_id: 1_385_370_978,
+            inputs: 2,
+            outputs: 2,
+            parameters: 2,
+            category: Category::RoomFx,
+            // Roughly the time it takes the longest comb filter to decay at the highest decay.
+            tail_samples: 44100 * 5,
+            ..Default::default()
+        }
+    }
+
+    fn set_sample_rate(&mut self, rate: f32) {
+        // New delay lines start out silent.
+        self.channels = [Channel::new(rate, 0), Channel::new(rate, STEREO_SPREAD)];
+    }
+
+    // Called when the host suspends the plugin.
+    fn reset(&mut self) {
+        self.clear();
+    }
+
+    // Called when the host restarts processing without suspending the plugin first, e.g. after
+    // changing its configuration.
+    fn start_process(&mut self) {
+        self.clear();
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
+        if buffer.input_count() < 2 || buffer.output_count() < 2 {
+            return;
+        }
+
+        let feedback = 0.7 + self.params.decay.get() * 0.28;
+        let dry_wet = self.params.dry_wet.get();
+
+        for (channel, (input, output)) in self.channels.iter_mut().zip(buffer.zip()) {
+            for (input_sample, output_sample) in input.iter().zip(output) {
+                let wet = channel.process(*input_sample, feedback);
+                *output_sample = *input_sample * (1.0 - dry_wet) + wet * dry_wet;
+            }
+        }
+    }
+
+    fn get_parameter_object(&mut self) -> Arc<dyn PluginParameters> {
+        Arc::clone(&self.params) as Arc<dyn PluginParameters>
+    }
+}
+
+impl PluginParameters for ReverbParameters {
+    fn get_parameter(&self, index: i32) -> f32 {
+        match index {
+            0 => self.decay.get(),
+            1 => self.dry_wet.get(),
+            _ => 0.0,
+        }
+    }
+
+    fn set_parameter(&self, index: i32, value: f32) {
+        match index {
+            0 => self.decay.set(value),
+            1 => self.dry_wet.set(value),
+            _ => (),
+        }
+    }
+
+    fn get_parameter_text(&self, index: i32) -> String {
+        match index {
+            0 => format!("{:.0}%", self.decay.get() * 100.0),
+            1 => format!("{:.0}%", self.dry_wet.get() * 100.0),
+            _ => String::new(),
+        }
+    }
+
+    fn get_parameter_name(&self, index: i32) -> String {
+        match index {
+            0 => "Decay",
+            1 => "Dry/Wet",
+            _ => "",
+        }
+        .to_string()
+    }
+}
+
+plugin_main!(Reverb);
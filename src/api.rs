@@ -1,5 +1,7 @@
 //! Structures and types for interfacing with the VST 2.4 API.
 
+use std::fmt;
+use std::mem;
 use std::os::raw::c_void;
 use std::sync::Arc;
 
@@ -17,6 +19,10 @@ pub mod consts {
     pub const MAX_SHORT_LABEL: usize = 8;
     pub const MAX_PRODUCT_STR_LEN: usize = 64;
     pub const MAX_VENDOR_STR_LEN: usize = 64;
+    pub const MAX_NAME_LEN: usize = 64;
+    pub const MAX_EFFECT_NAME_LEN: usize = 32;
+    pub const MAX_CATEGORY_LABEL_LEN: usize = 24;
+    pub const MAX_PATH_LEN: usize = 2048;
 
     /// VST plugins are identified by a magic number. This corresponds to 0x56737450.
     pub const VST_MAGIC: i32 = ('V' as i32) << 24 | ('s' as i32) << 16 | ('t' as i32) << 8 | ('P' as i32);
@@ -131,6 +137,12 @@ pub struct AEffect {
     pub future: [u8; 56],
 }
 
+// Compile-time check that `AEffect` has the same size as the C struct of the VST 2.4 SDK.
+#[cfg(target_pointer_width = "64")]
+const _: [(); 192] = [(); mem::size_of::<AEffect>()];
+#[cfg(target_pointer_width = "32")]
+const _: [(); 144] = [(); mem::size_of::<AEffect>()];
+
 impl AEffect {
     /// Return handle to Plugin object. Only works for plugins created using this library.
     /// Caller is responsible for not calling this function concurrently.
@@ -143,7 +155,7 @@ impl AEffect {
 
     /// Return handle to Info object. Only works for plugins created using this library.
     pub unsafe fn get_info(&self) -> &Info {
-        &(*(self.user as *mut super::PluginCache)).info
+        (*(self.user as *const super::PluginCache)).info.get()
     }
 
     /// Return handle to PluginParameters object. Only works for plugins created using this library.
@@ -157,6 +169,12 @@ impl AEffect {
         &mut (*(self.user as *mut super::PluginCache)).editor
     }
 
+    /// Decode `flags`, e.g. to check whether a loaded plugin has an editor or processes in double
+    /// precision. Undefined and deprecated bits are dropped. Works for any plugin.
+    pub fn plugin_flags(&self) -> PluginFlags {
+        PluginFlags::from_bits_truncate(self.flags)
+    }
+
     /// Drop the Plugin object. Only works for plugins created using this library.
     pub unsafe fn drop_plugin(&mut self) {
         drop(Box::from_raw(self.object as *mut Box<dyn Plugin>));
@@ -164,6 +182,24 @@ impl AEffect {
     }
 }
 
+/// Buffers for processing with differing input and output lengths, e.g. for offline time
+/// stretching. Used with `plugin::OpCode::ProcessVarIo`.
+#[repr(C)]
+pub struct VariableIo {
+    /// Input channel buffers.
+    pub inputs: *mut *mut f32,
+    /// Output channel buffers.
+    pub outputs: *mut *mut f32,
+    /// Number of samples in each input buffer.
+    pub num_samples_input: i32,
+    /// Number of samples in each output buffer.
+    pub num_samples_output: i32,
+    /// Set by the plugin to the number of input samples it consumed.
+    pub num_samples_input_processed: *mut i32,
+    /// Set by the plugin to the number of output samples it wrote.
+    pub num_samples_output_processed: *mut i32,
+}
+
 /// Information about a channel. Only some hosts use this information.
 #[repr(C)]
 pub struct ChannelProperties {
@@ -183,6 +219,120 @@ pub struct ChannelProperties {
     pub future: [u8; 48],
 }
 
+/// Display properties of a parameter. Used with `plugin::OpCode::GetParamInfo`.
+#[repr(C)]
+pub struct ParameterProperties {
+    /// Float step, used with `ParameterFlags::USES_FLOAT_STEP`.
+    pub step_float: f32,
+
+    /// Small float step, used with `ParameterFlags::USES_FLOAT_STEP`.
+    pub small_step_float: f32,
+
+    /// Large float step, used with `ParameterFlags::USES_FLOAT_STEP`.
+    pub large_step_float: f32,
+
+    /// Parameter label.
+    pub label: [u8; MAX_LABEL],
+
+    /// Flags found in `ParameterFlags`.
+    pub flags: i32,
+
+    /// Integer minimum, used with `ParameterFlags::USES_INTEGER_MIN_MAX`.
+    pub min_integer: i32,
+
+    /// Integer maximum, used with `ParameterFlags::USES_INTEGER_MIN_MAX`.
+    pub max_integer: i32,
+
+    /// Integer step, used with `ParameterFlags::USES_INT_STEP`.
+    pub step_integer: i32,
+
+    /// Large integer step, used with `ParameterFlags::USES_INT_STEP`.
+    pub large_step_integer: i32,
+
+    /// Short label (recommended: 6 characters + delimiter).
+    pub short_label: [u8; MAX_SHORT_LABEL],
+
+    /// Index in which the parameter is displayed, used with
+    /// `ParameterFlags::SUPPORTS_DISPLAY_INDEX`.
+    pub display_index: i16,
+
+    /// Category of the parameter, 0 means no category. Used with
+    /// `ParameterFlags::SUPPORTS_DISPLAY_CATEGORY`.
+    pub category: i16,
+
+    /// Number of parameters in the category.
+    pub num_parameters_in_category: i16,
+
+    /// Reserved, should be 0.
+    pub _reserved: i16,
+
+    /// Category label, e.g. "Osc 1".
+    pub category_label: [u8; MAX_CATEGORY_LABEL_LEN],
+
+    /// Reserved for future use.
+    pub future: [u8; 16],
+}
+
+/// Properties of a single speaker in a `SpeakerArrangement`.
+#[repr(C)]
+pub struct SpeakerProperties {
+    /// Azimuth in radians, between -pi and pi.
+    pub azimuth: f32,
+
+    /// Elevation in radians, between -pi/2 and pi/2.
+    pub elevation: f32,
+
+    /// Distance from the listener, between 0 and 1.
+    pub radius: f32,
+
+    /// Reserved, should be 0.
+    pub _reserved: f32,
+
+    /// Speaker name.
+    pub name: [u8; MAX_NAME_LEN],
+
+    /// Speaker type, e.g. left or LFE.
+    pub speaker_type: i32,
+
+    /// Reserved for future use.
+    pub future: [u8; 28],
+}
+
+/// Layout of the speakers connected to a set of channels.
+///
+/// The `speakers` array is variable sized: only the first `num_channels` entries are valid and
+/// the host or plugin may allocate more than 8 of them.
+#[repr(C)]
+pub struct SpeakerArrangement {
+    /// Type of the arrangement.
+    pub arrangement_type: SpeakerArrangementType,
+
+    /// Number of channels in this arrangement.
+    pub num_channels: i32,
+
+    /// Properties of each speaker.
+    pub speakers: [SpeakerProperties; 8],
+}
+
+/// Name of a MIDI key, e.g. the drum sound mapped to it. Used with `plugin::OpCode::GetMidiKeyName`.
+#[repr(C)]
+pub struct MidiKeyName {
+    /// Current program index.
+    pub this_program_index: i32,
+
+    /// Key number the name is requested for.
+    pub this_key_number: i32,
+
+    /// Key name. Empty means the regular note name is used.
+    pub key_name: [u8; MAX_NAME_LEN],
+
+    /// Reserved, should be 0.
+    pub _reserved: i32,
+
+    /// Reserved, none defined yet, should be 0.
+    pub _flags: i32,
+}
+
 /// Tells the host how the channels are intended to be used in the plugin. Only useful for some
 /// hosts.
 #[repr(i32)]
@@ -266,9 +416,20 @@ pub enum SpeakerArrangementType {
     Surround102,
 }
 
+impl SpeakerArrangementType {
+    /// Convert a raw value to this enum, returning `None` if it is out of range.
+    pub fn checked_from(value: i64) -> Option<SpeakerArrangementType> {
+        if value >= SpeakerArrangementType::Custom as i64 && value <= SpeakerArrangementType::Surround102 as i64 {
+            Some(unsafe { mem::transmute::<i32, SpeakerArrangementType>(value as i32) })
+        } else {
+            None
+        }
+    }
+}
+
 /// Used to specify whether functionality is supported.
 #[allow(missing_docs)]
-#[derive(PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum Supported {
     Yes,
     Maybe,
@@ -320,6 +481,35 @@ pub enum ProcessLevel {
     Offline,
 }
 
+/// Automation state of the host, see `Host::get_automation_state`.
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AutomationState {
+    /// Unsupported by host.
+    Unsupported,
+
+    /// Automation is off.
+    Off,
+    /// Automation is being read (played back).
+    Read,
+    /// Automation is being written (recorded).
+    Write,
+    /// Automation is being read and written.
+    ReadWrite,
+}
+impl_clike!(AutomationState; last = ReadWrite);
+
+/// Pan law of the host, see `Plugin::set_pan_law`.
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanLaw {
+    /// Linear pan law, keeping the sum of both channel gains constant.
+    Linear,
+    /// Equal power pan law, keeping the sum of both squared channel gains constant.
+    EqualPower,
+}
+impl_clike!(PanLaw; last = EqualPower);
+
 /// Language that the host is using.
 #[repr(i32)]
 #[allow(missing_docs)]
@@ -474,7 +664,35 @@ impl Events {
     #[inline]
     #[allow(clippy::needless_lifetimes)]
     pub fn events<'a>(&'a self) -> impl Iterator<Item = ::event::Event<'a>> {
-        self.events_raw().iter().map(|ptr| unsafe { **ptr }.into())
+        use std::slice;
+        self.events_raw().iter().map(|&ptr| unsafe {
+            match (*ptr).event_type {
+                // A SysEx event is larger than `Event`, so its payload pointer has to be read
+                // through the original pointer rather than a copy.
+                EventType::SysEx => {
+                    #[allow(clippy::cast_ptr_alignment)]
+                    let event = &*(ptr as *const SysExEvent);
+                    ::event::Event::SysEx(::event::SysExEvent {
+                        payload: slice::from_raw_parts(event.system_data, event.data_size as usize),
+                        delta_frames: event.delta_frames,
+                    })
+                }
+                _ => (*ptr).into(),
+            }
+        })
+    }
+
+    /// The events sorted by `delta_frames`, for processing a block in sample order.
+    ///
+    /// Hosts usually send events in order, but not all do. The sort is stable, so events on the
+    /// same frame (e.g. a note off followed by a note on of the same key) keep their order.
+    ///
+    /// This allocates the returned `Vec`. On the processing thread, collect `events()` into a
+    /// preallocated `Vec` and sort it with `sort_by_key(Event::delta_frames)` instead.
+    pub fn sorted_by_frame(&self) -> Vec<::event::Event<'_>> {
+        let mut events: Vec<_> = self.events().collect();
+        events.sort_by_key(::event::Event::delta_frames);
+        events
     }
 }
 
@@ -658,7 +876,8 @@ pub struct TimeInfo {
     /// current Sample Rate in Hertz (always valid)
     pub sample_rate: f64,
 
-    /// System Time in nanoseconds (10^-9 second)
+    /// System Time in nanoseconds (10^-9 second), valid with `TimeInfoFlags::NANOSECONDS_VALID`.
+    /// Not all hosts provide it, see `system_time`.
     pub nanoseconds: f64,
 
     /// Musical Position, in Quarter Note (1.0 equals 1 Quarter Note)
@@ -696,6 +915,214 @@ pub struct TimeInfo {
     pub flags: i32,
 }
 
+impl TimeInfo {
+    /// Musical position in quarter notes at `offset` samples into the current block.
+    ///
+    /// This allows sample accurate tempo synced effects such as LFOs and delays. The position is
+    /// extrapolated from `ppq_pos` using `tempo` and `sample_rate`, so `get_time_info` should be
+    /// called with both `TimeInfoFlags::PPQ_POS_VALID` and `TimeInfoFlags::TEMPO_VALID` set.
+    ///
+    /// If the host did not provide `ppq_pos`, it is derived from `sample_pos` and `tempo`. If the
+    /// host did not provide `tempo`, the position cannot be extrapolated and `ppq_pos` is returned
+    /// (0.0 if it is not valid either).
+    pub fn ppq_at_sample(&self, offset: usize) -> f64 {
+        let flags = TimeInfoFlags::from_bits_truncate(self.flags);
+        let ppq_valid = flags.contains(TimeInfoFlags::PPQ_POS_VALID);
+        let ppq_pos = if ppq_valid { self.ppq_pos } else { 0.0 };
+
+        if !flags.contains(TimeInfoFlags::TEMPO_VALID) || self.sample_rate <= 0.0 {
+            return ppq_pos;
+        }
+
+        let quarters_per_sample = self.tempo / 60.0 / self.sample_rate;
+        if ppq_valid {
+            ppq_pos + offset as f64 * quarters_per_sample
+        } else {
+            (self.sample_pos + offset as f64) * quarters_per_sample
+        }
+    }
+
+    /// Fill in `tempo` (in BPM) and the time signature `numerator`/`denominator` where the host
+    /// didn't provide valid values, and mark them as valid.
+    ///
+    /// Hosts without a musical timeline, such as audio editors and offline batch processors,
+    /// often report no tempo or time signature (or zero), and some hosts provide no time info at
+    /// all. With this, tempo synced plugins can fall back to a default instead of dividing by
+    /// zero:
+    ///
+    /// ```
+    /// # use vst::api::TimeInfo;
+    /// # let time_info: Option<TimeInfo> = None;
+    /// // `time_info` as returned from `Host::get_time_info`.
+    /// let time_info = time_info.unwrap_or_default().with_fallback(120.0, 4, 4);
+    /// assert_eq!(time_info.tempo, 120.0);
+    /// ```
+    pub fn with_fallback(mut self, tempo: f64, numerator: i32, denominator: i32) -> TimeInfo {
+        let mut flags = TimeInfoFlags::from_bits_truncate(self.flags);
+
+        if !flags.contains(TimeInfoFlags::TEMPO_VALID) || self.tempo.is_nan() || self.tempo <= 0.0 {
+            self.tempo = tempo;
+            flags.insert(TimeInfoFlags::TEMPO_VALID);
+        }
+        if !flags.contains(TimeInfoFlags::TIME_SIG_VALID)
+            || self.time_sig_numerator <= 0
+            || self.time_sig_denominator <= 0
+        {
+            self.time_sig_numerator = numerator;
+            self.time_sig_denominator = denominator;
+            flags.insert(TimeInfoFlags::TIME_SIG_VALID);
+        }
+
+        // Keep undefined bits set by the host.
+        self.flags = (self.flags & !TimeInfoFlags::all().bits()) | flags.bits();
+        self
+    }
+
+    /// SMPTE timecode at `offset` samples into the current block.
+    ///
+    /// The position is calculated from `sample_pos` and `sample_rate`, starting at `smpte_offset`.
+    /// Returns `None` unless `get_time_info` was called with `TimeInfoFlags::SMPTE_VALID` and the
+    /// host provided the frame rate.
+    pub fn smpte_at_sample(&self, offset: usize) -> Option<SmpteTime> {
+        let flags = TimeInfoFlags::from_bits_truncate(self.flags);
+        if !flags.contains(TimeInfoFlags::SMPTE_VALID) || self.sample_rate <= 0.0 {
+            return None;
+        }
+
+        let rate = self.smpte_frame_rate;
+        let seconds = (self.sample_pos + offset as f64) / self.sample_rate;
+        let subframes = seconds * rate.frames_per_second() * 80.0 + f64::from(self.smpte_offset);
+        Some(SmpteTime::from_subframes(subframes.max(0.0) as u64, rate))
+    }
+
+    /// The host's system time at the start of the block in nanoseconds, e.g. for estimating the
+    /// tempo of incoming MIDI clock when the host doesn't provide it.
+    ///
+    /// The reference point of the time is up to the host, so only differences between blocks
+    /// are meaningful. Returns `None` unless `get_time_info` was called with
+    /// `TimeInfoFlags::NANOSECONDS_VALID` and the host provided it, which many hosts don't.
+    pub fn system_time(&self) -> Option<f64> {
+        let flags = TimeInfoFlags::from_bits_truncate(self.flags);
+        if flags.contains(TimeInfoFlags::NANOSECONDS_VALID) {
+            Some(self.nanoseconds)
+        } else {
+            None
+        }
+    }
+
+    /// The current bar and the beat within it, at the start of the block.
+    ///
+    /// Beats are counted in units of the time signature denominator, so in 7/8 a bar is 3.5
+    /// quarter notes and `beat` runs from 0.0 up to 7.0. Use `ppq_at_sample` for positions later
+    /// in the block.
+    ///
+    /// Returns `None` unless `get_time_info` was called with `TimeInfoFlags::PPQ_POS_VALID` and
+    /// `TimeInfoFlags::TIME_SIG_VALID` and the host provided both, or if the position is before
+    /// the start of the project (e.g. during a count-in).
+    ///
+    /// With `TimeInfoFlags::BARS_VALID`, the beat is measured from `bar_start_pos`, which stays
+    /// correct across time signature changes. Otherwise, the bars are counted from the start of
+    /// the project, assuming the current time signature was used throughout. The bar number is
+    /// always derived that way, as the host doesn't report it.
+    pub fn bar_position(&self) -> Option<BarPosition> {
+        let flags = TimeInfoFlags::from_bits_truncate(self.flags);
+        if !flags.contains(TimeInfoFlags::PPQ_POS_VALID | TimeInfoFlags::TIME_SIG_VALID)
+            || self.time_sig_numerator <= 0
+            || self.time_sig_denominator <= 0
+            || self.ppq_pos.is_nan()
+            || self.ppq_pos < 0.0
+        {
+            return None;
+        }
+
+        let quarters_per_beat = 4.0 / f64::from(self.time_sig_denominator);
+        let quarters_per_bar = f64::from(self.time_sig_numerator) * quarters_per_beat;
+
+        let bar_start = if flags.contains(TimeInfoFlags::BARS_VALID) && self.bar_start_pos <= self.ppq_pos {
+            self.bar_start_pos
+        } else {
+            (self.ppq_pos / quarters_per_bar).floor() * quarters_per_bar
+        };
+
+        Some(BarPosition {
+            bar: (bar_start / quarters_per_bar).round().max(0.0) as u32,
+            beat: (self.ppq_pos - bar_start) / quarters_per_beat,
+        })
+    }
+
+    /// Musical position in quarter notes of `position`, assuming the current time signature was
+    /// used since the start of the project. The inverse of `bar_position` without
+    /// `TimeInfoFlags::BARS_VALID`.
+    ///
+    /// Returns `None` unless the host provided a valid time signature
+    /// (`TimeInfoFlags::TIME_SIG_VALID`).
+    pub fn ppq_at_bar_position(&self, position: BarPosition) -> Option<f64> {
+        let flags = TimeInfoFlags::from_bits_truncate(self.flags);
+        if !flags.contains(TimeInfoFlags::TIME_SIG_VALID)
+            || self.time_sig_numerator <= 0
+            || self.time_sig_denominator <= 0
+        {
+            return None;
+        }
+        let quarters_per_beat = 4.0 / f64::from(self.time_sig_denominator);
+        let quarters_per_bar = f64::from(self.time_sig_numerator) * quarters_per_beat;
+        Some(f64::from(position.bar) * quarters_per_bar + position.beat * quarters_per_beat)
+    }
+
+    /// Offset into the current block of `samples` samples of the first sample at or after the
+    /// musical position `target_ppq` (in quarter notes), for scheduling events at musical
+    /// positions, e.g. together with `ppq_at_bar_position`.
+    ///
+    /// Returns `None` if the target lies outside the block, which includes targets before its
+    /// start. The offset is calculated from `ppq_pos`, `tempo` and `sample_rate`, so
+    /// `get_time_info` must be called with `TimeInfoFlags::PPQ_POS_VALID` and
+    /// `TimeInfoFlags::TEMPO_VALID` and the host must provide both, otherwise `None` is returned
+    /// as well. Tempo changes and loop jumps within the block are not taken into account, as the
+    /// host only reports the state at the start of the block.
+    ///
+    /// ```
+    /// # use vst::api::{TimeInfo, TimeInfoFlags};
+    /// let time_info = TimeInfo {
+    ///     ppq_pos: 3.75,
+    ///     tempo: 120.0,
+    ///     sample_rate: 48000.0,
+    ///     flags: (TimeInfoFlags::PPQ_POS_VALID | TimeInfoFlags::TEMPO_VALID).bits(),
+    ///     ..Default::default()
+    /// };
+    /// // A quarter note lasts 24000 samples at 120 BPM, so the next beat is 6000 samples away.
+    /// assert_eq!(time_info.sample_offset_for_ppq(4.0, 8192), Some(6000));
+    /// assert_eq!(time_info.sample_offset_for_ppq(4.0, 512), None);
+    /// ```
+    pub fn sample_offset_for_ppq(&self, target_ppq: f64, samples: usize) -> Option<i64> {
+        let flags = TimeInfoFlags::from_bits_truncate(self.flags);
+        if !flags.contains(TimeInfoFlags::PPQ_POS_VALID | TimeInfoFlags::TEMPO_VALID)
+            || !self.tempo.is_finite()
+            || self.tempo <= 0.0
+            || self.sample_rate <= 0.0
+        {
+            return None;
+        }
+
+        let samples_per_quarter = self.sample_rate * 60.0 / self.tempo;
+        // Allow for rounding errors, so a target exactly on a sample isn't pushed to the next one.
+        let offset = ((target_ppq - self.ppq_pos) * samples_per_quarter - 1e-6).ceil();
+        if offset >= 0.0 && offset < samples as f64 {
+            Some(offset as i64)
+        } else {
+            None
+        }
+    }
+}
+
+/// A musical position in bars and beats, returned from `TimeInfo::bar_position`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BarPosition {
+    /// Index of the current bar, 0 for the first bar of the project.
+    pub bar: u32,
+    /// Position within the bar in beats of the time signature denominator, starting at 0.0.
+    pub beat: f64,
+}
+
 #[repr(i32)]
 #[derive(Copy, Clone, Debug)]
 /// SMPTE Frame Rates.
@@ -734,6 +1161,97 @@ impl Default for SmpteFrameRate {
     }
 }
 
+impl SmpteFrameRate {
+    /// The actual number of frames per second, e.g. 29.97 for `Smpte2997fps`.
+    ///
+    /// The film rates are 24 fps.
+    pub fn frames_per_second(self) -> f64 {
+        use self::SmpteFrameRate::*;
+
+        match self {
+            Smpte24fps | SmpteFilm16mm | SmpteFilm35mm => 24.0,
+            Smpte25fps => 25.0,
+            Smpte2997fps | Smpte2997dfps => 30_000.0 / 1001.0,
+            Smpte30fps | Smpte30dfps => 30.0,
+            Smpte239fps => 24_000.0 / 1001.0,
+            Smpte249fps => 25_000.0 / 1001.0,
+            Smpte599fps => 60_000.0 / 1001.0,
+            Smpte60fps => 60.0,
+        }
+    }
+
+    /// The number of frames per timecode second, e.g. 30 for `Smpte2997fps`.
+    pub fn nominal_frames_per_second(self) -> u32 {
+        self.frames_per_second().round() as u32
+    }
+
+    /// Whether timecode at this rate skips frame numbers to stay in sync with the clock.
+    pub fn is_drop_frame(self) -> bool {
+        matches!(self, SmpteFrameRate::Smpte2997dfps | SmpteFrameRate::Smpte30dfps)
+    }
+}
+
+/// A SMPTE timecode, returned from `TimeInfo::smpte_at_sample`.
+///
+/// Formats as `hh:mm:ss:ff`, or `hh:mm:ss;ff` for drop frame rates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SmpteTime {
+    /// Hours, wrapping around after 24.
+    pub hours: u32,
+    /// Minutes.
+    pub minutes: u32,
+    /// Seconds.
+    pub seconds: u32,
+    /// Frames, less than `SmpteFrameRate::nominal_frames_per_second`.
+    pub frames: u32,
+    /// Position within the frame in 1/80 frames.
+    pub subframes: u32,
+    /// Whether `frames` are counted with drop frame numbering.
+    pub drop_frame: bool,
+}
+
+impl SmpteTime {
+    /// Timecode at `subframes` (1/80 frames) from 00:00:00:00.
+    pub fn from_subframes(subframes: u64, rate: SmpteFrameRate) -> SmpteTime {
+        let fps = u64::from(rate.nominal_frames_per_second());
+        let mut frame = subframes / 80;
+
+        if rate.is_drop_frame() {
+            // Frame numbers 0 and 1 are skipped at the start of every minute, except for every
+            // tenth minute.
+            let dropped = 2;
+            let per_minute = fps * 60 - dropped;
+            let per_ten_minutes = fps * 600 - dropped * 9;
+            let tens = frame / per_ten_minutes;
+            let rest = frame % per_ten_minutes;
+            frame += dropped * 9 * tens;
+            if rest > dropped {
+                frame += dropped * ((rest - dropped) / per_minute);
+            }
+        }
+
+        SmpteTime {
+            hours: (frame / (fps * 3600) % 24) as u32,
+            minutes: (frame / (fps * 60) % 60) as u32,
+            seconds: (frame / fps % 60) as u32,
+            frames: (frame % fps) as u32,
+            subframes: (subframes % 80) as u32,
+            drop_frame: rate.is_drop_frame(),
+        }
+    }
+}
+
+impl fmt::Display for SmpteTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let separator = if self.drop_frame { ';' } else { ':' };
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours, self.minutes, self.seconds, separator, self.frames
+        )
+    }
+}
+
 bitflags! {
     /// Flags for VST channels.
     pub struct ChannelFlags: i32 {
@@ -764,6 +1282,26 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Flags for `ParameterProperties`.
+    pub struct ParameterFlags: i32 {
+        /// Parameter is a switch (on/off).
+        const IS_SWITCH = 1;
+        /// `min_integer` and `max_integer` are valid.
+        const USES_INTEGER_MIN_MAX = 1 << 1;
+        /// `step_float`, `small_step_float` and `large_step_float` are valid.
+        const USES_FLOAT_STEP = 1 << 2;
+        /// `step_integer` and `large_step_integer` are valid.
+        const USES_INT_STEP = 1 << 3;
+        /// `display_index` is valid.
+        const SUPPORTS_DISPLAY_INDEX = 1 << 4;
+        /// `category`, `num_parameters_in_category` and `category_label` are valid.
+        const SUPPORTS_DISPLAY_CATEGORY = 1 << 5;
+        /// Parameter can ramp up and down.
+        const CAN_RAMP = 1 << 6;
+    }
+}
+
 bitflags! {
     /// Cross platform modifier key flags.
     pub struct ModifierKey: u8 {
@@ -871,6 +1409,217 @@ mod tests {
         ec
     }
 
+    /// Check the field offsets of `AEffect` against the layout of the VST 2.4 SDK.
+    #[test]
+    fn aeffect_layout() {
+        use std::mem::MaybeUninit;
+        use std::ptr::addr_of;
+
+        let effect = MaybeUninit::<AEffect>::uninit();
+        let base = effect.as_ptr();
+        let offset = |field: *const u8| field as usize - base as usize;
+
+        macro_rules! offsets {
+            ($($field:ident),*) => {
+                unsafe { [$(offset(addr_of!((*base).$field) as *const u8)),*] }
+            };
+        }
+
+        let actual = offsets!(
+            magic,
+            dispatcher,
+            _process,
+            setParameter,
+            getParameter,
+            numPrograms,
+            numParams,
+            numInputs,
+            numOutputs,
+            flags,
+            reserved1,
+            reserved2,
+            initialDelay,
+            _realQualities,
+            _offQualities,
+            _ioRatio,
+            object,
+            user,
+            uniqueId,
+            version,
+            processReplacing,
+            processReplacingF64,
+            future
+        );
+
+        #[cfg(target_pointer_width = "64")]
+        let expected = [
+            0, 8, 16, 24, 32, 40, 44, 48, 52, 56, 64, 72, 80, 84, 88, 92, 96, 104, 112, 116, 120, 128, 136,
+        ];
+        #[cfg(target_pointer_width = "32")]
+        let expected = [
+            0, 4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 44, 48, 52, 56, 60, 64, 68, 72, 76, 80, 84, 88,
+        ];
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn parameter_properties_size() {
+        assert_eq!(mem::size_of::<ParameterProperties>(), 152);
+    }
+
+    #[test]
+    fn speaker_arrangement_size() {
+        assert_eq!(mem::size_of::<SpeakerProperties>(), 112);
+        assert_eq!(mem::size_of::<SpeakerArrangement>(), 8 + 8 * 112);
+    }
+
+    #[test]
+    fn ppq_at_sample() {
+        let mut time_info = TimeInfo {
+            sample_pos: 44100.0,
+            sample_rate: 44100.0,
+            ppq_pos: 4.0,
+            tempo: 120.0,
+            flags: (TimeInfoFlags::PPQ_POS_VALID | TimeInfoFlags::TEMPO_VALID).bits(),
+            ..Default::default()
+        };
+
+        // At 120 BPM, half a second is one quarter note.
+        assert_eq!(time_info.ppq_at_sample(0), 4.0);
+        assert_eq!(time_info.ppq_at_sample(22050), 5.0);
+
+        // Derived from `sample_pos` without a valid `ppq_pos`.
+        time_info.flags = TimeInfoFlags::TEMPO_VALID.bits();
+        assert_eq!(time_info.ppq_at_sample(22050), 3.0);
+
+        // No extrapolation possible without a valid tempo.
+        time_info.flags = TimeInfoFlags::PPQ_POS_VALID.bits();
+        assert_eq!(time_info.ppq_at_sample(22050), 4.0);
+        time_info.flags = 0;
+        assert_eq!(time_info.ppq_at_sample(22050), 0.0);
+    }
+
+    #[test]
+    fn sample_offset_for_ppq() {
+        let mut time_info = TimeInfo {
+            sample_rate: 44100.0,
+            ppq_pos: 8.0,
+            tempo: 90.0,
+            flags: (TimeInfoFlags::PPQ_POS_VALID | TimeInfoFlags::TEMPO_VALID).bits(),
+            ..Default::default()
+        };
+
+        // A sixteenth note at 90 BPM is 7350 samples.
+        assert_eq!(time_info.sample_offset_for_ppq(8.0, 512), Some(0));
+        assert_eq!(time_info.sample_offset_for_ppq(8.25, 8192), Some(7350));
+        assert_eq!(time_info.sample_offset_for_ppq(8.25, 7350), None);
+        // Already passed.
+        assert_eq!(time_info.sample_offset_for_ppq(7.99, 8192), None);
+
+        time_info.flags = TimeInfoFlags::PPQ_POS_VALID.bits();
+        assert_eq!(time_info.sample_offset_for_ppq(8.0, 512), None);
+    }
+
+    #[test]
+    fn time_info_with_fallback() {
+        let time_info = TimeInfo::default().with_fallback(90.0, 3, 4);
+        assert_eq!(time_info.tempo, 90.0);
+        assert_eq!((time_info.time_sig_numerator, time_info.time_sig_denominator), (3, 4));
+        let flags = TimeInfoFlags::from_bits_truncate(time_info.flags);
+        assert!(flags.contains(TimeInfoFlags::TEMPO_VALID | TimeInfoFlags::TIME_SIG_VALID));
+
+        // Valid host values are kept, and a zero tempo is replaced even if marked as valid.
+        let time_info = TimeInfo {
+            tempo: 0.0,
+            time_sig_numerator: 7,
+            time_sig_denominator: 8,
+            flags: (TimeInfoFlags::TEMPO_VALID | TimeInfoFlags::TIME_SIG_VALID).bits(),
+            ..Default::default()
+        }
+        .with_fallback(120.0, 4, 4);
+        assert_eq!(time_info.tempo, 120.0);
+        assert_eq!((time_info.time_sig_numerator, time_info.time_sig_denominator), (7, 8));
+    }
+
+    #[test]
+    fn smpte_at_sample() {
+        let mut time_info = TimeInfo {
+            sample_pos: 48000.0 * 3661.5,
+            sample_rate: 48000.0,
+            smpte_frame_rate: SmpteFrameRate::Smpte25fps,
+            flags: TimeInfoFlags::SMPTE_VALID.bits(),
+            ..Default::default()
+        };
+        assert_eq!(time_info.smpte_at_sample(0).unwrap().to_string(), "01:01:01:12");
+        assert_eq!(time_info.smpte_at_sample(24000).unwrap().to_string(), "01:01:02:00");
+
+        // The offset is added to the position, in subframes.
+        time_info.smpte_offset = 80 * 3;
+        assert_eq!(time_info.smpte_at_sample(0).unwrap().frames, 15);
+
+        time_info.flags = 0;
+        assert!(time_info.smpte_at_sample(0).is_none());
+    }
+
+    #[test]
+    fn system_time() {
+        let mut time_info = TimeInfo {
+            nanoseconds: 1.5e9,
+            ..Default::default()
+        };
+        assert_eq!(time_info.system_time(), None);
+        time_info.flags = TimeInfoFlags::NANOSECONDS_VALID.bits();
+        assert_eq!(time_info.system_time(), Some(1.5e9));
+    }
+
+    #[test]
+    fn bar_position() {
+        let mut time_info = TimeInfo {
+            ppq_pos: 9.5,
+            time_sig_numerator: 4,
+            time_sig_denominator: 4,
+            flags: (TimeInfoFlags::PPQ_POS_VALID | TimeInfoFlags::TIME_SIG_VALID).bits(),
+            ..Default::default()
+        };
+        assert_eq!(time_info.bar_position(), Some(BarPosition { bar: 2, beat: 1.5 }));
+
+        // A bar of 7/8 is 3.5 quarter notes long, with beats in eighth notes.
+        time_info.time_sig_numerator = 7;
+        time_info.time_sig_denominator = 8;
+        assert_eq!(time_info.bar_position(), Some(BarPosition { bar: 2, beat: 5.0 }));
+
+        // The host's bar start takes precedence, e.g. after a time signature change.
+        time_info.bar_start_pos = 8.0;
+        time_info.flags |= TimeInfoFlags::BARS_VALID.bits();
+        assert_eq!(time_info.bar_position(), Some(BarPosition { bar: 2, beat: 3.0 }));
+
+        time_info.ppq_pos = -1.0;
+        assert_eq!(time_info.bar_position(), None);
+        time_info.flags = (TimeInfoFlags::PPQ_POS_VALID | TimeInfoFlags::TIME_SIG_VALID).bits();
+        assert_eq!(
+            time_info.ppq_at_bar_position(BarPosition { bar: 2, beat: 5.0 }),
+            Some(9.5)
+        );
+        time_info.ppq_pos = 9.5;
+        time_info.flags = TimeInfoFlags::PPQ_POS_VALID.bits();
+        assert_eq!(time_info.bar_position(), None);
+    }
+
+    #[test]
+    fn smpte_drop_frame() {
+        let timecode = |frame: u64| SmpteTime::from_subframes(frame * 80, SmpteFrameRate::Smpte2997dfps).to_string();
+
+        assert_eq!(timecode(1799), "00:00:59;29");
+        // Frames 0 and 1 are skipped at the start of a minute...
+        assert_eq!(timecode(1800), "00:01:00;02");
+        assert_eq!(timecode(1800 * 2 - 2), "00:02:00;02");
+        // ...except for every tenth minute.
+        assert_eq!(timecode(17982), "00:10:00;00");
+        // An hour of 29.97 fps video is exactly an hour of drop frame timecode.
+        assert_eq!(timecode(107_892), "01:00:00;00");
+    }
+
     #[test]
     fn encode_and_decode_gives_back_original_message() {
         let message: [u8; 3] = [35, 16, 22];
@@ -4,6 +4,13 @@ use num_traits::Float;
 
 use std::slice;
 
+use api::SpeakerArrangementType;
+use channels::Speaker;
+
+/// Alignment in bytes of the slices returned by `AudioBuffer::input_aligned` and
+/// `AudioBuffer::output_aligned`, enough for 128 bit SIMD (SSE, NEON) loads and stores.
+pub const SIMD_ALIGNMENT: usize = 16;
+
 /// `AudioBuffer` contains references to the audio buffers for all input and output channels.
 ///
 /// To create an `AudioBuffer` in a host, use a [`HostBuffer`](../host/struct.HostBuffer.html).
@@ -61,6 +68,55 @@ impl<'a, T: 'a + Float> AudioBuffer<'a, T> {
         &mut self.outputs
     }
 
+    /// The host's input channel pointers, for passing the buffer to C DSP libraries without
+    /// copying.
+    ///
+    /// # Safety
+    ///
+    /// Every pointer points to `samples()` samples, but only until the `process` call that this
+    /// buffer was passed to returns. Don't keep the pointers around after that, and don't write
+    /// through them: hosts may pass the same memory as input and output (in-place processing), so
+    /// inputs can also change while writing to the outputs.
+    #[inline]
+    pub unsafe fn input_ptrs(&self) -> &[*const T] {
+        self.inputs
+    }
+
+    /// The host's output channel pointers, for passing the buffer to C DSP libraries without
+    /// copying.
+    ///
+    /// # Safety
+    ///
+    /// Every pointer points to `samples()` writable samples, but only until the `process` call
+    /// that this buffer was passed to returns. Output pointers may alias the input pointers, and
+    /// no slices returned from the safe accessors may be used at the same time.
+    #[inline]
+    pub unsafe fn output_ptrs(&mut self) -> &[*mut T] {
+        self.outputs
+    }
+
+    /// Check whether any output channel shares memory with an input channel.
+    ///
+    /// VST 2.4 allows hosts to process in place, passing the same buffer as an input and an
+    /// output channel. Whether a host does this depends on the host and often on the routing, so
+    /// it can change from one block to the next. Plugins which write to an output before they are
+    /// done reading all inputs (e.g. mixing matrices, or channel swaps) should check this and copy
+    /// the affected inputs first. Plugins processing each channel sample by sample into the
+    /// output of the same channel are not affected.
+    pub fn inputs_alias_outputs(&self) -> bool {
+        let size = self.samples * std::mem::size_of::<T>();
+        if size == 0 {
+            return false;
+        }
+        self.outputs.iter().any(|&output| {
+            let output = output as usize;
+            self.inputs.iter().any(|&input| {
+                let input = input as usize;
+                input < output + size && output < input + size
+            })
+        })
+    }
+
     /// Split this buffer into separate inputs and outputs.
     #[inline]
     pub fn split<'b>(&'b mut self) -> (Inputs<'b, T>, Outputs<'b, T>)
@@ -80,6 +136,9 @@ impl<'a, T: 'a + Float> AudioBuffer<'a, T> {
     }
 
     /// Create an iterator over pairs of input buffers and output buffers.
+    ///
+    /// If the input and output counts differ, the iterator stops after the last pair, leaving the
+    /// extra channels out. Use `zip_channels` to also get the channel index.
     #[inline]
     pub fn zip<'b>(&'b mut self) -> AudioBufferIterator<'a, 'b, T> {
         AudioBufferIterator {
@@ -87,6 +146,682 @@ impl<'a, T: 'a + Float> AudioBuffer<'a, T> {
             index: 0,
         }
     }
+
+    /// Create an iterator over `(channel index, input, output)` triples, e.g. for applying
+    /// per-channel parameters.
+    ///
+    /// Channels are paired like in `zip`.
+    #[inline]
+    pub fn zip_channels<'b>(&'b mut self) -> ChannelIterator<'a, 'b, T> {
+        ChannelIterator { inner: self.zip() }
+    }
+
+    /// Create an iterator over `(main input, aux input, output)` triples, for plugins with a
+    /// sidechain.
+    ///
+    /// VST 2.4 has no separate sidechain buses, so the inputs after the first `main_inputs` are
+    /// treated as aux (sidechain) inputs, as declared by the plugin in `Info::inputs`. Main inputs
+    /// are paired with outputs like in `zip`. If there are fewer aux than main channels, the aux
+    /// channels are repeated, so a mono key signal is shared by all channels. If there are no aux
+    /// inputs (e.g. nothing is connected and the host didn't provide the channels), the aux input
+    /// is `None`.
+    #[inline]
+    pub fn zip_with_aux<'b>(&'b mut self, main_inputs: usize) -> AuxIterator<'b, T>
+    where
+        'a: 'b,
+    {
+        let main_inputs = main_inputs.min(self.input_count());
+        let (inputs, outputs) = self.split();
+        let (main, aux) = inputs.split_at(main_inputs);
+        AuxIterator {
+            main,
+            aux,
+            outputs,
+            index: 0,
+        }
+    }
+
+    /// Clamp all output samples to the range `[min, max]`.
+    ///
+    /// This can be used to prevent output overflow before the samples reach the host.
+    #[inline]
+    pub fn clamp_outputs(&mut self, min: T, max: T) {
+        let (_, mut outputs) = self.split();
+        for output in &mut outputs {
+            for sample in output.iter_mut() {
+                *sample = sample.max(min).min(max);
+            }
+        }
+    }
+
+    /// Softly saturate all output samples to the range `(-1, 1)` using `tanh`.
+    #[inline]
+    pub fn soft_clip_outputs(&mut self) {
+        let (_, mut outputs) = self.split();
+        for output in &mut outputs {
+            for sample in output.iter_mut() {
+                *sample = sample.tanh();
+            }
+        }
+    }
+
+    /// Multiply all output samples by a gain ramping linearly from `from` at the first sample to
+    /// `to` at the last.
+    ///
+    /// Pass the previous and the new value of a gain parameter to avoid the zipper noise of
+    /// changing the gain at once.
+    #[inline]
+    pub fn apply_gain_smoothed(&mut self, from: T, to: T) {
+        let (_, mut outputs) = self.split();
+        for output in &mut outputs {
+            ramp_gain(output, from, to);
+        }
+    }
+
+    /// Multiply the samples of output `channel` by a gain ramping linearly from `from` to `to`,
+    /// see `apply_gain_smoothed`.
+    ///
+    /// # Panics
+    /// Panics if `channel` is not below `output_count()`.
+    #[inline]
+    pub fn apply_gain_smoothed_channel(&mut self, channel: usize, from: T, to: T) {
+        let (_, mut outputs) = self.split();
+        ramp_gain(outputs.get_mut(channel), from, to);
+    }
+
+    /// Replace all denormal and NaN output samples with zero.
+    ///
+    /// Denormals (e.g. from decaying feedback loops) are very slow to compute on most CPUs, and a
+    /// single NaN propagates through any filter it reaches. Calling this at the end of `process`
+    /// keeps both from reaching the host; use `flush_denormal` to do the same inside a feedback
+    /// loop. Setting the FTZ/DAZ flags of the CPU (e.g. through the MXCSR register on x86) avoids
+    /// denormals altogether, but those flags are shared with the host thread and should be
+    /// restored before returning from `process`.
+    #[inline]
+    pub fn flush_denormals(&mut self) {
+        let (_, mut outputs) = self.split();
+        for output in &mut outputs {
+            for sample in output.iter_mut() {
+                *sample = flush_denormal(*sample);
+            }
+        }
+    }
+
+    /// Get input `channel` if its samples are aligned to `SIMD_ALIGNMENT` bytes.
+    ///
+    /// The VST API makes no guarantees about the alignment of host buffers. Most hosts allocate
+    /// aligned buffers, but some pass pointers into the middle of larger buffers, so this returns
+    /// `None` if the buffer is not aligned.
+    ///
+    /// # Panics
+    /// This function will panic if `channel` is out of range.
+    #[inline]
+    pub fn input_aligned(&self, channel: usize) -> Option<&[T]> {
+        let ptr = self.inputs[channel];
+        if is_aligned(ptr) {
+            Some(unsafe { slice::from_raw_parts(ptr, self.samples) })
+        } else {
+            None
+        }
+    }
+
+    /// Get output `channel` if its samples are aligned to `SIMD_ALIGNMENT` bytes.
+    ///
+    /// See `input_aligned`, and `with_aligned_output` for processing unaligned outputs.
+    ///
+    /// # Panics
+    /// This function will panic if `channel` is out of range.
+    #[inline]
+    pub fn output_aligned(&mut self, channel: usize) -> Option<&mut [T]> {
+        let ptr = self.outputs[channel];
+        if is_aligned(ptr) {
+            Some(unsafe { slice::from_raw_parts_mut(ptr, self.samples) })
+        } else {
+            None
+        }
+    }
+
+    /// Call `f` with output `channel` aligned to `SIMD_ALIGNMENT` bytes.
+    ///
+    /// If the host buffer is aligned, it is passed to `f` directly. Otherwise its samples are
+    /// copied into `scratch`, and copied back after `f` returns.
+    ///
+    /// # Panics
+    /// This function will panic if `channel` is out of range.
+    pub fn with_aligned_output<F>(&mut self, channel: usize, scratch: &mut AlignedScratch<T>, f: F)
+    where
+        F: FnOnce(&mut [T]),
+    {
+        if let Some(output) = self.output_aligned(channel) {
+            f(output);
+            return;
+        }
+
+        let output = unsafe { slice::from_raw_parts_mut(self.outputs[channel], self.samples) };
+        let aligned = scratch.get(self.samples);
+        aligned.copy_from_slice(output);
+        f(aligned);
+        output.copy_from_slice(aligned);
+    }
+
+    /// View this buffer as a single channel, regardless of the actual channel counts.
+    ///
+    /// Reading an input sample returns the average of all input channels (silence if there are
+    /// none). Writing an output sample writes the same value to all output channels.
+    #[inline]
+    pub fn as_mono<'b>(&'b mut self) -> MonoAdapter<'b, T>
+    where
+        'a: 'b,
+    {
+        let (inputs, outputs) = self.split();
+        MonoAdapter { inputs, outputs }
+    }
+
+    /// View this buffer as a stereo pair, regardless of the actual channel counts.
+    ///
+    /// With a single input channel, it is duplicated to both the left and right input. With more
+    /// than two input channels, only the first two are used. Without any inputs, silence is read.
+    ///
+    /// With a single output channel, the average of the left and right output is written to it.
+    /// With more than two output channels, only the first two are written.
+    #[inline]
+    pub fn as_stereo<'b>(&'b mut self) -> StereoAdapter<'b, T>
+    where
+        'a: 'b,
+    {
+        let (inputs, outputs) = self.split();
+        StereoAdapter { inputs, outputs }
+    }
+
+    /// View this buffer as the channels of `arrangement`, e.g. the arrangement returned by
+    /// `Host::get_output_speaker_arrangement`, to access them by speaker.
+    ///
+    /// Returns `None` for `Custom` and `Empty` arrangements, which don't define the role of the
+    /// channels; use the channel indices in that case.
+    #[inline]
+    pub fn as_multichannel<'b>(&'b mut self, arrangement: SpeakerArrangementType) -> Option<MultiChannelBuffer<'b, T>>
+    where
+        'a: 'b,
+    {
+        let speakers = Speaker::layout(arrangement)?;
+        let (inputs, outputs) = self.split();
+        Some(MultiChannelBuffer {
+            speakers,
+            inputs,
+            outputs,
+        })
+    }
+
+    /// Iterate over the buffer frame by frame, i.e. over one sample of every channel at a time.
+    ///
+    /// This is convenient for per-frame algorithms like panning. Since the channels are stored
+    /// separately, every access goes through the channel pointers; iterating channel by channel
+    /// (e.g. with `zip`) is faster and easier for the compiler to vectorize.
+    #[inline]
+    pub fn frames<'b>(&'b mut self) -> Frames<'b, T>
+    where
+        'a: 'b,
+    {
+        Frames {
+            inputs: self.inputs,
+            outputs: self.outputs,
+            index: 0,
+            samples: self.samples,
+        }
+    }
+
+    /// Create a channel-separated view of an interleaved sample array with `channels` inputs and
+    /// outputs, e.g. for hosts or test harnesses that only deal in interleaved audio.
+    ///
+    /// The samples are copied (deinterleaved) into scratch storage allocated here, so this should
+    /// not be used in the audio thread of a real-time host. Call
+    /// `InterleavedBuffer::write_outputs` to copy the outputs back into `data`.
+    ///
+    /// # Panics
+    /// This function will panic if `channels` is 0 or the length of `data` is not a multiple of
+    /// `channels`.
+    pub fn from_interleaved(data: &'a mut [T], channels: usize) -> InterleavedBuffer<'a, T> {
+        assert!(channels > 0, "An interleaved buffer needs at least one channel");
+        assert_eq!(
+            data.len() % channels,
+            0,
+            "Interleaved data is not a multiple of the channel count"
+        );
+
+        let samples = data.len() / channels;
+        let inputs: Vec<Vec<T>> = (0..channels)
+            .map(|channel| data.iter().skip(channel).step_by(channels).cloned().collect())
+            .collect();
+
+        InterleavedBuffer {
+            data,
+            inputs,
+            outputs: vec![vec![T::zero(); samples]; channels],
+            input_ptrs: Vec::with_capacity(channels),
+            output_ptrs: Vec::with_capacity(channels),
+        }
+    }
+}
+
+/// Conversion to and from plugin-owned `f64` buffers, for plugins which process in double precision
+/// when the host provides single precision buffers.
+///
+/// Widening to `f64` is exact, so converting inputs and writing them back unchanged reproduces
+/// them bit for bit. Narrowing rounds to the nearest `f32`.
+///
+/// The scratch buffers are resized to `samples()`, which only allocates when a buffer is too
+/// small. Reserve the maximum block size (see `Plugin::set_block_size`) up front to keep the
+/// conversion allocation free on the processing thread.
+impl<'a> AudioBuffer<'a, f32> {
+    /// Copy the inputs into `dst`, one `Vec` per channel, converting them to `f64`.
+    ///
+    /// Only the first `min(input_count(), dst.len())` channels are copied.
+    pub fn widen_into(&self, dst: &mut [Vec<f64>]) {
+        for (input, dst) in self.inputs.iter().zip(dst) {
+            let input = unsafe { slice::from_raw_parts(*input, self.samples) };
+            dst.clear();
+            dst.extend(input.iter().map(|&sample| f64::from(sample)));
+        }
+    }
+
+    /// Write `src`, one `Vec` per channel, to the outputs, rounding to `f32`.
+    ///
+    /// Only the first `min(output_count(), src.len())` channels are written, and at most
+    /// `samples()` samples of each.
+    pub fn narrow_from(&mut self, src: &[Vec<f64>]) {
+        let samples = self.samples;
+        for (output, src) in self.outputs.iter_mut().zip(src) {
+            let output = unsafe { slice::from_raw_parts_mut(*output, samples) };
+            for (out, &sample) in output.iter_mut().zip(src) {
+                *out = sample as f32;
+            }
+        }
+    }
+}
+
+/// Conversion to and from plugin-owned `f32` buffers, for plugins which process in single precision
+/// when the host provides double precision buffers.
+///
+/// Narrowing to `f32` rounds to the nearest value (about 7 significant digits, far beyond audible
+/// resolution), and writing back is exact. Scratch buffers are resized like for
+/// `AudioBuffer<f32>`.
+impl<'a> AudioBuffer<'a, f64> {
+    /// Copy the inputs into `dst`, one `Vec` per channel, rounding them to `f32`.
+    ///
+    /// Only the first `min(input_count(), dst.len())` channels are copied.
+    pub fn narrow_into(&self, dst: &mut [Vec<f32>]) {
+        for (input, dst) in self.inputs.iter().zip(dst) {
+            let input = unsafe { slice::from_raw_parts(*input, self.samples) };
+            dst.clear();
+            dst.extend(input.iter().map(|&sample| sample as f32));
+        }
+    }
+
+    /// Write `src`, one `Vec` per channel, to the outputs, converting them to `f64`.
+    ///
+    /// Only the first `min(output_count(), src.len())` channels are written, and at most
+    /// `samples()` samples of each.
+    pub fn widen_from(&mut self, src: &[Vec<f32>]) {
+        let samples = self.samples;
+        for (output, src) in self.outputs.iter_mut().zip(src) {
+            let output = unsafe { slice::from_raw_parts_mut(*output, samples) };
+            for (out, &sample) in output.iter_mut().zip(src) {
+                *out = f64::from(sample);
+            }
+        }
+    }
+}
+
+/// Deinterleaved copy of an interleaved sample array. Created by `AudioBuffer::from_interleaved`.
+pub struct InterleavedBuffer<'a, T: 'a + Float> {
+    data: &'a mut [T],
+    inputs: Vec<Vec<T>>,
+    outputs: Vec<Vec<T>>,
+    input_ptrs: Vec<*const T>,
+    output_ptrs: Vec<*mut T>,
+}
+
+impl<'a, T: 'a + Float> InterleavedBuffer<'a, T> {
+    /// Get an `AudioBuffer` over the deinterleaved inputs and outputs.
+    ///
+    /// The outputs start out silent and keep their contents between calls.
+    pub fn buffer(&mut self) -> AudioBuffer<'_, T> {
+        self.input_ptrs.clear();
+        self.input_ptrs.extend(self.inputs.iter().map(|input| input.as_ptr()));
+        self.output_ptrs.clear();
+        self.output_ptrs
+            .extend(self.outputs.iter_mut().map(|output| output.as_mut_ptr()));
+
+        unsafe {
+            AudioBuffer::from_raw(
+                self.inputs.len(),
+                self.outputs.len(),
+                self.input_ptrs.as_ptr(),
+                self.output_ptrs.as_mut_ptr(),
+                self.data.len() / self.inputs.len(),
+            )
+        }
+    }
+
+    /// Interleave the outputs back into the original sample array.
+    pub fn write_outputs(&mut self) {
+        let channels = self.outputs.len();
+        for (channel, output) in self.outputs.iter().enumerate() {
+            for (sample, value) in self.data.iter_mut().skip(channel).step_by(channels).zip(output) {
+                *sample = *value;
+            }
+        }
+    }
+}
+
+#[inline]
+// `usize::is_multiple_of` needs Rust 1.87.
+#[allow(clippy::manual_is_multiple_of)]
+fn is_aligned<T>(ptr: *const T) -> bool {
+    (ptr as usize) % SIMD_ALIGNMENT == 0
+}
+
+/// Scratch storage aligned to `SIMD_ALIGNMENT` bytes, used by `AudioBuffer::with_aligned_output`.
+///
+/// Create this outside of the audio thread with the maximum block size, e.g. in
+/// `Plugin::set_block_size`.
+pub struct AlignedScratch<T: Float> {
+    data: Vec<T>,
+    offset: usize,
+    samples: usize,
+}
+
+impl<T: Float> AlignedScratch<T> {
+    /// Allocate aligned storage for `samples` samples.
+    pub fn new(samples: usize) -> AlignedScratch<T> {
+        // Enough extra samples to start at an aligned address within the allocation.
+        let padding = SIMD_ALIGNMENT / mem::size_of::<T>();
+        let data = vec![T::zero(); samples + padding];
+        // Samples are aligned to their size, so the distance to the next aligned address is a
+        // whole number of samples.
+        let misalignment = data.as_ptr() as usize % SIMD_ALIGNMENT;
+        let offset = (SIMD_ALIGNMENT - misalignment) % SIMD_ALIGNMENT / mem::size_of::<T>();
+        AlignedScratch { data, offset, samples }
+    }
+
+    /// Get an aligned slice of `samples` samples, reallocating if more are needed than were
+    /// allocated in `new`.
+    pub fn get(&mut self, samples: usize) -> &mut [T] {
+        if samples > self.samples {
+            *self = AlignedScratch::new(samples);
+        }
+        &mut self.data[self.offset..self.offset + samples]
+    }
+}
+
+/// Return zero for denormal and NaN samples, and the sample itself otherwise.
+///
+/// See `AudioBuffer::flush_denormals`.
+#[inline]
+pub fn flush_denormal<T: Float>(sample: T) -> T {
+    if sample.is_nan() || sample.is_subnormal() {
+        T::zero()
+    } else {
+        sample
+    }
+}
+
+/// Multiply `samples` by a linear ramp from `from` at the first to exactly `to` at the last sample.
+fn ramp_gain<T: Float>(samples: &mut [T], from: T, to: T) {
+    let last = samples.len().saturating_sub(1);
+    let step = if last == 0 {
+        T::zero()
+    } else {
+        (to - from) / T::from(last).unwrap()
+    };
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let gain = if i == last {
+            to
+        } else {
+            from + step * T::from(i).unwrap()
+        };
+        *sample = *sample * gain;
+    }
+}
+
+/// Single channel view of an `AudioBuffer`. Created by `AudioBuffer::as_mono`.
+pub struct MonoAdapter<'a, T: 'a + Float> {
+    inputs: Inputs<'a, T>,
+    outputs: Outputs<'a, T>,
+}
+
+impl<'a, T: 'a + Float> MonoAdapter<'a, T> {
+    /// The number of samples in the underlying buffer.
+    #[inline]
+    pub fn samples(&self) -> usize {
+        self.inputs.samples
+    }
+
+    /// Get the mono input sample at index `i`, averaged over all input channels.
+    #[inline]
+    pub fn input(&self, i: usize) -> T {
+        if self.inputs.is_empty() {
+            return T::zero();
+        }
+
+        let sum = (0..self.inputs.len()).fold(T::zero(), |acc, channel| acc + self.inputs.get(channel)[i]);
+        sum / T::from(self.inputs.len()).unwrap()
+    }
+
+    /// Write the mono output sample at index `i` to all output channels.
+    #[inline]
+    pub fn set_output(&mut self, i: usize, value: T) {
+        for channel in 0..self.outputs.len() {
+            self.outputs.get_mut(channel)[i] = value;
+        }
+    }
+}
+
+/// Stereo view of an `AudioBuffer`. Created by `AudioBuffer::as_stereo`.
+pub struct StereoAdapter<'a, T: 'a + Float> {
+    inputs: Inputs<'a, T>,
+    outputs: Outputs<'a, T>,
+}
+
+impl<'a, T: 'a + Float> StereoAdapter<'a, T> {
+    /// The number of samples in the underlying buffer.
+    #[inline]
+    pub fn samples(&self) -> usize {
+        self.inputs.samples
+    }
+
+    /// Get the `(left, right)` input samples at index `i`.
+    #[inline]
+    pub fn input(&self, i: usize) -> (T, T) {
+        match self.inputs.len() {
+            0 => (T::zero(), T::zero()),
+            1 => {
+                let sample = self.inputs.get(0)[i];
+                (sample, sample)
+            }
+            _ => (self.inputs.get(0)[i], self.inputs.get(1)[i]),
+        }
+    }
+
+    /// Write the `(left, right)` output samples at index `i`.
+    #[inline]
+    pub fn set_output(&mut self, i: usize, (left, right): (T, T)) {
+        match self.outputs.len() {
+            0 => {}
+            1 => self.outputs.get_mut(0)[i] = (left + right) / T::from(2).unwrap(),
+            _ => {
+                self.outputs.get_mut(0)[i] = left;
+                self.outputs.get_mut(1)[i] = right;
+            }
+        }
+    }
+}
+
+/// View of an `AudioBuffer` with the channels of a speaker arrangement, accessed by speaker.
+/// Created by `AudioBuffer::as_multichannel`.
+///
+/// Speakers that aren't part of the arrangement, or whose channel index is beyond the channel
+/// count of the buffer, are `None`.
+pub struct MultiChannelBuffer<'a, T: 'a + Float> {
+    speakers: &'static [Speaker],
+    inputs: Inputs<'a, T>,
+    outputs: Outputs<'a, T>,
+}
+
+impl<'a, T: 'a + Float> MultiChannelBuffer<'a, T> {
+    /// The number of samples in the underlying buffer.
+    #[inline]
+    pub fn samples(&self) -> usize {
+        self.inputs.samples
+    }
+
+    /// The speakers of the arrangement, in channel order.
+    #[inline]
+    pub fn speakers(&self) -> &'static [Speaker] {
+        self.speakers
+    }
+
+    /// The channel index of `speaker` in the arrangement.
+    #[inline]
+    pub fn index(&self, speaker: Speaker) -> Option<usize> {
+        self.speakers.iter().position(|&s| s == speaker)
+    }
+
+    /// The input channel of `speaker`.
+    #[inline]
+    pub fn input(&self, speaker: Speaker) -> Option<&[T]> {
+        let index = self.index(speaker).filter(|&index| index < self.inputs.len())?;
+        Some(self.inputs.get(index))
+    }
+
+    /// The output channel of `speaker`.
+    #[inline]
+    pub fn output(&mut self, speaker: Speaker) -> Option<&mut [T]> {
+        let index = self.index(speaker).filter(|&index| index < self.outputs.len())?;
+        Some(self.outputs.get_mut(index))
+    }
+
+    /// The input and output channel of `speaker`, if the buffer has both.
+    #[inline]
+    pub fn channel(&mut self, speaker: Speaker) -> Option<(&[T], &mut [T])> {
+        let index = self
+            .index(speaker)
+            .filter(|&index| index < self.inputs.len() && index < self.outputs.len())?;
+        Some((self.inputs.get(index), self.outputs.get_mut(index)))
+    }
+}
+
+/// Iterator over main inputs, aux inputs and outputs. Created by `AudioBuffer::zip_with_aux`.
+pub struct AuxIterator<'a, T: 'a + Float> {
+    main: Inputs<'a, T>,
+    aux: Inputs<'a, T>,
+    outputs: Outputs<'a, T>,
+    index: usize,
+}
+
+impl<'a, T: 'a + Float> Iterator for AuxIterator<'a, T> {
+    type Item = (&'a [T], Option<&'a [T]>, &'a mut [T]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.main.len().min(self.outputs.len()) {
+            return None;
+        }
+        let aux = if self.aux.is_empty() {
+            None
+        } else {
+            Some(self.aux.get(self.index % self.aux.len()))
+        };
+        let item = (self.main.get(self.index), aux, self.outputs.get_mut(self.index));
+        self.index += 1;
+        Some(item)
+    }
+}
+
+/// Iterator over the frames of an `AudioBuffer`. Created by `AudioBuffer::frames`.
+pub struct Frames<'a, T: 'a + Float> {
+    inputs: &'a [*const T],
+    outputs: &'a [*mut T],
+    index: usize,
+    samples: usize,
+}
+
+impl<'a, T: 'a + Float> Iterator for Frames<'a, T> {
+    type Item = Frame<'a, T>;
+
+    fn next(&mut self) -> Option<Frame<'a, T>> {
+        if self.index >= self.samples {
+            return None;
+        }
+        let frame = Frame {
+            inputs: self.inputs,
+            outputs: self.outputs,
+            index: self.index,
+        };
+        self.index += 1;
+        Some(frame)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.samples - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: 'a + Float> ExactSizeIterator for Frames<'a, T> {}
+
+/// One sample of every input and output channel of an `AudioBuffer`.
+pub struct Frame<'a, T: 'a + Float> {
+    inputs: &'a [*const T],
+    outputs: &'a [*mut T],
+    index: usize,
+}
+
+impl<'a, T: 'a + Float> Frame<'a, T> {
+    /// The index of this frame in the buffer.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The number of input channels.
+    #[inline]
+    pub fn input_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// The number of output channels.
+    #[inline]
+    pub fn output_count(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Get the sample of input `channel`.
+    ///
+    /// # Panics
+    /// This function will panic if `channel` is out of range.
+    #[inline]
+    pub fn input(&self, channel: usize) -> T {
+        // Every frame only accesses its own index, which is within the buffer.
+        unsafe { *self.inputs[channel].add(self.index) }
+    }
+
+    /// Get the sample of output `channel`.
+    ///
+    /// # Panics
+    /// This function will panic if `channel` is out of range.
+    #[inline]
+    pub fn output(&self, channel: usize) -> T {
+        unsafe { *self.outputs[channel].add(self.index) }
+    }
+
+    /// Set the sample of output `channel`.
+    ///
+    /// # Panics
+    /// This function will panic if `channel` is out of range.
+    #[inline]
+    pub fn set_output(&mut self, channel: usize, value: T) {
+        unsafe { *self.outputs[channel].add(self.index) = value }
+    }
 }
 
 /// Iterator over pairs of buffers of input channels and output channels.
@@ -120,6 +855,28 @@ where
     }
 }
 
+/// Iterator over the index, input and output buffer of each channel. See
+/// `AudioBuffer::zip_channels`.
+pub struct ChannelIterator<'a, 'b, T>
+where
+    T: 'a + Float,
+    'a: 'b,
+{
+    inner: AudioBufferIterator<'a, 'b, T>,
+}
+
+impl<'a, 'b, T> Iterator for ChannelIterator<'a, 'b, T>
+where
+    T: 'b + Float,
+{
+    type Item = (usize, &'b [T], &'b mut [T]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.inner.index;
+        self.inner.next().map(|(input, output)| (index, input, output))
+    }
+}
+
 use std::ops::{Index, IndexMut};
 
 /// Wrapper type to access the buffers for the input channels of an `AudioBuffer` in a safe way.
@@ -448,8 +1205,52 @@ impl SendEventBuffer {
         plugin.process_events(self.events());
     }
 
+    /// Stores events to be sent to the host later in the same block with `flush_events`.
+    ///
+    /// This is useful for MIDI thru plugins (humanizers, transposers, etc.), which receive events
+    /// in `process_events` but may only send them to the host from within `process`. The
+    /// `delta_frames` of each event are preserved. If more events are given than the buffer has
+    /// capacity for, the remaining events are dropped.
+    ///
+    /// Note that a `SysExEvent` only stores a pointer to its payload, so the payload must stay
+    /// valid until `flush_events` is called. The host's events are only valid during
+    /// `process_events`, so forwarding incoming SysEx events requires copying their payloads into
+    /// storage owned by the plugin first. The example below drops them instead.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use vst::plugin::{Info, Plugin, HostCallback};
+    /// # use vst::buffer::{AudioBuffer, SendEventBuffer};
+    /// # use vst::api;
+    /// # use vst::event::Event;
+    /// # struct ExamplePlugin { host: HostCallback, send_buffer: SendEventBuffer }
+    /// # impl Plugin for ExamplePlugin {
+    /// #     fn new(host: HostCallback) -> Self { Self { host, send_buffer: Default::default() } }
+    /// #
+    /// #     fn get_info(&self) -> Info { Default::default() }
+    /// #
+    /// fn process_events(&mut self, events: &api::Events) {
+    ///     // Transpose all incoming notes up by an octave. Other events are dropped, as SysEx
+    ///     // payloads would have to be copied to outlive this call.
+    ///     self.send_buffer.store_events(events.events().filter_map(|e| match e {
+    ///         Event::Midi(mut e) => {
+    ///             if e.data[0] & 0xE0 == 0x80 {
+    ///                 e.data[1] = e.data[1].saturating_add(12).min(127);
+    ///             }
+    ///             Some(Event::Midi(e))
+    ///         }
+    ///         _ => None,
+    ///     }));
+    /// }
+    ///
+    /// fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
+    ///     // ...
+    ///     self.send_buffer.flush_events(&mut self.host);
+    /// }
+    /// # }
+    /// ```
     #[inline(always)]
-    fn store_events<T: IntoIterator<Item = U>, U: WriteIntoPlaceholder>(&mut self, events: T) {
+    pub fn store_events<T: IntoIterator<Item = U>, U: WriteIntoPlaceholder>(&mut self, events: T) {
         #[allow(clippy::suspicious_map)]
         let count = events
             .into_iter()
@@ -459,8 +1260,17 @@ impl SendEventBuffer {
         self.set_num_events(count);
     }
 
+    /// Sends the events stored with `store_events` to the host and clears the buffer.
+    ///
+    /// This should only be called within `Plugin::process` or `Plugin::process_f64`.
+    #[inline(always)]
+    pub fn flush_events(&mut self, host: &mut dyn Host) {
+        host.process_events(self.events());
+        self.set_num_events(0);
+    }
+
     #[inline(always)]
-    fn events(&self) -> &api::Events {
+    pub(crate) fn events(&self) -> &api::Events {
         #[allow(clippy::cast_ptr_alignment)]
         unsafe {
             &*(self.buf.as_ptr() as *const api::Events)
@@ -485,7 +1295,12 @@ impl SendEventBuffer {
 
 #[cfg(test)]
 mod tests {
-    use buffer::AudioBuffer;
+    use std::sync::Mutex;
+
+    use api;
+    use buffer::{AudioBuffer, SendEventBuffer};
+    use event::{Event, MidiEvent};
+    use host::Host;
 
     /// Size of buffers used in tests.
     const SIZE: usize = 1024;
@@ -607,4 +1422,443 @@ mod tests {
             });
         }
     }
+
+    /// Test that output clamping and soft clipping work for both `f32` and `f64` buffers.
+    #[test]
+    fn clamp_and_soft_clip() {
+        macro_rules! test_clamp {
+            ($t:ty) => {
+                let input: Vec<$t> = vec![0.0; 4];
+                let mut output: Vec<$t> = vec![-3.0, -0.5, 0.5, 3.0];
+
+                let inputs = vec![input.as_ptr()];
+                let mut outputs = vec![output.as_mut_ptr()];
+                let mut buffer = unsafe { AudioBuffer::from_raw(1, 1, inputs.as_ptr(), outputs.as_mut_ptr(), 4) };
+
+                buffer.clamp_outputs(-1.0, 1.0);
+                assert_eq!(output, vec![-1.0, -0.5, 0.5, 1.0]);
+
+                let mut buffer = unsafe { AudioBuffer::from_raw(1, 1, inputs.as_ptr(), outputs.as_mut_ptr(), 4) };
+                buffer.soft_clip_outputs();
+                assert_eq!(
+                    output,
+                    vec![
+                        (-1.0 as $t).tanh(),
+                        (-0.5 as $t).tanh(),
+                        (0.5 as $t).tanh(),
+                        (1.0 as $t).tanh()
+                    ]
+                );
+                assert!(output.iter().all(|s| s.abs() < 1.0));
+            };
+        }
+
+        test_clamp!(f32);
+        test_clamp!(f64);
+    }
+
+    #[test]
+    fn flush_denormals() {
+        macro_rules! test_flush {
+            ($t:ty) => {
+                let input: Vec<$t> = vec![0.0; 6];
+                let tiny = <$t>::MIN_POSITIVE / 4.0;
+                let mut output: Vec<$t> = vec![tiny, -tiny, <$t>::NAN, <$t>::MIN_POSITIVE, -0.25, <$t>::INFINITY];
+
+                let inputs = vec![input.as_ptr()];
+                let mut outputs = vec![output.as_mut_ptr()];
+                let mut buffer = unsafe { AudioBuffer::from_raw(1, 1, inputs.as_ptr(), outputs.as_mut_ptr(), 6) };
+
+                buffer.flush_denormals();
+                assert_eq!(
+                    output,
+                    vec![0.0, 0.0, 0.0, <$t>::MIN_POSITIVE, -0.25, <$t>::INFINITY]
+                );
+            };
+        }
+
+        test_flush!(f32);
+        test_flush!(f64);
+    }
+
+    #[test]
+    fn zip_with_aux() {
+        let left = vec![1.0f32; SIZE];
+        let right = vec![2.0f32; SIZE];
+        let key = vec![0.5f32; SIZE];
+        let mut out_left = vec![0.0f32; SIZE];
+        let mut out_right = vec![0.0f32; SIZE];
+
+        let inputs = vec![left.as_ptr(), right.as_ptr(), key.as_ptr()];
+        let mut outputs = vec![out_left.as_mut_ptr(), out_right.as_mut_ptr()];
+
+        // Stereo main input with a mono key, shared by both channels.
+        let mut buffer = unsafe { AudioBuffer::from_raw(3, 2, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+        let mut channels = 0;
+        for (input, aux, output) in buffer.zip_with_aux(2) {
+            let aux = aux.unwrap();
+            for ((out, in_sample), aux_sample) in output.iter_mut().zip(input).zip(aux) {
+                *out = in_sample * aux_sample;
+            }
+            channels += 1;
+        }
+        assert_eq!(channels, 2);
+        assert_eq!(out_left, vec![0.5; SIZE]);
+        assert_eq!(out_right, vec![1.0; SIZE]);
+
+        // Without aux inputs.
+        let mut buffer = unsafe { AudioBuffer::from_raw(2, 2, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+        assert!(buffer.zip_with_aux(2).all(|(_, aux, _)| aux.is_none()));
+    }
+
+    #[test]
+    fn zip_channels() {
+        let left = vec![1.0f32; SIZE];
+        let right = vec![1.0f32; SIZE];
+        let mut out_left = vec![0.0f32; SIZE];
+        let mut out_right = vec![0.0f32; SIZE];
+        let mut out_extra = vec![0.0f32; SIZE];
+        let gains = [0.5, 0.25, 2.0];
+
+        let inputs = vec![left.as_ptr(), right.as_ptr()];
+        let mut outputs = vec![out_left.as_mut_ptr(), out_right.as_mut_ptr(), out_extra.as_mut_ptr()];
+
+        // The extra output has no input and is left out.
+        let mut buffer = unsafe { AudioBuffer::from_raw(2, 3, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+        let mut channels = Vec::new();
+        for (channel, input, output) in buffer.zip_channels() {
+            for (out, in_sample) in output.iter_mut().zip(input) {
+                *out = in_sample * gains[channel];
+            }
+            channels.push(channel);
+        }
+        assert_eq!(channels, vec![0, 1]);
+        assert_eq!(out_left, vec![0.5; SIZE]);
+        assert_eq!(out_right, vec![0.25; SIZE]);
+        assert_eq!(out_extra, vec![0.0; SIZE]);
+
+        // More inputs than outputs.
+        let mut buffer = unsafe { AudioBuffer::from_raw(2, 1, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+        assert_eq!(buffer.zip_channels().count(), 1);
+    }
+
+    #[test]
+    fn inputs_alias_outputs() {
+        let mut channels = vec![vec![0.0f32; SIZE]; 3];
+        let (first, rest) = channels.split_at_mut(1);
+        let (second, third) = rest.split_at_mut(1);
+        let (first, second, third) = (first[0].as_mut_ptr(), second[0].as_mut_ptr(), third[0].as_mut_ptr());
+
+        let inputs = vec![first as *const f32, second as *const f32];
+        let mut separate = vec![third];
+        let buffer = unsafe { AudioBuffer::from_raw(2, 1, inputs.as_ptr(), separate.as_mut_ptr(), SIZE) };
+        assert!(!buffer.inputs_alias_outputs());
+
+        // In place processing, or an output overlapping an input partially.
+        let mut in_place = vec![third, second];
+        let buffer = unsafe { AudioBuffer::from_raw(2, 2, inputs.as_ptr(), in_place.as_mut_ptr(), SIZE) };
+        assert!(buffer.inputs_alias_outputs());
+        let mut overlapping = vec![unsafe { first.add(SIZE / 2) }];
+        let buffer = unsafe { AudioBuffer::from_raw(2, 1, inputs.as_ptr(), overlapping.as_mut_ptr(), SIZE) };
+        assert!(buffer.inputs_alias_outputs());
+    }
+
+    #[test]
+    fn raw_channel_pointers() {
+        // Stands in for a C function taking `const float**` and `float**`.
+        unsafe fn gain(inputs: *const *const f32, outputs: *const *mut f32, channels: usize, samples: usize) {
+            for channel in 0..channels {
+                let input = *inputs.add(channel);
+                let output = *outputs.add(channel);
+                for i in 0..samples {
+                    *output.add(i) = *input.add(i) * 0.5;
+                }
+            }
+        }
+
+        let left = vec![1.0f32; SIZE];
+        let right = vec![2.0f32; SIZE];
+        let mut out_left = vec![0.0f32; SIZE];
+        let mut out_right = vec![0.0f32; SIZE];
+        let inputs = vec![left.as_ptr(), right.as_ptr()];
+        let mut outputs = vec![out_left.as_mut_ptr(), out_right.as_mut_ptr()];
+
+        let mut buffer = unsafe { AudioBuffer::from_raw(2, 2, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+        unsafe {
+            assert_eq!(buffer.input_ptrs(), &inputs[..]);
+            let input_ptrs = buffer.input_ptrs().as_ptr();
+            let output_ptrs = buffer.output_ptrs();
+            gain(input_ptrs, output_ptrs.as_ptr(), output_ptrs.len(), SIZE);
+        }
+        assert_eq!(out_left, vec![0.5; SIZE]);
+        assert_eq!(out_right, vec![1.0; SIZE]);
+    }
+
+    #[test]
+    fn aligned_outputs() {
+        use buffer::{AlignedScratch, SIMD_ALIGNMENT};
+
+        #[repr(C, align(16))]
+        struct Aligned([f32; SIZE + 1]);
+
+        let input = Aligned([1.0; SIZE + 1]);
+        let mut output = Aligned([2.0; SIZE + 1]);
+
+        // Offset by one sample to get deliberately misaligned pointers.
+        let inputs = vec![input.0.as_ptr(), unsafe { input.0.as_ptr().add(1) }];
+        let mut outputs = vec![output.0.as_mut_ptr(), unsafe { output.0.as_mut_ptr().add(1) }];
+        let mut buffer = unsafe { AudioBuffer::from_raw(2, 2, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+
+        assert_eq!(buffer.input_aligned(0).map(|input| input.len()), Some(SIZE));
+        assert!(buffer.input_aligned(1).is_none());
+        assert!(buffer.output_aligned(0).is_some());
+        assert!(buffer.output_aligned(1).is_none());
+
+        let mut scratch = AlignedScratch::new(SIZE / 2);
+        for channel in 0..2 {
+            buffer.with_aligned_output(channel, &mut scratch, |output| {
+                assert_eq!(output.as_ptr() as usize % SIMD_ALIGNMENT, 0);
+                assert_eq!(output.len(), SIZE);
+                for sample in output.iter_mut() {
+                    *sample += 1.0;
+                }
+            });
+        }
+
+        // Channel 0 covers samples 0.., channel 1 samples 1.. of the same storage.
+        assert_eq!(output.0[0], 3.0);
+        assert!(output.0[1..SIZE].iter().all(|&sample| sample == 4.0));
+        assert_eq!(output.0[SIZE], 3.0);
+    }
+
+    #[test]
+    fn frames() {
+        let input: Vec<f32> = (0..SIZE).map(|i| i as f32).collect();
+        let mut left = vec![0.0f32; SIZE];
+        let mut right = vec![0.0f32; SIZE];
+
+        let inputs = vec![input.as_ptr()];
+        let mut outputs = vec![left.as_mut_ptr(), right.as_mut_ptr()];
+        let mut buffer = unsafe { AudioBuffer::from_raw(1, 2, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+
+        assert_eq!(buffer.frames().len(), SIZE);
+        for mut frame in buffer.frames() {
+            assert_eq!((frame.input_count(), frame.output_count()), (1, 2));
+            // Pan hard left.
+            let sample = frame.input(0);
+            frame.set_output(0, sample);
+            frame.set_output(1, 0.0);
+            assert_eq!(frame.output(0), frame.index() as f32);
+        }
+
+        assert_eq!(left, input);
+        assert_eq!(right, vec![0.0; SIZE]);
+    }
+
+    #[test]
+    fn precision_conversion_round_trip() {
+        let input: Vec<f32> = (0..SIZE).map(|x| (x as f32 * 0.37).sin() / 3.0).collect();
+        let mut output = vec![0.0f32; SIZE];
+        let mut scratch = vec![Vec::with_capacity(SIZE)];
+
+        let inputs = vec![input.as_ptr()];
+        let mut outputs = vec![output.as_mut_ptr()];
+        let mut buffer = unsafe { AudioBuffer::from_raw(1, 1, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+        buffer.widen_into(&mut scratch);
+        assert_eq!(scratch[0].len(), SIZE);
+        buffer.narrow_from(&scratch);
+        // Widening is exact.
+        assert_eq!(output, input);
+
+        let input: Vec<f64> = (0..SIZE).map(|x| (x as f64 * 0.37).sin() / 3.0).collect();
+        let mut output = vec![0.0f64; SIZE];
+        let mut scratch = vec![Vec::new(), Vec::new()];
+
+        let inputs = vec![input.as_ptr()];
+        let mut outputs = vec![output.as_mut_ptr()];
+        let mut buffer = unsafe { AudioBuffer::from_raw(1, 1, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+        buffer.narrow_into(&mut scratch);
+        // Extra scratch channels are left alone.
+        assert!(scratch[1].is_empty());
+        buffer.widen_from(&scratch);
+        for (out, input) in output.iter().zip(&input) {
+            assert!((out - input).abs() <= f64::from(f32::EPSILON) * input.abs());
+        }
+    }
+
+    #[test]
+    fn interleaved_round_trip() {
+        let original: Vec<f32> = (0..SIZE * 2).map(|i| i as f32).collect();
+        let mut data = original.clone();
+        {
+            let mut interleaved = AudioBuffer::from_interleaved(&mut data, 2);
+            {
+                let mut buffer = interleaved.buffer();
+                assert_eq!((buffer.input_count(), buffer.output_count()), (2, 2));
+                assert_eq!(buffer.samples(), SIZE);
+
+                for (channel, (input, output)) in buffer.zip().enumerate() {
+                    for (i, (in_sample, out_sample)) in input.iter().zip(output.iter_mut()).enumerate() {
+                        assert_eq!(*in_sample, (i * 2 + channel) as f32);
+                        *out_sample = *in_sample;
+                    }
+                }
+            }
+            interleaved.write_outputs();
+        }
+        assert_eq!(data, original);
+    }
+
+    /// Test that mono and stereo views mix channels down and up as documented.
+    #[test]
+    fn mono_and_stereo_adapters() {
+        let left = vec![1.0f32; SIZE];
+        let right = vec![3.0f32; SIZE];
+        let mut out_mono = vec![0.0f32; SIZE];
+
+        // Stereo in, mono out, viewed as mono.
+        let inputs = vec![left.as_ptr(), right.as_ptr()];
+        let mut outputs = vec![out_mono.as_mut_ptr()];
+        let mut buffer = unsafe { AudioBuffer::from_raw(2, 1, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+        {
+            let mut mono = buffer.as_mono();
+            assert_eq!(mono.samples(), SIZE);
+            for i in 0..mono.samples() {
+                let sample = mono.input(i);
+                mono.set_output(i, sample);
+            }
+        }
+        assert_eq!(out_mono, vec![2.0; SIZE]);
+
+        // Stereo in, mono out, viewed as stereo.
+        let mut buffer = unsafe { AudioBuffer::from_raw(2, 1, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+        {
+            let mut stereo = buffer.as_stereo();
+            for i in 0..stereo.samples() {
+                let (l, r) = stereo.input(i);
+                assert_eq!((l, r), (1.0, 3.0));
+                stereo.set_output(i, (l * 2.0, r * 2.0));
+            }
+        }
+        assert_eq!(out_mono, vec![4.0; SIZE]);
+
+        // Mono in, stereo out, viewed as stereo.
+        let mut out_left = vec![0.0f32; SIZE];
+        let mut out_right = vec![0.0f32; SIZE];
+        let inputs = vec![right.as_ptr()];
+        let mut outputs = vec![out_left.as_mut_ptr(), out_right.as_mut_ptr()];
+        let mut buffer = unsafe { AudioBuffer::from_raw(1, 2, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE) };
+        {
+            let mut stereo = buffer.as_stereo();
+            for i in 0..stereo.samples() {
+                let (l, r) = stereo.input(i);
+                stereo.set_output(i, (l, -r));
+            }
+        }
+        assert_eq!(out_left, vec![3.0; SIZE]);
+        assert_eq!(out_right, vec![-3.0; SIZE]);
+    }
+
+    /// Host which records the midi data and delta frames of all events it receives.
+    #[derive(Default)]
+    struct RecordingHost {
+        received: Mutex<Vec<([u8; 3], i32)>>,
+    }
+
+    impl Host for RecordingHost {
+        fn process_events(&self, events: &api::Events) {
+            let mut received = self.received.lock().unwrap();
+            for event in events.events() {
+                if let Event::Midi(ev) = event {
+                    received.push((ev.data, ev.delta_frames));
+                }
+            }
+        }
+    }
+
+    fn note_on(note: u8, delta_frames: i32) -> MidiEvent {
+        MidiEvent {
+            data: [0x90, note, 100],
+            delta_frames,
+            live: false,
+            note_length: None,
+            note_offset: None,
+            detune: 0,
+            note_off_velocity: 0,
+        }
+    }
+
+    /// Test that events stored in `process_events` and flushed in `process` reach the host with
+    /// their modifications and original `delta_frames`.
+    #[test]
+    fn midi_thru() {
+        let mut host = RecordingHost::default();
+        let mut send_buffer = SendEventBuffer::new(16);
+
+        let incoming = vec![note_on(60, 0), note_on(64, 17), note_on(67, 511)];
+        send_buffer.store_events(incoming.iter().map(|e| {
+            let mut e = *e;
+            e.data[1] += 12;
+            e
+        }));
+        send_buffer.flush_events(&mut host);
+
+        assert_eq!(
+            *host.received.lock().unwrap(),
+            vec![([0x90, 72, 100], 0), ([0x90, 76, 100], 17), ([0x90, 79, 100], 511)]
+        );
+
+        // Flushing again must not resend the same events.
+        send_buffer.flush_events(&mut host);
+        assert_eq!(host.received.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn multichannel() {
+        use api::SpeakerArrangementType;
+        use channels::Speaker;
+
+        let inputs: Vec<Vec<f32>> = (0..6).map(|channel| vec![channel as f32; SIZE]).collect();
+        let mut outputs = vec![vec![0.0f32; SIZE]; 6];
+        let input_ptrs: Vec<*const f32> = inputs.iter().map(|i| i.as_ptr()).collect();
+        let mut output_ptrs: Vec<*mut f32> = outputs.iter_mut().map(|o| o.as_mut_ptr()).collect();
+        let mut buffer = unsafe { AudioBuffer::from_raw(6, 6, input_ptrs.as_ptr(), output_ptrs.as_mut_ptr(), SIZE) };
+
+        assert!(buffer.as_multichannel(SpeakerArrangementType::Custom).is_none());
+        {
+            let mut surround = buffer.as_multichannel(SpeakerArrangementType::Surround51).unwrap();
+            assert_eq!(surround.speakers().len(), 6);
+            assert_eq!(surround.input(Speaker::Lfe).unwrap()[0], 3.0);
+            assert!(surround.input(Speaker::CenterSurround).is_none());
+
+            let (input, output) = surround.channel(Speaker::Center).unwrap();
+            output.copy_from_slice(input);
+        }
+        assert_eq!(outputs[2], vec![2.0; SIZE]);
+
+        // Speakers beyond the channel count of the buffer aren't available.
+        let mut buffer = unsafe { AudioBuffer::from_raw(2, 2, input_ptrs.as_ptr(), output_ptrs.as_mut_ptr(), SIZE) };
+        let mut surround = buffer.as_multichannel(SpeakerArrangementType::Surround51).unwrap();
+        assert!(surround.output(Speaker::Right).is_some());
+        assert!(surround.output(Speaker::Center).is_none());
+    }
+
+    #[test]
+    fn gain_ramp() {
+        let inputs = vec![vec![0.0f32; 5]; 2];
+        let mut outputs = vec![vec![2.0f32; 5]; 2];
+        let input_ptrs: Vec<*const f32> = inputs.iter().map(|i| i.as_ptr()).collect();
+        let mut output_ptrs: Vec<*mut f32> = outputs.iter_mut().map(|o| o.as_mut_ptr()).collect();
+        let mut buffer = unsafe { AudioBuffer::from_raw(2, 2, input_ptrs.as_ptr(), output_ptrs.as_mut_ptr(), 5) };
+
+        buffer.apply_gain_smoothed(1.0, 0.0);
+        buffer.apply_gain_smoothed_channel(1, 0.5, 1.5);
+        assert_eq!(outputs[0], vec![2.0, 1.5, 1.0, 0.5, 0.0]);
+        assert_eq!(outputs[1], vec![1.0, 1.125, 1.0, 0.625, 0.0]);
+
+        // A single sample gets the target gain.
+        let mut sample = [2.0f32];
+        super::ramp_gain(&mut sample, 0.0, 0.5);
+        assert_eq!(sample, [1.0]);
+    }
 }
@@ -0,0 +1,242 @@
+//! Soft bypass handled by the library for plugins setting `Info::soft_bypass`.
+//!
+//! While bypassed, the outputs play the inputs delayed by the plugin latency, so the dry signal
+//! stays aligned with the rest of the mix. Switching between the plugin output and the dry signal
+//! crossfades over `BYPASS_FADE_SAMPLES` samples to avoid clicks.
+//!
+//! The buffers are allocated with `Bypass::prepare` when the plugin is resumed, so the audio
+//! thread never allocates. Blocks which don't fit them are processed without bypass.
+
+use num_traits::Float;
+
+use buffer::AudioBuffer;
+
+/// Length of the crossfade when the host switches the soft bypass on or off, in samples.
+pub(crate) const BYPASS_FADE_SAMPLES: usize = 256;
+
+pub(crate) struct Bypass<T: Float> {
+    /// Bypass state requested by the host.
+    bypassed: bool,
+    /// Progress of the crossfade, from 0 (plugin output) to `BYPASS_FADE_SAMPLES` (dry signal).
+    fade: usize,
+    /// The plugin latency the delay lines were prepared for.
+    latency: usize,
+    /// Delay line for every input, `latency` samples long.
+    delay: Vec<Vec<T>>,
+    /// Write position in the delay lines.
+    position: usize,
+    /// Delayed dry signal for every output of the current block, preallocated for the maximum
+    /// block size.
+    dry: Vec<Vec<T>>,
+    /// Whether the current block didn't fit the prepared buffers.
+    skipped: bool,
+}
+
+impl<T: Float> Default for Bypass<T> {
+    fn default() -> Self {
+        Bypass {
+            bypassed: false,
+            fade: 0,
+            latency: 0,
+            delay: Vec::new(),
+            position: 0,
+            dry: Vec::new(),
+            skipped: false,
+        }
+    }
+}
+
+impl<T: Float> Bypass<T> {
+    /// Allocate the buffers for `inputs` and `outputs` channels, blocks of up to `max_samples`
+    /// samples and a plugin latency of `latency` samples, e.g. when the plugin is resumed.
+    pub fn prepare(&mut self, inputs: usize, outputs: usize, max_samples: usize, latency: usize) {
+        self.latency = latency;
+        self.delay = vec![vec![T::zero(); latency]; inputs];
+        self.position = 0;
+        self.dry = vec![vec![T::zero(); max_samples]; outputs];
+        self.clear();
+    }
+
+    /// Clear the delay lines and skip any crossfade in progress, e.g. when the plugin is
+    /// suspended.
+    pub fn clear(&mut self) {
+        for line in &mut self.delay {
+            for sample in line.iter_mut() {
+                *sample = T::zero();
+            }
+        }
+        self.fade = if self.bypassed { BYPASS_FADE_SAMPLES } else { 0 };
+    }
+
+    /// Whether only the dry signal is heard.
+    fn is_dry(&self) -> bool {
+        self.bypassed && self.fade == BYPASS_FADE_SAMPLES
+    }
+
+    /// Whether only the plugin output is heard.
+    fn is_wet(&self) -> bool {
+        !self.bypassed && self.fade == 0
+    }
+
+    /// Feed the inputs of `buffer` into the delay lines, before the plugin overwrites them when
+    /// processing in place. `bypassed` is the state requested by the host. Returns whether the
+    /// plugin needs to process the block.
+    ///
+    /// Blocks with other channel counts or more samples than prepared for are passed to the
+    /// plugin without bypass.
+    pub fn begin(&mut self, buffer: &mut AudioBuffer<T>, bypassed: bool) -> bool {
+        self.bypassed = bypassed;
+        let samples = buffer.samples();
+        let (inputs, outputs) = buffer.split();
+        self.skipped = inputs.len() != self.delay.len()
+            || outputs.len() != self.dry.len()
+            || self.dry.first().map(|dry| samples > dry.len()).unwrap_or(false);
+        if self.skipped {
+            if !self.is_wet() {
+                debug!("Block of {} samples doesn't fit the soft bypass buffers", samples);
+            }
+            return true;
+        }
+        let latency = self.latency;
+        let keep_dry = !self.is_wet();
+
+        for (channel, line) in self.delay.iter_mut().enumerate() {
+            let input = inputs.get(channel);
+            let mut position = self.position;
+            for (i, &sample) in input.iter().enumerate() {
+                let delayed = if latency == 0 {
+                    sample
+                } else {
+                    let delayed = line[position];
+                    line[position] = sample;
+                    position = (position + 1) % latency;
+                    delayed
+                };
+                if keep_dry && channel < self.dry.len() {
+                    self.dry[channel][i] = delayed;
+                }
+            }
+        }
+        if latency > 0 {
+            self.position = (self.position + samples) % latency;
+        }
+        if keep_dry {
+            for dry in self.dry.iter_mut().skip(inputs.len()) {
+                for sample in &mut dry[..samples] {
+                    *sample = T::zero();
+                }
+            }
+        }
+
+        !self.is_dry()
+    }
+
+    /// Write the dry signal to the outputs of `buffer`, crossfading with what the plugin wrote if
+    /// the bypass state changed.
+    pub fn end(&mut self, buffer: &mut AudioBuffer<T>) {
+        if self.skipped || self.is_wet() {
+            return;
+        }
+        let fade = self.fade;
+        let (_, mut outputs) = buffer.split();
+
+        for (channel, dry) in self.dry.iter().enumerate().take(outputs.len()) {
+            let output = outputs.get_mut(channel);
+            if self.is_dry() {
+                output.copy_from_slice(&dry[..output.len()]);
+                continue;
+            }
+
+            let mut position = fade;
+            for (out_sample, &dry_sample) in output.iter_mut().zip(dry) {
+                position = if self.bypassed {
+                    (position + 1).min(BYPASS_FADE_SAMPLES)
+                } else {
+                    position.saturating_sub(1)
+                };
+                let gain = T::from(position).unwrap() / T::from(BYPASS_FADE_SAMPLES).unwrap();
+                *out_sample = *out_sample * (T::one() - gain) + dry_sample * gain;
+            }
+        }
+
+        let samples = buffer.samples();
+        self.fade = if self.bypassed {
+            (fade + samples).min(BYPASS_FADE_SAMPLES)
+        } else {
+            fade.saturating_sub(samples)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::AudioBuffer;
+    use bypass::{Bypass, BYPASS_FADE_SAMPLES};
+
+    /// Process one block of a constant 1.0 input with a "plugin" writing 0.5.
+    fn process(bypass: &mut Bypass<f32>, bypassed: bool, samples: usize) -> (Vec<f32>, bool) {
+        let input = vec![1.0f32; samples];
+        let mut output = vec![0.0f32; samples];
+        let inputs = [input.as_ptr()];
+        let mut outputs = [output.as_mut_ptr()];
+        let mut buffer = unsafe { AudioBuffer::from_raw(1, 1, inputs.as_ptr(), outputs.as_mut_ptr(), samples) };
+
+        let processed = bypass.begin(&mut buffer, bypassed);
+        if processed {
+            for (_, output) in buffer.zip() {
+                for sample in output.iter_mut() {
+                    *sample = 0.5;
+                }
+            }
+        }
+        bypass.end(&mut buffer);
+        (output, processed)
+    }
+
+    #[test]
+    fn crossfade() {
+        let mut bypass = Bypass::default();
+        bypass.prepare(1, 1, BYPASS_FADE_SAMPLES, 0);
+        assert_eq!(process(&mut bypass, false, 64), (vec![0.5; 64], true));
+
+        let (output, processed) = process(&mut bypass, true, BYPASS_FADE_SAMPLES);
+        assert!(processed);
+        // The dry signal fades in smoothly.
+        assert!(output.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(output[BYPASS_FADE_SAMPLES - 1], 1.0);
+
+        // Fully bypassed, the plugin doesn't process.
+        assert_eq!(process(&mut bypass, true, 64), (vec![1.0; 64], false));
+
+        let (output, processed) = process(&mut bypass, false, 64);
+        assert!(processed && output.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn latency_matched() {
+        let mut bypass = Bypass::default();
+        bypass.prepare(1, 1, 32, 48);
+        process(&mut bypass, true, 0);
+        bypass.clear();
+
+        // The dry signal is delayed by the latency, across blocks.
+        let (output, _) = process(&mut bypass, true, 32);
+        assert_eq!(output, vec![0.0; 32]);
+        let (output, _) = process(&mut bypass, true, 32);
+        assert_eq!(&output[..16], &[0.0; 16]);
+        assert_eq!(&output[16..], &[1.0; 16]);
+    }
+
+    #[test]
+    fn oversized_block() {
+        let mut bypass = Bypass::default();
+        bypass.prepare(1, 1, 32, 0);
+        process(&mut bypass, true, 0);
+        bypass.clear();
+        assert_eq!(process(&mut bypass, true, 32), (vec![1.0; 32], false));
+
+        // Blocks larger than prepared for are processed without bypass instead of allocating.
+        assert_eq!(process(&mut bypass, true, 64), (vec![0.5; 64], true));
+        assert_eq!(process(&mut bypass, true, 32), (vec![1.0; 32], false));
+    }
+}
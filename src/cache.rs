@@ -1,20 +1,149 @@
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
 
+use bypass::Bypass;
 use editor::Editor;
-use plugin::{Info, PluginParameters};
+use plugin::{CanDo, HostCallback, Info, PluginParameters};
+use process::EventStore;
+#[cfg(feature = "profiling")]
+use profiling::StatsRecorder;
+
+/// Block size assumed until the host sets one, for allocating the soft bypass buffers.
+const DEFAULT_BLOCK_SIZE: usize = 4096;
 
 pub(crate) struct PluginCache {
-    pub info: Info,
+    /// The `Info` queried from the plugin, replaced when it was invalidated.
+    pub info: InfoCell,
+    /// Set by `HostCallback::invalidate_info` to have `info` re-queried on the next state change.
+    pub info_changed: AtomicBool,
+    /// The latency reported to the host, mirrored in `AEffect::initialDelay`. Set from any thread
+    /// through `HostCallback::set_latency`, so both are only accessed atomically. The
+    /// `initial_delay` of `info` is the latency when it was last replaced.
+    pub latency: AtomicI32,
+    /// Maximum block size set by the host.
+    pub block_size: AtomicUsize,
     pub params: Arc<dyn PluginParameters>,
     pub editor: Option<Box<dyn Editor>>,
+    /// Whether `editor` was opened by the host and not closed since.
+    pub editor_open: AtomicBool,
+    pub host: HostCallback,
+    /// Events for the next `Plugin::process_ctx` call.
+    pub events: EventStore,
+    /// Answers of `Plugin::can_do` for the standard capability strings. Hosts don't always call
+    /// the dispatcher from the same thread, so it has its own lock.
+    pub can_do: Mutex<CanDoCache>,
+    /// Soft bypass state requested by the host, for `Info::soft_bypass`.
+    pub bypassed: AtomicBool,
+    /// Soft bypass processing state for each precision, allocated when the plugin is resumed.
+    pub bypass: Bypass<f32>,
+    pub bypass_f64: Bypass<f64>,
+    /// Timing of the process calls.
+    #[cfg(feature = "profiling")]
+    pub stats: StatsRecorder,
 }
 
 impl PluginCache {
-    pub fn new(info: &Info, params: Arc<dyn PluginParameters>, editor: Option<Box<dyn Editor>>) -> Self {
+    pub fn new(
+        info: Info,
+        params: Arc<dyn PluginParameters>,
+        editor: Option<Box<dyn Editor>>,
+        host: HostCallback,
+    ) -> Self {
         Self {
-            info: info.clone(),
+            latency: AtomicI32::new(info.initial_delay),
+            block_size: AtomicUsize::new(DEFAULT_BLOCK_SIZE),
+            info: InfoCell::new(info),
+            info_changed: AtomicBool::new(false),
             params,
             editor,
+            editor_open: AtomicBool::new(false),
+            host,
+            events: EventStore::default(),
+            can_do: Mutex::new(CanDoCache::default()),
+            bypassed: AtomicBool::new(false),
+            bypass: Bypass::default(),
+            bypass_f64: Bypass::default(),
+            #[cfg(feature = "profiling")]
+            stats: StatsRecorder::default(),
+        }
+    }
+}
+
+/// An `Info` which can be replaced while other threads read it.
+///
+/// Replaced `Info`s are kept until the cell is dropped, so references returned by `get` stay
+/// valid. The plugin info rarely changes, so they don't add up.
+pub(crate) struct InfoCell {
+    current: AtomicPtr<Info>,
+    // Boxed, as moving a retired `Info` would invalidate references to it.
+    #[allow(clippy::vec_box)]
+    retired: Mutex<Vec<Box<Info>>>,
+}
+
+impl InfoCell {
+    fn new(info: Info) -> InfoCell {
+        InfoCell {
+            current: AtomicPtr::new(Box::into_raw(Box::new(info))),
+            retired: Mutex::new(Vec::new()),
         }
     }
+
+    /// The current `Info`.
+    pub fn get(&self) -> &Info {
+        unsafe { &*self.current.load(Ordering::Acquire) }
+    }
+
+    /// Publish `info` as the current `Info`. Threads reading the previous one keep reading it.
+    pub fn replace(&self, info: Info) {
+        let old = self.current.swap(Box::into_raw(Box::new(info)), Ordering::AcqRel);
+        let old = unsafe { Box::from_raw(old) };
+        self.retired.lock().unwrap_or_else(PoisonError::into_inner).push(old);
+    }
+}
+
+impl Drop for InfoCell {
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(*self.current.get_mut()) });
+    }
+}
+
+/// Answers of `Plugin::can_do` as returned to the host, for every `CanDo` variant except
+/// `Channels` and `Other`. Cleared when the plugin info is invalidated.
+#[derive(Default)]
+pub(crate) struct CanDoCache {
+    answers: [Option<isize>; 12],
+}
+
+impl CanDoCache {
+    fn index(can_do: &CanDo) -> Option<usize> {
+        use plugin::CanDo::*;
+
+        Some(match *can_do {
+            SendEvents => 0,
+            SendMidiEvent => 1,
+            ReceiveEvents => 2,
+            ReceiveMidiEvent => 3,
+            ReceiveTimeInfo => 4,
+            Offline => 5,
+            MidiProgramNames => 6,
+            Bypass => 7,
+            NoRealTime => 8,
+            ReceiveSysExEvent => 9,
+            MidiSingleNoteTuningChange => 10,
+            MidiKeyBasedInstrumentControl => 11,
+            Channels { .. } | Other(_) => return None,
+        })
+    }
+
+    /// The cached answer for `can_do`, or the answer of `query` if there is none yet.
+    pub fn get_or_query<F: FnOnce(CanDo) -> isize>(&mut self, can_do: CanDo, query: F) -> isize {
+        match CanDoCache::index(&can_do) {
+            Some(index) => *self.answers[index].get_or_insert_with(|| query(can_do)),
+            None => query(can_do),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.answers = Default::default();
+    }
 }
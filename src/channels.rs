@@ -178,6 +178,169 @@ pub enum SurroundConfig {
     S10_2,
 }
 
+/// The role of a single speaker in a surround arrangement, see `Speaker::layout`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Speaker {
+    /// Mono (M).
+    Mono,
+    /// Left (L).
+    Left,
+    /// Right (R).
+    Right,
+    /// Center (C).
+    Center,
+    /// Low frequency effects (Lfe).
+    Lfe,
+    /// Left surround (Ls).
+    LeftSurround,
+    /// Right surround (Rs).
+    RightSurround,
+    /// Left of center (Lc).
+    LeftCenter,
+    /// Right of center (Rc).
+    RightCenter,
+    /// Center surround (Cs), also called surround (S).
+    CenterSurround,
+    /// Side left (Sl).
+    SideLeft,
+    /// Side right (Sr).
+    SideRight,
+    /// Top front left (Tfl).
+    TopFrontLeft,
+    /// Top front center (Tfc).
+    TopFrontCenter,
+    /// Top front right (Tfr).
+    TopFrontRight,
+    /// Top rear left (Trl).
+    TopRearLeft,
+    /// Top rear right (Trr).
+    TopRearRight,
+    /// Second low frequency effects channel (Lfe2).
+    Lfe2,
+}
+
+impl Speaker {
+    /// The speakers of `arrangement` in channel order, e.g. L R C Lfe Ls Rs for 5.1.
+    ///
+    /// Returns `None` for `Custom` and `Empty` arrangements, which don't define any speakers.
+    pub fn layout(arrangement: api::SpeakerArrangementType) -> Option<&'static [Speaker]> {
+        use self::Speaker::*;
+        use api::SpeakerArrangementType as Raw;
+
+        Some(match arrangement {
+            Raw::Custom | Raw::Empty => return None,
+            Raw::Mono => &[Mono],
+
+            Raw::Stereo => &[Left, Right],
+            Raw::StereoSurround => &[LeftSurround, RightSurround],
+            Raw::StereoCenter => &[LeftCenter, RightCenter],
+            Raw::StereoSide => &[SideLeft, SideRight],
+            Raw::StereoCLfe => &[Center, Lfe],
+
+            Raw::Cinema30 => &[Left, Right, Center],
+            Raw::Music30 => &[Left, Right, CenterSurround],
+            Raw::Cinema31 => &[Left, Right, Center, Lfe],
+            Raw::Music31 => &[Left, Right, Lfe, CenterSurround],
+            Raw::Cinema40 => &[Left, Right, Center, CenterSurround],
+            Raw::Music40 => &[Left, Right, LeftSurround, RightSurround],
+            Raw::Cinema41 => &[Left, Right, Center, Lfe, CenterSurround],
+            Raw::Music41 => &[Left, Right, Lfe, LeftSurround, RightSurround],
+            Raw::Surround50 => &[Left, Right, Center, LeftSurround, RightSurround],
+            Raw::Surround51 => &[Left, Right, Center, Lfe, LeftSurround, RightSurround],
+            Raw::Cinema60 => &[Left, Right, Center, LeftSurround, RightSurround, CenterSurround],
+            Raw::Music60 => &[Left, Right, LeftSurround, RightSurround, SideLeft, SideRight],
+            Raw::Cinema61 => &[Left, Right, Center, Lfe, LeftSurround, RightSurround, CenterSurround],
+            Raw::Music61 => &[Left, Right, Lfe, LeftSurround, RightSurround, SideLeft, SideRight],
+            Raw::Cinema70 => &[
+                Left,
+                Right,
+                Center,
+                LeftSurround,
+                RightSurround,
+                LeftCenter,
+                RightCenter,
+            ],
+            Raw::Music70 => &[Left, Right, Center, LeftSurround, RightSurround, SideLeft, SideRight],
+            Raw::Cinema71 => &[
+                Left,
+                Right,
+                Center,
+                Lfe,
+                LeftSurround,
+                RightSurround,
+                LeftCenter,
+                RightCenter,
+            ],
+            Raw::Music71 => &[
+                Left,
+                Right,
+                Center,
+                Lfe,
+                LeftSurround,
+                RightSurround,
+                SideLeft,
+                SideRight,
+            ],
+            Raw::Cinema80 => &[
+                Left,
+                Right,
+                Center,
+                LeftSurround,
+                RightSurround,
+                LeftCenter,
+                RightCenter,
+                CenterSurround,
+            ],
+            Raw::Music80 => &[
+                Left,
+                Right,
+                Center,
+                LeftSurround,
+                RightSurround,
+                CenterSurround,
+                SideLeft,
+                SideRight,
+            ],
+            Raw::Cinema81 => &[
+                Left,
+                Right,
+                Center,
+                Lfe,
+                LeftSurround,
+                RightSurround,
+                LeftCenter,
+                RightCenter,
+                CenterSurround,
+            ],
+            Raw::Music81 => &[
+                Left,
+                Right,
+                Center,
+                Lfe,
+                LeftSurround,
+                RightSurround,
+                CenterSurround,
+                SideLeft,
+                SideRight,
+            ],
+            Raw::Surround102 => &[
+                Left,
+                Right,
+                Center,
+                Lfe,
+                LeftSurround,
+                RightSurround,
+                TopFrontLeft,
+                TopFrontCenter,
+                TopFrontRight,
+                TopRearLeft,
+                TopRearRight,
+                Lfe2,
+            ],
+        })
+    }
+}
+
 /// Type representing how a channel is used. Only useful for some hosts.
 pub enum SpeakerArrangementType {
     /// Custom arrangement not specified to host.
@@ -208,6 +371,21 @@ impl SpeakerArrangementType {
         }
     }
 
+    /// The arrangement of channel `index` out of `count` channels paired up as stereo pairs:
+    /// channels 0 and 1 form the first pair, 2 and 3 the second and so on. A trailing unpaired
+    /// channel is `Custom`.
+    pub fn stereo_pair(index: i32, count: i32) -> SpeakerArrangementType {
+        use self::SpeakerArrangementType::{Custom, Stereo};
+
+        if index % 2 == 1 {
+            Stereo(StereoConfig::L_R, StereoChannel::Right)
+        } else if index + 1 < count {
+            Stereo(StereoConfig::L_R, StereoChannel::Left)
+        } else {
+            Custom
+        }
+    }
+
     /// Determine whether this channel is the left speaker in a stereo pair.
     pub fn is_left_stereo(&self) -> bool {
         if let SpeakerArrangementType::Stereo(_, StereoChannel::Left) = *self {
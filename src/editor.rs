@@ -3,18 +3,35 @@
 use std::os::raw::c_void;
 
 /// Implemented by plugin editors.
+///
+/// All methods are called on the host's UI thread, the thread that opens the editor. Editor state
+/// must not be touched from `Plugin::process` on the processing thread; share values through
+/// `PluginParameters` or atomics instead. Debug builds log a warning when the host calls editor
+/// methods on a processing thread, or processes audio on the thread that opened the editor.
 #[allow(unused_variables)]
 pub trait Editor {
     /// Get the size of the editor window.
+    ///
+    /// This is also queried by the host before the editor is opened. Resizable editors can use a
+    /// [`util::EditorSize`](../util/struct.EditorSize.html) to reopen at the last size the user chose.
     fn size(&self) -> (i32, i32);
 
     /// Get the coordinates of the editor window.
     fn position(&self) -> (i32, i32);
 
-    /// Editor idle call. Called by host.
+    /// Editor idle call. Called by host, only while the editor is open.
     fn idle(&mut self) {}
 
+    /// Called after the host changed the preset to `preset`, while the editor is open, so it can
+    /// show the new parameter values. Only called if `Info::refresh_on_preset_change` is set.
+    fn preset_changed(&mut self, preset: i32) {}
+
     /// Called when the editor window is closed.
+    ///
+    /// Only called if the editor was successfully opened, and at most once per `open`: repeated
+    /// close requests from the host (e.g. during teardown) are not passed on, so native window
+    /// resources can be released here without guarding against a second call. The editor can be
+    /// opened again afterwards.
     fn close(&mut self) {}
 
     /// Called when the editor window is opened.
@@ -31,16 +48,41 @@ pub trait Editor {
     /// Return `true` if the window opened successfully, `false` otherwise.
     fn open(&mut self, parent: *mut c_void) -> bool;
 
+    /// Return the native handle of the window created in `open`, if any.
+    ///
+    /// The return value of the open call is only specified as non-zero on success, but on
+    /// Windows some hosts treat it as the `HWND` of the plugin's child window for focus
+    /// handling. On Windows, a handle returned here is passed to the host after a successful
+    /// `open`. On other platforms, and if this returns `None` (the default), 1 is returned.
+    fn window_handle(&self) -> Option<*mut c_void> {
+        None
+    }
+
     /// Return whether the window is currently open.
+    ///
+    /// The library tracks this as well, see
+    /// [`HostCallback::is_editor_open`](../plugin/struct.HostCallback.html#method.is_editor_open).
     fn is_open(&mut self) -> bool;
 
     /// Set the knob mode for this editor (if supported by host).
     ///
+    /// This is the knob interaction style the user configured in the host. GUI plugins should
+    /// respect it for consistency with the host and other plugins, e.g. through `gui::KnobDrag`
+    /// with the `gui` feature.
+    ///
     /// Return `true` if the knob mode was set.
     fn set_knob_mode(&mut self, mode: KnobMode) -> bool {
         false
     }
 
+    /// Return `true` if the editor needs keyboard input, e.g. for text fields.
+    ///
+    /// Unless this returns `true`, hosts may capture key events and not forward them to the
+    /// editor. Default is `false`.
+    fn wants_keyboard_focus(&self) -> bool {
+        false
+    }
+
     /// Receive key up event. Return `true` if the key was used.
     fn key_up(&mut self, keycode: KeyCode) -> bool {
         false
@@ -79,14 +121,16 @@ pub struct KeyCode {
 
 /// Allows host to set how a parameter knob works.
 #[repr(usize)]
-#[derive(Copy, Clone, Debug)]
-#[allow(missing_docs)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum KnobMode {
+    /// The knob follows the mouse position in a circle around its center.
     Circular,
+    /// Circular motion relative to where the knob was clicked, without jumping.
     CircularRelative,
+    /// Vertical (linear) mouse movement changes the knob value.
     Linear,
 }
-impl_clike!(KnobMode);
+impl_clike!(KnobMode; last = Linear);
 
 /// Platform independent key codes.
 #[allow(missing_docs)]
@@ -152,4 +196,4 @@ pub enum Key {
     Alt,
     Equals,
 }
-impl_clike!(Key);
+impl_clike!(Key; last = Equals);
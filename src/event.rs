@@ -27,6 +27,17 @@ pub enum Event<'a> {
     Deprecated(api::Event),
 }
 
+impl<'a> Event<'a> {
+    /// Number of samples into the current processing block that this event occurs on.
+    pub fn delta_frames(&self) -> i32 {
+        match *self {
+            Event::Midi(ref event) => event.delta_frames,
+            Event::SysEx(ref event) => event.delta_frames,
+            Event::Deprecated(ref event) => event.delta_frames,
+        }
+    }
+}
+
 /// A midi event.
 ///
 /// These are sent to the plugin before `Plugin::processing()` or `Plugin::processing_f64()` is
@@ -61,6 +72,73 @@ pub struct MidiEvent {
     pub note_off_velocity: u8,
 }
 
+impl MidiEvent {
+    /// Create a channel message with the upper nibble of `status` (e.g. `0x90` for note on) and
+    /// two data bytes, occurring `delta_frames` samples into the block.
+    ///
+    /// `channel` is clamped to `0..=15` and the data bytes to `0..=127`. The other fields are
+    /// set to their neutral values and can be changed with struct update syntax:
+    ///
+    /// ```
+    /// # use vst::event::MidiEvent;
+    /// let event = MidiEvent {
+    ///     live: true,
+    ///     ..MidiEvent::note_on(0, 60, 100, 0)
+    /// };
+    /// # assert!(event.live);
+    /// ```
+    pub fn new(status: u8, channel: u8, data1: u8, data2: u8, delta_frames: i32) -> MidiEvent {
+        MidiEvent {
+            data: [(status & 0xf0) | channel.min(15), data1.min(127), data2.min(127)],
+            delta_frames,
+            live: false,
+            note_length: None,
+            note_offset: None,
+            detune: 0,
+            note_off_velocity: 0,
+        }
+    }
+
+    /// Create a note on message.
+    pub fn note_on(channel: u8, note: u8, velocity: u8, delta_frames: i32) -> MidiEvent {
+        MidiEvent::new(0x90, channel, note, velocity, delta_frames)
+    }
+
+    /// Create a note off message.
+    pub fn note_off(channel: u8, note: u8, velocity: u8, delta_frames: i32) -> MidiEvent {
+        MidiEvent {
+            note_off_velocity: velocity.min(127),
+            ..MidiEvent::new(0x80, channel, note, velocity, delta_frames)
+        }
+    }
+
+    /// Create a polyphonic aftertouch message.
+    pub fn poly_pressure(channel: u8, note: u8, pressure: u8, delta_frames: i32) -> MidiEvent {
+        MidiEvent::new(0xa0, channel, note, pressure, delta_frames)
+    }
+
+    /// Create a control change message.
+    pub fn control_change(channel: u8, controller: u8, value: u8, delta_frames: i32) -> MidiEvent {
+        MidiEvent::new(0xb0, channel, controller, value, delta_frames)
+    }
+
+    /// Create a program change message.
+    pub fn program_change(channel: u8, program: u8, delta_frames: i32) -> MidiEvent {
+        MidiEvent::new(0xc0, channel, program, 0, delta_frames)
+    }
+
+    /// Create a channel aftertouch message.
+    pub fn channel_pressure(channel: u8, pressure: u8, delta_frames: i32) -> MidiEvent {
+        MidiEvent::new(0xd0, channel, pressure, 0, delta_frames)
+    }
+
+    /// Create a pitch bend message. `bend` is clamped to `-8192..=8191`, with 0 being the center.
+    pub fn pitch_bend(channel: u8, bend: i16, delta_frames: i32) -> MidiEvent {
+        let value = (bend.clamp(-8192, 8191) + 8192) as u16;
+        MidiEvent::new(0xe0, channel, (value & 0x7f) as u8, (value >> 7) as u8, delta_frames)
+    }
+}
+
 /// A system exclusive event.
 ///
 /// This is just a block of data and it is up to the plugin to interpret this. Generally used
@@ -124,3 +202,58 @@ impl<'a> From<api::Event> for Event<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use buffer::SendEventBuffer;
+    use event::{Event, MidiEvent};
+
+    #[test]
+    fn midi_event_constructors() {
+        assert_eq!(MidiEvent::note_on(1, 60, 100, 12).data, [0x91, 60, 100]);
+        assert_eq!(MidiEvent::note_on(1, 60, 100, 12).delta_frames, 12);
+        assert_eq!(MidiEvent::note_off(0, 60, 64, 0).note_off_velocity, 64);
+        assert_eq!(MidiEvent::control_change(15, 7, 127, 0).data, [0xbf, 7, 127]);
+        assert_eq!(MidiEvent::program_change(2, 5, 0).data, [0xc2, 5, 0]);
+
+        // Out of range values are clamped.
+        assert_eq!(MidiEvent::note_on(16, 200, 255, 0).data, [0x9f, 127, 127]);
+
+        assert_eq!(MidiEvent::pitch_bend(0, 0, 0).data, [0xe0, 0x00, 0x40]);
+        assert_eq!(MidiEvent::pitch_bend(0, i16::MIN, 0).data, [0xe0, 0x00, 0x00]);
+        assert_eq!(MidiEvent::pitch_bend(0, i16::MAX, 0).data, [0xe0, 0x7f, 0x7f]);
+    }
+
+    #[test]
+    fn sorted_by_frame() {
+        let mut buffer = SendEventBuffer::new(8);
+        buffer.store_events(vec![
+            MidiEvent::note_on(0, 60, 100, 64),
+            MidiEvent::note_on(0, 62, 100, 0),
+            MidiEvent::note_off(0, 60, 0, 64),
+            MidiEvent::note_on(0, 64, 100, 32),
+            MidiEvent::note_on(0, 65, 100, 64),
+        ]);
+
+        let notes: Vec<(i32, u8, u8)> = buffer
+            .events()
+            .sorted_by_frame()
+            .iter()
+            .map(|event| match *event {
+                Event::Midi(ref e) => (event.delta_frames(), e.data[0], e.data[1]),
+                _ => panic!("unexpected event"),
+            })
+            .collect();
+        // Events on the same frame keep their order.
+        assert_eq!(
+            notes,
+            vec![
+                (0, 0x90, 62),
+                (32, 0x90, 64),
+                (64, 0x90, 60),
+                (64, 0x80, 60),
+                (64, 0x90, 65)
+            ]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Helpers for custom editor GUIs, enabled with the `gui` feature.
+//!
+//! These implement interactions that the host expects to work the same in every plugin, so GUIs
+//! don't need to reimplement the math.
+
+use editor::KnobMode;
+
+/// Angle covered by a knob from its minimum to its maximum value, in degrees. The range is
+/// centered at the top, with the gap at the bottom.
+pub const KNOB_SWEEP_DEGREES: f32 = 300.0;
+
+/// Vertical mouse movement in pixels for the full value range in `KnobMode::Linear`, unless set
+/// through `KnobDrag::with_range`.
+pub const DEFAULT_LINEAR_RANGE: f32 = 200.0;
+
+/// A mouse drag on a knob, mapping mouse positions to parameter values according to the
+/// `KnobMode` set by the host through `Editor::set_knob_mode`.
+///
+/// Create it when the mouse button is pressed on the knob, and call [`drag`](#method.drag) for
+/// every mouse move until it is released. Positions are in pixels with `y` growing downwards,
+/// values between 0.0 and 1.0.
+///
+/// ```
+/// use vst::editor::KnobMode;
+/// use vst::gui::KnobDrag;
+///
+/// let mut drag = KnobDrag::new(KnobMode::Linear, (50.0, 50.0), (50.0, 50.0), 0.5);
+/// // Moving up by 50 pixels increases the value by a quarter of the range.
+/// assert_eq!(drag.drag((50.0, 0.0)), 0.75);
+/// ```
+#[derive(Clone, Debug)]
+pub struct KnobDrag {
+    mode: KnobMode,
+    center: (f32, f32),
+    last: (f32, f32),
+    value: f32,
+    range: f32,
+}
+
+impl KnobDrag {
+    /// Start dragging the knob centered at `center`, with the mouse pressed at `position` while
+    /// the parameter was at `value`.
+    pub fn new(mode: KnobMode, center: (f32, f32), position: (f32, f32), value: f32) -> KnobDrag {
+        KnobDrag {
+            mode,
+            center,
+            last: position,
+            value: value.clamp(0.0, 1.0),
+            range: DEFAULT_LINEAR_RANGE,
+        }
+    }
+
+    /// Set the vertical mouse movement in pixels for the full value range in `KnobMode::Linear`.
+    pub fn with_range(mut self, pixels: f32) -> KnobDrag {
+        self.range = pixels;
+        self
+    }
+
+    /// The parameter value after the mouse moved to `position`.
+    ///
+    /// With `KnobMode::Circular`, the value jumps to the angle of the mouse around the center.
+    /// With `CircularRelative`, the angle the mouse moved around the center is added to the value,
+    /// and with `Linear`, the vertical distance moved. The value is clamped to 0.0 - 1.0.
+    pub fn drag(&mut self, position: (f32, f32)) -> f32 {
+        let value = match self.mode {
+            KnobMode::Circular if position != self.center => self.angle(position) / KNOB_SWEEP_DEGREES + 0.5,
+            KnobMode::CircularRelative if position != self.center && self.last != self.center => {
+                let mut delta = self.angle(position) - self.angle(self.last);
+                if delta > 180.0 {
+                    delta -= 360.0;
+                } else if delta < -180.0 {
+                    delta += 360.0;
+                }
+                self.value + delta / KNOB_SWEEP_DEGREES
+            }
+            KnobMode::Linear => self.value + (self.last.1 - position.1) / self.range,
+            // The angle is undefined at the center.
+            _ => self.value,
+        };
+        self.last = position;
+        self.value = value.clamp(0.0, 1.0);
+        self.value
+    }
+
+    /// Clockwise angle of `position` around the center in degrees, 0 at the top.
+    fn angle(&self, position: (f32, f32)) -> f32 {
+        let x = position.0 - self.center.0;
+        let y = self.center.1 - position.1;
+        x.atan2(y).to_degrees()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use editor::KnobMode;
+    use gui::KnobDrag;
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!((value - expected).abs() < 1e-5, "{} != {}", value, expected);
+    }
+
+    #[test]
+    fn knob_modes() {
+        let center = (100.0, 100.0);
+
+        // Clicking the right side of the knob jumps to 90 degrees clockwise.
+        let mut circular = KnobDrag::new(KnobMode::Circular, center, (150.0, 100.0), 0.0);
+        assert_close(circular.drag((150.0, 100.0)), 0.8);
+        assert_close(circular.drag((100.0, 50.0)), 0.5);
+        // The gap at the bottom clamps to the nearest end.
+        assert_close(circular.drag((90.0, 150.0)), 0.0);
+
+        // Relative to the start, and continuing across the left side of the knob.
+        let mut relative = KnobDrag::new(KnobMode::CircularRelative, center, (100.0, 50.0), 0.2);
+        assert_close(relative.drag((150.0, 100.0)), 0.5);
+        assert_close(relative.drag((100.0, 50.0)), 0.2);
+        assert_close(relative.drag((50.0, 100.0)), 0.0);
+        assert_close(relative.drag((50.0, 110.0)), 0.0);
+
+        let mut linear = KnobDrag::new(KnobMode::Linear, center, (0.0, 0.0), 0.5).with_range(100.0);
+        assert_close(linear.drag((40.0, -25.0)), 0.75);
+        assert_close(linear.drag((40.0, 200.0)), 0.0);
+        assert_close(linear.drag((40.0, 190.0)), 0.1);
+    }
+}
@@ -8,20 +8,60 @@ use std::error::Error;
 use std::ffi::CString;
 use std::mem::MaybeUninit;
 use std::os::raw::c_void;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::{fmt, ptr, slice};
 
 use api::consts::*;
-use api::{self, AEffect, PluginFlags, PluginMain, Supported, TimeInfo};
+use api::{
+    self, AEffect, AutomationState, PluginFlags, PluginMain, SpeakerArrangementType, Supported, TimeInfo, TimeInfoFlags,
+};
 use buffer::AudioBuffer;
 use channels::ChannelInfo;
-use editor::{Editor, Rect};
+use editor::{Editor, KnobMode, Rect};
 use interfaces;
+use parameter::ParameterInfo;
 use plugin::{self, Category, HostCallback, Info, Plugin, PluginParameters};
 
+#[cfg(feature = "presonus")]
+pub mod presonus;
+#[cfg(feature = "reaper")]
+pub mod reaper;
+
+/// Common feature strings for `Host::can_do`.
+pub mod can_do {
+    /// The host can receive events from the plugin.
+    pub const SEND_EVENTS: &str = "sendVstEvents";
+    /// The host can receive MIDI events from the plugin.
+    pub const SEND_MIDI_EVENT: &str = "sendVstMidiEvent";
+    /// The host can provide time info.
+    pub const SEND_TIME_INFO: &str = "sendVstTimeInfo";
+    /// The host can send events to the plugin.
+    pub const RECEIVE_EVENTS: &str = "receiveVstEvents";
+    /// The host can send MIDI events to the plugin.
+    pub const RECEIVE_MIDI_EVENT: &str = "receiveVstMidiEvent";
+    /// The host will inform the plugin about changed connections.
+    pub const REPORT_CONNECTION_CHANGES: &str = "reportConnectionChanges";
+    /// The host handles changed inputs, outputs or latency (`OpCode::IOChanged`).
+    pub const ACCEPT_IO_CHANGES: &str = "acceptIOChanges";
+    /// The host can resize the editor window.
+    pub const SIZE_WINDOW: &str = "sizeWindow";
+    /// The host supports offline processing.
+    pub const OFFLINE: &str = "offline";
+    /// The host provides a file selector.
+    pub const OPEN_FILE_SELECTOR: &str = "openFileSelector";
+    /// The host can close its file selector.
+    pub const CLOSE_FILE_SELECTOR: &str = "closeFileSelector";
+    /// The host calls `Plugin::start_process` and `Plugin::stop_process`.
+    pub const START_STOP_PROCESS: &str = "startStopProcess";
+    /// The host supports shell plugins.
+    pub const SHELL_CATEGORY: &str = "shellCategory";
+    /// The host respects the realtime flag of MIDI events sent by the plugin.
+    pub const SEND_MIDI_EVENT_FLAG_IS_REALTIME: &str = "sendVstMidiEventFlagIsRealtime";
+}
+
 #[repr(usize)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[doc(hidden)]
 pub enum OpCode {
     /// [index]: parameter index
@@ -54,8 +94,10 @@ pub enum OpCode {
     _TempoAt,
     /// Deprecated.
     _GetNumAutomatableParameters,
-    /// Deprecated.
-    _GetParameterQuantization,
+    /// [value]: parameter index (-1 for all parameters).
+    /// [return]: integer value representing +1.0, or 1 if full single float precision is
+    ///           maintained in automation.
+    GetParameterQuantization,
 
     /// Notifies the host that the input/output setup has changed. This can allow the host to check
     /// numInputs/numOutputs or call `getSpeakerArrangement()`.
@@ -87,7 +129,7 @@ pub enum OpCode {
 
     /// [return]: the current process level, see `VstProcessLevels`
     GetCurrentProcessLevel,
-    /// [return]: the current automation state, see `VstAutomationStates`
+    /// [return]: the current automation state, see `api::AutomationState`
     GetAutomationState,
 
     /// The plugin is ready to begin offline processing.
@@ -116,7 +158,9 @@ pub enum OpCode {
     /// Deprecated.
     _SetOutputSampleRate,
     /// Deprecated.
-    _GetOutputSpeakerArrangement,
+    /// [return]: `*mut SpeakerArrangement` of the channels the plugin's outputs are routed into,
+    /// or 0 if unknown.
+    GetOutputSpeakerArrangement,
 
     /// Get the vendor string.
     /// [ptr]: `char*` for vendor string, limited to `MAX_VENDOR_STR_LEN`.
@@ -142,8 +186,10 @@ pub enum OpCode {
 
     /// Deprecated.
     _OpenWindow,
-    /// Deprecated.
-    _CloseWindow,
+    /// Deprecated. Ask the host to close the plugin's editor window.
+    /// [ptr]: window to close, null for the plugin's editor.
+    /// [return]: 1 if the editor will be closed.
+    CloseWindow,
 
     /// Get the current directory.
     /// [return]: `FSSpec` on OS X, `char*` otherwise
@@ -177,14 +223,14 @@ pub enum OpCode {
 
     /// Deprecated.
     _EditFile,
-    /// Deprecated.
+    /// Get the native path of the bank or project currently being loaded.
     /// [ptr]: char[2048] or sizeof (FSSpec).
     /// [return]: 1 if supported.
-    _GetChunkFile,
+    GetChunkFile,
     /// Deprecated.
     _GetInputSpeakerArrangement,
 }
-impl_clike!(OpCode);
+impl_clike!(OpCode; last = _GetInputSpeakerArrangement);
 
 /// Implemented by all VST hosts.
 #[allow(unused_variables)]
@@ -227,17 +273,102 @@ pub trait Host {
         None
     }
 
+    /// Get the current tempo in BPM, or `None` if the host doesn't provide it.
+    ///
+    /// This requests only the tempo through `get_time_info`, for plugins which don't need the
+    /// rest of the time information.
+    fn get_tempo(&self) -> Option<f64> {
+        let time_info = self.get_time_info(TimeInfoFlags::TEMPO_VALID.bits())?;
+        let flags = TimeInfoFlags::from_bits_truncate(time_info.flags);
+        if flags.contains(TimeInfoFlags::TEMPO_VALID) && time_info.tempo.is_finite() && time_info.tempo > 0.0 {
+            Some(time_info.tempo)
+        } else {
+            None
+        }
+    }
+
+    /// Get the current sample rate, or 0 if unknown.
+    ///
+    /// Many plugins ask for this during initialization, before the sample rate is set.
+    fn get_sample_rate(&self) -> isize {
+        0
+    }
+
     /// Get block size.
     fn get_block_size(&self) -> isize {
         0
     }
 
+    /// Vendor specific handling.
+    ///
+    /// Used by some hosts to expose extensions, e.g. the REAPER extensions in
+    /// [`host::reaper`](reaper/index.html) (with the `reaper` feature). Messages sent to the plugin
+    /// by Studio One are handled in [`host::presonus`](presonus/index.html) (with the `presonus`
+    /// feature).
+    fn vendor_specific(&self, index: i32, value: isize, ptr: *mut c_void, opt: f32) -> isize {
+        0
+    }
+
+    /// Get the automation state of the host, e.g. whether it is playing back automation.
+    ///
+    /// See `util::ParameterWriter` for keeping the editor from fighting the host's automation.
+    fn get_automation_state(&self) -> AutomationState {
+        AutomationState::Unsupported
+    }
+
+    /// Get the automation resolution of the host.
+    ///
+    /// Returns the integer value which represents a parameter value of +1.0, or 1 if full single
+    /// float precision is maintained in automation. Returns -1 if unknown.
+    fn get_parameter_quantization(&self) -> i32 {
+        -1
+    }
+
+    /// Get the speaker arrangement the host routes the plugin's outputs into, as the arrangement
+    /// type and number of channels. Returns `None` if the host doesn't support this.
+    fn get_output_speaker_arrangement(&self) -> Option<(SpeakerArrangementType, i32)> {
+        None
+    }
+
     /// Refresh UI after the plugin's parameters changed.
     ///
     /// Note: some hosts will call some `PluginParameters` methods from within the `update_display`
     /// call, including `get_parameter`, `get_parameter_label`, `get_parameter_name`
     /// and `get_parameter_text`.
     fn update_display(&self) {}
+
+    /// Tell the host that the number of inputs or outputs, or the latency of the plugin changed.
+    ///
+    /// Returns `true` if the host handled the change. Use `HostCallback::set_latency` to change
+    /// the latency.
+    fn io_changed(&self) -> bool {
+        false
+    }
+
+    /// Check whether the host supports a feature. See `host::can_do` for common feature strings.
+    ///
+    /// Returns `Supported::Maybe` if the host doesn't know the feature.
+    fn can_do(&self, can_do: &str) -> Supported {
+        Supported::Maybe
+    }
+
+    /// Ask the host to close the plugin's editor, e.g. after showing a fatal error in it.
+    ///
+    /// This is best-effort: the underlying opcode is deprecated and many hosts ignore it. Returns
+    /// `true` if the host will close the editor (through `Editor::close`).
+    fn close_window(&self) -> bool {
+        false
+    }
+
+    /// Get the path of the bank or project file currently being loaded or saved.
+    ///
+    /// Plugins with large state (e.g. samplers) can store their data in a file next to it and
+    /// only put a reference into the chunk. Few hosts support this, so fall back to storing
+    /// everything in the chunk if this returns `None`. Only valid during
+    /// `PluginParameters::get_bank_data`, `load_bank_data` and the preset equivalents.
+    fn get_chunk_file(&self) -> Option<PathBuf> {
+        None
+    }
 }
 
 /// All possible errors that can occur when loading a VST plugin.
@@ -246,7 +377,8 @@ pub enum PluginLoadError {
     /// Could not load given path.
     InvalidPath,
 
-    /// Given path is not a VST plugin.
+    /// Given path is not a VST plugin, i.e. the library has no `VSTPluginMain` (or `main`) entry
+    /// point.
     NotAPlugin,
 
     /// Failed to create an instance of this plugin.
@@ -274,6 +406,55 @@ impl fmt::Display for PluginLoadError {
 
 impl Error for PluginLoadError {}
 
+/// Errors which can occur in `process_replacing`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProcessError {
+    /// The number of input channels doesn't match `Info::inputs` of the plugin.
+    InputCount {
+        /// Number of inputs of the plugin.
+        expected: usize,
+        /// Number of given input channels.
+        actual: usize,
+    },
+
+    /// An input channel doesn't have the requested number of samples.
+    InputLength {
+        /// Index of the input channel.
+        channel: usize,
+        /// Requested number of samples.
+        expected: usize,
+        /// Number of samples in the channel.
+        actual: usize,
+    },
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProcessError::InputCount { expected, actual } => {
+                write!(f, "The plugin has {} inputs, but {} were given", expected, actual)
+            }
+            ProcessError::InputLength {
+                channel,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Input channel {} has {} samples instead of {}",
+                channel, actual, expected
+            ),
+        }
+    }
+}
+
+impl Error for ProcessError {}
+
+/// Names of the plugin entry point, in order of preference.
+#[cfg(target_os = "macos")]
+const ENTRY_POINTS: &[&[u8]] = &[b"VSTPluginMain", b"main_macho", b"main"];
+#[cfg(not(target_os = "macos"))]
+const ENTRY_POINTS: &[&[u8]] = &[b"VSTPluginMain", b"main"];
+
 /// Wrapper for an externally loaded VST plugin.
 ///
 /// The only functionality this struct provides is loading plugins, which can be done via the
@@ -354,7 +535,8 @@ impl Editor for EditorInstance {
     fn open(&mut self, parent: *mut c_void) -> bool {
         let result = self.params.dispatch(plugin::OpCode::EditorOpen, 0, 0, parent, 0.0);
 
-        let opened = result == 1;
+        // Some plugins return their window handle instead of 1.
+        let opened = result != 0;
         if opened {
             self.is_open = true;
         }
@@ -365,6 +547,20 @@ impl Editor for EditorInstance {
     fn is_open(&mut self) -> bool {
         self.is_open
     }
+
+    fn set_knob_mode(&mut self, mode: KnobMode) -> bool {
+        self.params.dispatch(
+            plugin::OpCode::EditorSetKnobMode,
+            0,
+            mode as isize,
+            ptr::null_mut(),
+            0.0,
+        ) == 1
+    }
+
+    fn wants_keyboard_focus(&self) -> bool {
+        self.params.opcode(plugin::OpCode::KeysRequired) == 0
+    }
 }
 
 impl<T: Host> PluginLoader<T> {
@@ -416,13 +612,17 @@ impl<T: Host> PluginLoader<T> {
             Err(_) => return Err(PluginLoadError::InvalidPath),
         };
 
+        // Search the library for the VSTAPI entry point, falling back to the names used by
+        // plugins built with older SDKs.
+        let main = ENTRY_POINTS
+            .iter()
+            .filter_map(|name| unsafe { lib.get::<PluginMain>(name) }.ok().map(|main| *main))
+            .next();
+
         Ok(PluginLoader {
-            main: unsafe {
-                // Search the library for the VSTAPI entry point
-                match lib.get(b"VSTPluginMain") {
-                    Ok(s) => *s,
-                    _ => return Err(PluginLoadError::NotAPlugin),
-                }
+            main: match main {
+                Some(main) => main,
+                None => return Err(PluginLoadError::NotAPlugin),
             },
             lib: Arc::new(lib),
             host,
@@ -481,30 +681,45 @@ impl PluginInstance {
 
         unsafe {
             let effect: &AEffect = &*effect;
-            let flags = PluginFlags::from_bits_truncate(effect.flags);
+            let flags = effect.plugin_flags();
+
+            // Use a product sized buffer for the effect name as well, some plugins ignore the
+            // shorter limit.
+            let name = plug.read_string(op::GetEffectName, MAX_PRODUCT_STR_LEN);
+            let product = plug.read_string(op::GetProductName, MAX_PRODUCT_STR_LEN);
 
             plug.info = Info {
-                name: plug.read_string(op::GetProductName, MAX_PRODUCT_STR_LEN),
+                name: if name.is_empty() { product.clone() } else { name },
                 vendor: plug.read_string(op::GetVendorName, MAX_VENDOR_STR_LEN),
+                product: if product.is_empty() { None } else { Some(product) },
 
                 presets: effect.numPrograms,
                 parameters: effect.numParams,
                 inputs: effect.numInputs,
                 outputs: effect.numOutputs,
+                io_configs: Vec::new(),
 
                 midi_inputs: 0,
                 midi_outputs: 0,
+                midi_cc_parameters: Vec::new(),
 
                 unique_id: effect.uniqueId,
                 version: effect.version,
 
-                category: Category::from(plug.opcode(op::GetCategory)),
+                category: Category::checked_from(plug.opcode(op::GetCategory) as i64).unwrap_or(Category::Unknown),
 
                 initial_delay: effect.initialDelay,
 
                 preset_chunks: flags.intersects(PluginFlags::PROGRAM_CHUNKS),
                 f64_precision: flags.intersects(PluginFlags::CAN_DOUBLE_REPLACING),
                 silent_when_stopped: flags.intersects(PluginFlags::NO_SOUND_IN_STOP),
+
+                tail_samples: 0,
+
+                process_context: false,
+                refresh_on_preset_change: false,
+                soft_bypass: false,
+                uses_time_info: false,
             };
         }
 
@@ -661,6 +876,31 @@ impl Plugin for PluginInstance {
         ChannelInfo::from(unsafe { props.assume_init() })
     }
 
+    fn get_midi_key_name(&self, channel: i32, key: i32) -> Option<String> {
+        let mut key_name = api::MidiKeyName {
+            this_program_index: 0,
+            this_key_number: key,
+            key_name: [0; MAX_NAME_LEN],
+            _reserved: 0,
+            _flags: 0,
+        };
+        let ptr = &mut key_name as *mut api::MidiKeyName as *mut c_void;
+
+        if self.dispatch(plugin::OpCode::GetMidiKeyName, channel, 0, ptr, 0.0) == 0 {
+            return None;
+        }
+
+        let name: String = String::from_utf8_lossy(&key_name.key_name)
+            .chars()
+            .take_while(|c| *c != '\0')
+            .collect();
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
+
     fn get_parameter_object(&mut self) -> Arc<dyn PluginParameters> {
         Arc::clone(&self.params) as Arc<dyn PluginParameters>
     }
@@ -709,6 +949,16 @@ impl PluginParameters for PluginParametersInstance {
         self.read_string_param(plugin::OpCode::GetParameterName, index, 0, 0.0, MAX_PARAM_STR_LEN)
     }
 
+    fn get_parameter_info(&self, index: i32) -> Option<ParameterInfo> {
+        let mut props = MaybeUninit::<api::ParameterProperties>::zeroed();
+        let ptr = props.as_mut_ptr() as *mut c_void;
+
+        match self.dispatch(plugin::OpCode::GetParamInfo, index, 0, ptr, 0.0) {
+            0 => None,
+            _ => Some(ParameterInfo::from(unsafe { props.assume_init() })),
+        }
+    }
+
     fn get_parameter(&self, index: i32) -> f32 {
         unsafe { ((*self.get_effect()).getParameter)(self.get_effect(), index) }
     }
@@ -880,6 +1130,65 @@ impl<T: Float> HostBuffer<T> {
     }
 }
 
+/// Process `samples` samples of `inputs`, one `Vec` per channel, and return the outputs.
+///
+/// This is a convenience for offline processing and tests, which builds the pointer arrays of the
+/// `AudioBuffer` internally. For a `PluginInstance`, the audio is processed through the plugin's
+/// `processReplacing` function. It allocates the outputs on every call, so use a `HostBuffer` for
+/// real-time processing instead.
+///
+/// There must be exactly as many inputs as `Info::inputs` of the plugin, each with `samples`
+/// samples. One output with `samples` samples is returned for each of `Info::outputs`.
+///
+/// ```no_run
+/// # use std::path::Path;
+/// # use std::sync::{Arc, Mutex};
+/// # use vst::host::{self, Host, PluginLoader};
+/// # use vst::plugin::Plugin;
+/// # struct SampleHost;
+/// # impl Host for SampleHost {}
+/// # let host = Arc::new(Mutex::new(SampleHost));
+/// let mut loader = PluginLoader::load(Path::new("plugin.dll"), host).unwrap();
+/// let mut instance = loader.instance().unwrap();
+/// instance.init();
+/// instance.resume();
+///
+/// let inputs = vec![vec![0.5; 512]; instance.get_info().inputs as usize];
+/// let outputs = host::process_replacing(&mut instance, &inputs, 512).unwrap();
+/// ```
+pub fn process_replacing<P: Plugin + ?Sized>(
+    plugin: &mut P,
+    inputs: &[Vec<f32>],
+    samples: usize,
+) -> Result<Vec<Vec<f32>>, ProcessError> {
+    let info = plugin.get_info();
+    let expected = info.inputs.max(0) as usize;
+    if inputs.len() != expected {
+        return Err(ProcessError::InputCount {
+            expected,
+            actual: inputs.len(),
+        });
+    }
+    if let Some((channel, input)) = inputs.iter().enumerate().find(|(_, input)| input.len() != samples) {
+        return Err(ProcessError::InputLength {
+            channel,
+            expected: samples,
+            actual: input.len(),
+        });
+    }
+
+    let mut outputs = vec![vec![0.0; samples]; info.outputs.max(0) as usize];
+    let mut host_buffer = HostBuffer::new(inputs.len(), outputs.len());
+    {
+        let mut buffer = host_buffer.bind(inputs, &mut outputs);
+        // `bind` derives the length from the channels, which is 0 without any.
+        if buffer.samples() == samples {
+            plugin.process(&mut buffer);
+        }
+    }
+    Ok(outputs)
+}
+
 /// HACK: a pointer to store the host so that it can be accessed from the `callback_wrapper`
 /// function passed to the plugin.
 ///
@@ -929,7 +1238,107 @@ extern "C" fn callback_wrapper<T: Host>(
 
 #[cfg(test)]
 mod tests {
-    use host::HostBuffer;
+    use api::{TimeInfo, TimeInfoFlags};
+    use host::{self, Host, HostBuffer, OpCode, ProcessError};
+    use plugin::{HostCallback, Info, Plugin};
+
+    #[test]
+    fn get_tempo() {
+        struct TempoHost(Option<TimeInfo>);
+
+        impl Host for TempoHost {
+            fn get_time_info(&self, mask: i32) -> Option<TimeInfo> {
+                assert_eq!(mask, TimeInfoFlags::TEMPO_VALID.bits());
+                self.0
+            }
+        }
+
+        let time_info = TimeInfo {
+            tempo: 128.0,
+            flags: TimeInfoFlags::TEMPO_VALID.bits(),
+            ..Default::default()
+        };
+        assert_eq!(TempoHost(Some(time_info)).get_tempo(), Some(128.0));
+
+        // No time info, or no valid tempo in it.
+        assert_eq!(TempoHost(None).get_tempo(), None);
+        let time_info = TimeInfo { flags: 0, ..time_info };
+        assert_eq!(TempoHost(Some(time_info)).get_tempo(), None);
+        let time_info = TimeInfo {
+            tempo: 0.0,
+            flags: TimeInfoFlags::TEMPO_VALID.bits(),
+            ..Default::default()
+        };
+        assert_eq!(TempoHost(Some(time_info)).get_tempo(), None);
+    }
+
+    #[test]
+    fn process_replacing() {
+        #[derive(Default)]
+        struct TestPlugin;
+
+        impl Plugin for TestPlugin {
+            fn new(_host: HostCallback) -> Self {
+                TestPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    inputs: 2,
+                    outputs: 2,
+                    ..Default::default()
+                }
+            }
+        }
+
+        let mut plugin = TestPlugin;
+        let inputs = vec![vec![0.25; 64], vec![-0.5; 64]];
+        // The default `process` copies the inputs.
+        assert_eq!(host::process_replacing(&mut plugin, &inputs, 64), Ok(inputs.clone()));
+
+        assert_eq!(
+            host::process_replacing(&mut plugin, &inputs[..1], 64),
+            Err(ProcessError::InputCount { expected: 2, actual: 1 })
+        );
+        assert_eq!(
+            host::process_replacing(&mut plugin, &inputs, 32),
+            Err(ProcessError::InputLength {
+                channel: 0,
+                expected: 32,
+                actual: 64
+            })
+        );
+    }
+
+    #[test]
+    fn opcode_values() {
+        // The `audioMaster*` values of the VST 2.4 SDK.
+        for &(opcode, value) in &[
+            (OpCode::Automate, 0),
+            (OpCode::Version, 1),
+            (OpCode::Idle, 3),
+            (OpCode::GetTime, 7),
+            (OpCode::IOChanged, 13),
+            (OpCode::GetSampleRate, 16),
+            (OpCode::GetBlockSize, 17),
+            (OpCode::GetAutomationState, 24),
+            (OpCode::GetOutputSpeakerArrangement, 31),
+            (OpCode::GetVendorString, 32),
+            (OpCode::VendorSpecific, 35),
+            (OpCode::CanDo, 37),
+            (OpCode::UpdateDisplay, 42),
+            (OpCode::BeginEdit, 43),
+            (OpCode::EndEdit, 44),
+            (OpCode::GetChunkFile, 48),
+            (OpCode::_GetInputSpeakerArrangement, 49),
+        ] {
+            let raw: i32 = opcode.into();
+            assert_eq!(raw, value, "{:?}", opcode);
+            assert_eq!(OpCode::checked_from(i64::from(value)), Some(opcode));
+        }
+        assert_eq!(OpCode::checked_from(50), None);
+        assert_eq!(OpCode::checked_from(-1), None);
+    }
 
     #[test]
     fn host_buffer() {
@@ -955,4 +1364,23 @@ mod tests {
         assert_eq!(output_left, vec![2.0; LENGTH]);
         assert_eq!(output_right, vec![2.0; LENGTH]);
     }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    #[test]
+    fn load_library_without_entry_point() {
+        use std::path::Path;
+        use std::sync::{Arc, Mutex};
+
+        use host::{Host, PluginLoadError, PluginLoader};
+
+        struct TestHost;
+        impl Host for TestHost {}
+
+        // The C library is always available, but doesn't export a plugin entry point.
+        match PluginLoader::load(Path::new("libc.so.6"), Arc::new(Mutex::new(TestHost))) {
+            Err(PluginLoadError::NotAPlugin) => (),
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("loaded libc as a plugin"),
+        }
+    }
 }
@@ -0,0 +1,72 @@
+//! Typed handling of the host extensions of PreSonus Studio One.
+//!
+//! Studio One sends these to plugins through `Plugin::vendor_specific`, with `index` set to
+//! `VENDOR_ID`. The magic numbers are four character codes defined in the PreSonus Plug-In
+//! Extensions SDK (`ipslviewscaling.h`), which other plugin frameworks handle the same way.
+//!
+//! Only available with the `presonus` feature.
+
+use util::VendorSpecificHandlers;
+
+/// `index` of the vendor specific messages sent by Studio One, `'PreS'`.
+pub const VENDOR_ID: i32 = 0x5072_6553;
+
+/// `value` of the message setting the content scale factor of the editor, `'AeCs'`. The scale
+/// factor (e.g. 2.0 on a HiDPI display) is passed in `opt`.
+pub const SET_CONTENT_SCALE_FACTOR: isize = 0x4165_4373;
+
+/// The content scale factor set by a vendor specific message, if it is one.
+///
+/// This is for plugins handling `Plugin::vendor_specific` themselves. Studio One sends it when
+/// the editor is opened and whenever it is moved to a display with a different scale factor.
+pub fn content_scale_factor(index: i32, value: isize, opt: f32) -> Option<f32> {
+    if index == VENDOR_ID && value == SET_CONTENT_SCALE_FACTOR {
+        Some(opt)
+    } else {
+        None
+    }
+}
+
+/// Register `handler` to be called with the content scale factor set by Studio One, see
+/// `content_scale_factor`. The host is told that the message was handled.
+pub fn on_content_scale_factor<F>(handlers: &mut VendorSpecificHandlers, mut handler: F)
+where
+    F: FnMut(f32) + Send + 'static,
+{
+    handlers.register(VENDOR_ID, Some(SET_CONTENT_SCALE_FACTOR), move |_value, _ptr, opt| {
+        handler(opt);
+        1
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    use host::presonus::{self, SET_CONTENT_SCALE_FACTOR, VENDOR_ID};
+    use util::VendorSpecificHandlers;
+
+    #[test]
+    fn content_scale_factor() {
+        assert_eq!(VENDOR_ID.to_be_bytes(), *b"PreS");
+        assert_eq!((SET_CONTENT_SCALE_FACTOR as i32).to_be_bytes(), *b"AeCs");
+
+        assert_eq!(
+            presonus::content_scale_factor(VENDOR_ID, SET_CONTENT_SCALE_FACTOR, 2.0),
+            Some(2.0)
+        );
+        assert_eq!(presonus::content_scale_factor(VENDOR_ID, 0, 2.0), None);
+
+        let scale = Arc::new(AtomicU32::new(0));
+        let mut handlers = VendorSpecificHandlers::new();
+        let stored = scale.clone();
+        presonus::on_content_scale_factor(&mut handlers, move |factor| {
+            stored.store(factor.to_bits(), Ordering::Relaxed)
+        });
+        let result = handlers.dispatch(VENDOR_ID, SET_CONTENT_SCALE_FACTOR, ptr::null_mut(), 1.5);
+        assert_eq!(result, Some(1));
+        assert_eq!(f32::from_bits(scale.load(Ordering::Relaxed)), 1.5);
+    }
+}
@@ -0,0 +1,248 @@
+//! Typed wrappers for the host extensions of [REAPER](https://www.reaper.fm).
+//!
+//! REAPER exposes its extension API to plugins through special host callback calls. See the
+//! [REAPER VST extensions](https://www.reaper.fm/sdk/vst/vst_ext.php) documentation for details.
+//!
+//! These calls don't go through `Host::vendor_specific`: REAPER doesn't use the
+//! `audioMasterVendorSpecific` opcode for them, but its own `OPCODE`, and function lookups must
+//! pass a null effect pointer. Both need the raw host callback (`HostCallback::raw_callback`).
+//!
+//! Only available with the `reaper` feature.
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+
+use plugin::HostCallback;
+
+/// Size of the buffer used to receive paths from REAPER.
+const MAX_PATH_LEN: usize = 4096;
+
+/// Size of the buffer used to receive parameter names from REAPER.
+const MAX_PARAM_NAME_LEN: usize = 256;
+
+/// Host callback opcode used by REAPER for its extensions.
+pub const OPCODE: i32 = 0xdead_beef_u32 as i32;
+
+/// `index` value for retrieving a REAPER API function by name (passed in `ptr`).
+pub const GET_FUNCTION: i32 = 0xdead_f00d_u32 as i32;
+
+/// `index` value for retrieving a context pointer (see `Context`).
+pub const GET_CONTEXT: i32 = 0xdead_f00e_u32 as i32;
+
+/// Context objects that can be retrieved with `Reaper::get_context`.
+#[repr(isize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Context {
+    /// `MediaTrack*` of the track containing the plugin.
+    Track = 1,
+    /// `MediaItem_Take*` of the take containing the plugin.
+    Take = 2,
+    /// `ReaProject*` of the project containing the plugin.
+    Project = 3,
+}
+
+/// Access to the REAPER extension API from a plugin.
+#[derive(Copy, Clone)]
+pub struct Reaper {
+    host: HostCallback,
+}
+
+impl Reaper {
+    /// Wrap the `HostCallback` passed to `Plugin::new`.
+    ///
+    /// Returns `None` if the host does not provide the REAPER extension API.
+    pub fn new(host: HostCallback) -> Option<Reaper> {
+        let reaper = Reaper { host };
+        if reaper.get_function("GetPlayState").is_null() {
+            None
+        } else {
+            Some(reaper)
+        }
+    }
+
+    fn call(&self, index: i32, value: isize, ptr: *mut c_void) -> isize {
+        match self.host.raw_callback() {
+            Some(callback) => callback(self.host.raw_effect(), OPCODE, index, value, ptr, 0.0),
+            None => 0,
+        }
+    }
+
+    /// Get a pointer to the REAPER API function called `name`, or null if it doesn't exist.
+    ///
+    /// The pointer must be transmuted to the function signature found in `reaper_plugin_functions.h`.
+    pub fn get_function(&self, name: &str) -> *mut c_void {
+        let name = match CString::new(name) {
+            Ok(name) => name,
+            Err(_) => return ptr::null_mut(),
+        };
+        // REAPER expects a null effect pointer for function lookups.
+        match self.host.raw_callback() {
+            Some(callback) => callback(
+                ptr::null_mut(),
+                OPCODE,
+                GET_FUNCTION,
+                0,
+                name.as_ptr() as *mut c_void,
+                0.0,
+            ) as *mut c_void,
+            None => ptr::null_mut(),
+        }
+    }
+
+    /// Get a context pointer (e.g. the track or project containing this plugin), or null if not
+    /// available.
+    pub fn get_context(&self, context: Context) -> *mut c_void {
+        self.call(GET_CONTEXT, context as isize, ptr::null_mut()) as *mut c_void
+    }
+
+    /// Get the path of the current project, where media files are stored.
+    pub fn project_path(&self) -> Option<String> {
+        let func = self.get_function("GetProjectPath");
+        if func.is_null() {
+            return None;
+        }
+
+        let get_project_path: extern "C" fn(*mut c_char, c_int) = unsafe { ::std::mem::transmute(func) };
+        let mut buf = vec![0u8; MAX_PATH_LEN];
+        get_project_path(buf.as_mut_ptr() as *mut c_char, buf.len() as c_int);
+        Some(
+            String::from_utf8_lossy(&buf)
+                .chars()
+                .take_while(|c| *c != '\0')
+                .collect(),
+        )
+    }
+
+    /// Get the play state of the project: `&1` playing, `&2` paused, `&4` recording.
+    pub fn play_state(&self) -> Option<i32> {
+        let func = self.get_function("GetPlayState");
+        if func.is_null() {
+            return None;
+        }
+
+        let get_play_state: extern "C" fn() -> c_int = unsafe { ::std::mem::transmute(func) };
+        Some(get_play_state() as i32)
+    }
+
+    /// Find the parameter called `name` of the FX at index `fx` on the track containing this
+    /// plugin, e.g. to automate another plugin in the same chain.
+    ///
+    /// Returns `None` if the plugin isn't on a track, or there is no such FX or parameter.
+    pub fn track_fx_parameter(&self, fx: i32, name: &str) -> Option<i32> {
+        let track = self.get_context(Context::Track);
+        let get_num_params = self.get_function("TrackFX_GetNumParams");
+        let get_param_name = self.get_function("TrackFX_GetParamName");
+        if track.is_null() || get_num_params.is_null() || get_param_name.is_null() {
+            return None;
+        }
+
+        let get_num_params: extern "C" fn(*mut c_void, c_int) -> c_int =
+            unsafe { ::std::mem::transmute(get_num_params) };
+        let get_param_name: extern "C" fn(*mut c_void, c_int, c_int, *mut c_char, c_int) -> bool =
+            unsafe { ::std::mem::transmute(get_param_name) };
+        let mut buf = vec![0u8; MAX_PARAM_NAME_LEN];
+        (0..get_num_params(track, fx)).find(|&param| {
+            buf.iter_mut().for_each(|b| *b = 0);
+            get_param_name(track, fx, param, buf.as_mut_ptr() as *mut c_char, buf.len() as c_int)
+                && buf.split(|&b| b == 0).next() == Some(name.as_bytes())
+        })
+    }
+
+    /// Set the parameter called `name` of the FX at index `fx` on the track containing this
+    /// plugin to the normalized `value`, see `track_fx_parameter`.
+    ///
+    /// Returns whether the parameter was found and REAPER accepted the value.
+    pub fn set_track_fx_parameter(&self, fx: i32, name: &str, value: f64) -> bool {
+        let param = match self.track_fx_parameter(fx, name) {
+            Some(param) => param,
+            None => return false,
+        };
+        let set_param = self.get_function("TrackFX_SetParamNormalized");
+        if set_param.is_null() {
+            return false;
+        }
+
+        let set_param: extern "C" fn(*mut c_void, c_int, c_int, f64) -> bool =
+            unsafe { ::std::mem::transmute(set_param) };
+        set_param(self.get_context(Context::Track), fx, param, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+    use std::os::raw::{c_char, c_int, c_void};
+    use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+    use api::AEffect;
+    use host::reaper::{Reaper, GET_CONTEXT, GET_FUNCTION, OPCODE};
+    use plugin::HostCallback;
+
+    static SET_VALUE: AtomicU64 = AtomicU64::new(0);
+    /// Stands in for the `MediaTrack*` of the plugin.
+    static TRACK: AtomicU8 = AtomicU8::new(0);
+
+    extern "C" fn get_num_params(_track: *mut c_void, fx: c_int) -> c_int {
+        if fx == 0 {
+            2
+        } else {
+            0
+        }
+    }
+
+    extern "C" fn get_param_name(_track: *mut c_void, _fx: c_int, param: c_int, buf: *mut c_char, len: c_int) -> bool {
+        let name: &[u8] = if param == 0 { b"Cutoff\0" } else { b"Resonance\0" };
+        assert!(name.len() <= len as usize);
+        unsafe { ::std::ptr::copy_nonoverlapping(name.as_ptr() as *const c_char, buf, name.len()) };
+        true
+    }
+
+    extern "C" fn set_param_normalized(_track: *mut c_void, fx: c_int, param: c_int, value: f64) -> bool {
+        assert_eq!((fx, param), (0, 1));
+        SET_VALUE.store(value.to_bits(), Ordering::SeqCst);
+        true
+    }
+
+    extern "C" fn get_play_state() -> c_int {
+        1
+    }
+
+    extern "C" fn callback(
+        _effect: *mut AEffect,
+        opcode: i32,
+        index: i32,
+        _value: isize,
+        ptr: *mut c_void,
+        _opt: f32,
+    ) -> isize {
+        if opcode != OPCODE {
+            return 0;
+        }
+        match index {
+            GET_FUNCTION => match unsafe { CStr::from_ptr(ptr as *const c_char) }.to_bytes() {
+                b"GetPlayState" => get_play_state as *const () as isize,
+                b"TrackFX_GetNumParams" => get_num_params as *const () as isize,
+                b"TrackFX_GetParamName" => get_param_name as *const () as isize,
+                b"TrackFX_SetParamNormalized" => set_param_normalized as *const () as isize,
+                _ => 0,
+            },
+            GET_CONTEXT => &TRACK as *const AtomicU8 as isize,
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn named_parameters() {
+        let reaper = Reaper::new(HostCallback::wrap(callback, ::std::ptr::null_mut())).unwrap();
+        assert_eq!(reaper.play_state(), Some(1));
+
+        assert_eq!(reaper.track_fx_parameter(0, "Resonance"), Some(1));
+        assert_eq!(reaper.track_fx_parameter(0, "Reso"), None);
+        assert_eq!(reaper.track_fx_parameter(1, "Cutoff"), None);
+
+        assert!(reaper.set_track_fx_parameter(0, "Resonance", 0.25));
+        assert_eq!(f64::from_bits(SET_VALUE.load(Ordering::SeqCst)), 0.25);
+        assert!(!reaper.set_track_fx_parameter(0, "Drive", 0.5));
+    }
+}
@@ -4,21 +4,57 @@
 
 use std::cell::Cell;
 use std::os::raw::{c_char, c_void};
-use std::{mem, slice};
+use std::sync::atomic::Ordering;
+use std::sync::PoisonError;
+use std::{mem, ptr, slice};
 
 use api::consts::*;
-use api::{self, AEffect, TimeInfo};
+use api::{self, AEffect, TimeInfo, TimeInfoFlags};
 use buffer::AudioBuffer;
-use editor::{Key, KeyCode, KnobMode, Rect};
+use cache::PluginCache;
+use editor::{Editor, Key, KeyCode, KnobMode, Rect};
+use event::{Event, MidiEvent};
 use host::Host;
+use plugin::{Info, PluginParameters};
+use process::ProcessContext;
+use rt_check::ProcessGuard;
+use thread_check;
 
-/// Deprecated process function.
+/// Sample rates above this are ignored as bogus values sent by hosts while probing.
+const MAX_SAMPLE_RATE: f32 = 10_000_000.0;
+/// Block sizes above this are ignored as bogus values sent by hosts while probing.
+const MAX_BLOCK_SIZE: isize = 1 << 24;
+
+/// Deprecated accumulating process function.
+///
+/// Only called by hosts predating VST 2.4. The block is processed like in `process_replacing`
+/// (including `process_ctx` and soft bypass) into temporary output buffers, the result of which
+/// is then added to the outputs provided by the host.
 pub extern "C" fn process_deprecated(
-    _effect: *mut AEffect,
-    _raw_inputs: *const *const f32,
-    _raw_outputs: *mut *mut f32,
-    _samples: i32,
+    effect: *mut AEffect,
+    raw_inputs: *const *const f32,
+    raw_outputs: *mut *mut f32,
+    samples: i32,
 ) {
+    // Some hosts send empty blocks, e.g. while probing the plugin.
+    if samples <= 0 {
+        return;
+    }
+    let output_count = unsafe { (*effect).get_info() }.outputs as usize;
+
+    // Legacy path, so allocating the temporary outputs here is acceptable.
+    let mut temp_outputs = vec![vec![0.0f32; samples as usize]; output_count];
+    let mut temp_raw_outputs: Vec<*mut f32> = temp_outputs.iter_mut().map(|o| o.as_mut_ptr()).collect();
+    process_replacing(effect, raw_inputs, temp_raw_outputs.as_mut_ptr(), samples);
+
+    let samples = samples as usize;
+    let outputs = unsafe { slice::from_raw_parts(raw_outputs, output_count) };
+    for (output, temp_output) in outputs.iter().zip(&temp_outputs) {
+        let output = unsafe { slice::from_raw_parts_mut(*output, samples) };
+        for (out_sample, temp_sample) in output.iter_mut().zip(temp_output) {
+            *out_sample += *temp_sample;
+        }
+    }
 }
 
 /// VST2.4 replacing function.
@@ -28,13 +64,51 @@ pub extern "C" fn process_replacing(
     raw_outputs: *mut *mut f32,
     samples: i32,
 ) {
+    // Some hosts send empty blocks, e.g. while probing the plugin. Events received for them are
+    // passed on with the next block.
+    if samples <= 0 {
+        return;
+    }
     // Handle to the VST
     let plugin = unsafe { (*effect).get_plugin() };
-    let info = unsafe { (*effect).get_info() };
-    let (input_count, output_count) = (info.inputs as usize, info.outputs as usize);
+    let (input_count, output_count, soft_bypass, process_context, uses_time_info) = {
+        let info = unsafe { (*effect).get_info() };
+        (
+            info.inputs as usize,
+            info.outputs as usize,
+            info.soft_bypass,
+            info.process_context,
+            info.uses_time_info,
+        )
+    };
     let mut buffer =
         unsafe { AudioBuffer::from_raw(input_count, output_count, raw_inputs, raw_outputs, samples as usize) };
-    plugin.process(&mut buffer);
+    let _guard = ProcessGuard::new();
+    thread_check::check_process_thread();
+    #[cfg(feature = "profiling")]
+    let start = std::time::Instant::now();
+    let cache = unsafe { plugin_cache(effect) };
+    // Only the audio thread uses these while the plugin is resumed. Other fields of the cache are
+    // used concurrently, so no reference to the whole cache is created.
+    let bypass = unsafe { &mut *ptr::addr_of_mut!((*cache).bypass) };
+    let events = unsafe { &mut *ptr::addr_of_mut!((*cache).events) };
+    let bypassed = unsafe { &(*cache).bypassed }.load(Ordering::Relaxed);
+    if soft_bypass && !bypass.begin(&mut buffer, bypassed) {
+        events.clear();
+    } else if process_context {
+        let time_info = unsafe { context_time_info(cache, uses_time_info) };
+        plugin.process_ctx(ProcessContext::new(buffer, time_info, events));
+        events.clear();
+    } else {
+        plugin.process(&mut buffer);
+    }
+    if soft_bypass {
+        let mut buffer =
+            unsafe { AudioBuffer::from_raw(input_count, output_count, raw_inputs, raw_outputs, samples as usize) };
+        bypass.end(&mut buffer);
+    }
+    #[cfg(feature = "profiling")]
+    unsafe { &(*cache).stats }.record(start, samples as usize);
 }
 
 /// VST2.4 replacing function with `f64` values.
@@ -44,12 +118,64 @@ pub extern "C" fn process_replacing_f64(
     raw_outputs: *mut *mut f64,
     samples: i32,
 ) {
+    // Some hosts send empty blocks, e.g. while probing the plugin. Events received for them are
+    // passed on with the next block.
+    if samples <= 0 {
+        return;
+    }
     let plugin = unsafe { (*effect).get_plugin() };
-    let info = unsafe { (*effect).get_info() };
-    let (input_count, output_count) = (info.inputs as usize, info.outputs as usize);
+    let (input_count, output_count, soft_bypass, process_context, uses_time_info) = {
+        let info = unsafe { (*effect).get_info() };
+        (
+            info.inputs as usize,
+            info.outputs as usize,
+            info.soft_bypass,
+            info.process_context,
+            info.uses_time_info,
+        )
+    };
     let mut buffer =
         unsafe { AudioBuffer::from_raw(input_count, output_count, raw_inputs, raw_outputs, samples as usize) };
-    plugin.process_f64(&mut buffer);
+    let _guard = ProcessGuard::new();
+    thread_check::check_process_thread();
+    #[cfg(feature = "profiling")]
+    let start = std::time::Instant::now();
+    let cache = unsafe { plugin_cache(effect) };
+    // Only the audio thread uses these while the plugin is resumed. Other fields of the cache are
+    // used concurrently, so no reference to the whole cache is created.
+    let bypass = unsafe { &mut *ptr::addr_of_mut!((*cache).bypass_f64) };
+    let events = unsafe { &mut *ptr::addr_of_mut!((*cache).events) };
+    let bypassed = unsafe { &(*cache).bypassed }.load(Ordering::Relaxed);
+    if soft_bypass && !bypass.begin(&mut buffer, bypassed) {
+        events.clear();
+    } else if process_context {
+        let time_info = unsafe { context_time_info(cache, uses_time_info) };
+        plugin.process_ctx_f64(ProcessContext::new(buffer, time_info, events));
+        events.clear();
+    } else {
+        plugin.process_f64(&mut buffer);
+    }
+    if soft_bypass {
+        let mut buffer =
+            unsafe { AudioBuffer::from_raw(input_count, output_count, raw_inputs, raw_outputs, samples as usize) };
+        bypass.end(&mut buffer);
+    }
+    #[cfg(feature = "profiling")]
+    unsafe { &(*cache).stats }.record(start, samples as usize);
+}
+
+/// The time info passed to `Plugin::process_ctx`, only requested from the host if the plugin set
+/// `Info::uses_time_info`.
+unsafe fn context_time_info(cache: *mut PluginCache, uses_time_info: bool) -> Option<TimeInfo> {
+    if !uses_time_info {
+        return None;
+    }
+    let flags = TimeInfoFlags::PPQ_POS_VALID
+        | TimeInfoFlags::TEMPO_VALID
+        | TimeInfoFlags::BARS_VALID
+        | TimeInfoFlags::CYCLE_POS_VALID
+        | TimeInfoFlags::TIME_SIG_VALID;
+    (*cache).host.get_time_info(flags.bits())
 }
 
 /// VST2.4 set parameter function.
@@ -62,22 +188,124 @@ pub extern "C" fn get_parameter(effect: *mut AEffect, index: i32) -> f32 {
     unsafe { (*effect).get_params() }.get_parameter(index)
 }
 
-/// Copy a string into a destination buffer.
+/// Copy a string into a destination buffer of `max` bytes.
 ///
-/// String will be cut at `max` characters.
+/// String will be cut to leave room for the null terminator, see `truncate`.
 fn copy_string(dst: *mut c_void, src: &str, max: usize) -> isize {
     unsafe {
         use libc::{memcpy, memset};
-        use std::cmp::min;
 
+        let src = truncate(src, max - 1);
         let dst = dst as *mut c_void;
         memset(dst, 0, max);
-        memcpy(dst, src.as_ptr() as *const c_void, min(max, src.as_bytes().len()));
+        memcpy(dst, src.as_ptr() as *const c_void, src.len());
     }
 
     1 // Success
 }
 
+/// Cut a string to at most `max` bytes, never cutting a multi-byte character in half.
+fn truncate(src: &str, max: usize) -> &str {
+    let mut len = max.min(src.len());
+    while !src.is_char_boundary(len) {
+        len -= 1;
+    }
+    &src[..len]
+}
+
+/// Copy a name that hosts expect to be plain ASCII, e.g. the effect or vendor name.
+///
+/// Accented latin letters are replaced by their base letter and any other non-ASCII character is
+/// dropped. The result is truncated to leave room for a null terminator within `max` bytes.
+fn copy_ascii_string(dst: *mut c_void, src: &str, max: usize) -> isize {
+    let ascii: String = src.chars().filter_map(to_ascii).take(max - 1).collect();
+    copy_string(dst, &ascii, max)
+}
+
+fn to_ascii(c: char) -> Option<char> {
+    let base = match c {
+        c if c.is_ascii() => c,
+        'À'..='Å' => 'A',
+        'Ç' => 'C',
+        'È'..='Ë' => 'E',
+        'Ì'..='Ï' => 'I',
+        'Ð' => 'D',
+        'Ñ' => 'N',
+        'Ò'..='Ö' | 'Ø' => 'O',
+        'Ù'..='Ü' => 'U',
+        'Ý' => 'Y',
+        'à'..='å' => 'a',
+        'ç' => 'c',
+        'è'..='ë' => 'e',
+        'ì'..='ï' => 'i',
+        'ñ' => 'n',
+        'ò'..='ö' | 'ø' => 'o',
+        'ù'..='ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        _ => return None,
+    };
+    Some(base)
+}
+
+/// The cache of a plugin created by this library.
+///
+/// Its fields are used from different threads, so never create a reference to the whole cache,
+/// only to the fields needed.
+unsafe fn plugin_cache(effect: *mut AEffect) -> *mut PluginCache {
+    (*effect).user as *mut PluginCache
+}
+
+/// Query the plugin's `Info` again if it was invalidated through `HostCallback::invalidate_info`.
+///
+/// Only called when the host suspends or resumes the plugin, so processing never sees the
+/// channel counts change. Other threads may still read the previous `Info`, so the new one is
+/// published through `PluginCache::replace_info` instead of being written in place.
+unsafe fn refresh_info(effect: *mut AEffect) {
+    let cache = plugin_cache(effect);
+    if !(*cache).info_changed.swap(false, Ordering::AcqRel) {
+        return;
+    }
+
+    let mut info = (*effect).get_plugin().get_info();
+    info.sync_parameter_count(&*(*cache).params);
+    // The latency is owned by `PluginCache::latency` after creation, see `set_latency`.
+    info.initial_delay = (*cache).latency.load(Ordering::Acquire);
+    (*cache).can_do.lock().unwrap_or_else(PoisonError::into_inner).clear();
+    let old = (*cache).info.get();
+    if info.unique_id != old.unique_id
+        || info.version != old.version
+        || info.category != old.category
+        || info.f64_precision != old.f64_precision
+        || info.preset_chunks != old.preset_chunks
+        || info.silent_when_stopped != old.silent_when_stopped
+    {
+        warn!("Plugin info fields fixed at creation changed, hosts may keep the old values");
+    }
+
+    let effect = &mut *effect;
+    effect.numPrograms = info.presets;
+    effect.numParams = info.parameters;
+    effect.numInputs = info.inputs;
+    effect.numOutputs = info.outputs;
+    (*cache).info.replace(info);
+}
+
+/// Name of the `Editor` method called for `opcode`, if any.
+fn editor_method(opcode: ::plugin::OpCode) -> Option<&'static str> {
+    use plugin::OpCode::*;
+
+    Some(match opcode {
+        EditorGetRect => "size",
+        EditorOpen => "open",
+        EditorClose => "close",
+        EditorIdle => "idle",
+        EditorKeyDown => "key_down",
+        EditorKeyUp => "key_up",
+        EditorSetKnobMode => "set_knob_mode",
+        _ => return None,
+    })
+}
+
 /// VST2.4 dispatch function. This function handles dispatching all opcodes to the VST plugin.
 pub extern "C" fn dispatch(
     effect: *mut AEffect,
@@ -89,13 +317,25 @@ pub extern "C" fn dispatch(
 ) -> isize {
     use plugin::{CanDo, OpCode};
 
-    // Convert passed in opcode to enum
-    let opcode = OpCode::from(opcode);
+    // Opcodes from newer SDKs or host extensions can't be represented by the enum.
+    let opcode = match OpCode::checked_from(i64::from(opcode)) {
+        Some(opcode) => opcode,
+        None => {
+            trace!("Unknown opcode ({})", opcode);
+            return 0;
+        }
+    };
     // Only query plugin or editor when needed to avoid creating multiple
     // concurrent mutable references to the same object.
     let get_plugin = || unsafe { (*effect).get_plugin() };
     let get_editor = || unsafe { (*effect).get_editor() };
+    let get_info = || unsafe { (*effect).get_info() };
     let params = unsafe { (*effect).get_params() };
+    let editor_open = unsafe { &(*plugin_cache(effect)).editor_open };
+
+    if let Some(method) = editor_method(opcode) {
+        thread_check::check_editor_thread(method);
+    }
 
     match opcode {
         OpCode::Initialize => get_plugin().init(),
@@ -104,9 +344,29 @@ pub extern "C" fn dispatch(
             drop(Box::from_raw(effect))
         },
 
-        OpCode::ChangePreset => params.change_preset(value as i32),
+        OpCode::ChangePreset => {
+            let presets = get_info().presets;
+            if value >= 0 && value < presets as isize {
+                params.change_preset(value as i32);
+                if get_info().refresh_on_preset_change {
+                    if let Some(ref mut editor) = get_editor() {
+                        if editor_open.load(Ordering::Acquire) {
+                            editor.preset_changed(value as i32);
+                        }
+                    }
+                    unsafe { &(*plugin_cache(effect)).host }.update_display();
+                }
+            } else {
+                debug!("Host requested invalid preset {} (plugin has {})", value, presets);
+            }
+        }
         OpCode::GetCurrentPresetNum => return params.get_preset_num() as isize,
-        OpCode::SetCurrentPresetName => params.set_preset_name(read_string(ptr)),
+        OpCode::BeginSetPreset => params.begin_set_preset(),
+        OpCode::EndSetPreset => params.end_set_preset(),
+        OpCode::SetCurrentPresetName => {
+            let name = read_string(ptr);
+            params.set_preset_name(truncate(&name, MAX_PRESET_NAME_LEN - 1).to_string());
+        }
         OpCode::GetCurrentPresetName => {
             let num = params.get_preset_num();
             return copy_string(ptr, &params.get_preset_name(num), MAX_PRESET_NAME_LEN);
@@ -116,13 +376,59 @@ pub extern "C" fn dispatch(
         OpCode::GetParameterDisplay => return copy_string(ptr, &params.get_parameter_text(index), MAX_PARAM_STR_LEN),
         OpCode::GetParameterName => return copy_string(ptr, &params.get_parameter_name(index), MAX_PARAM_STR_LEN),
 
-        OpCode::SetSampleRate => get_plugin().set_sample_rate(opt),
-        OpCode::SetBlockSize => get_plugin().set_block_size(value as i64),
+        OpCode::SetSampleRate => {
+            if !(opt.is_finite() && opt > 0.0 && opt <= MAX_SAMPLE_RATE) {
+                debug!("Host sent invalid sample rate ({})", opt);
+                return 0;
+            }
+            let supported = match get_plugin().supported_sample_rates() {
+                // Allow for rounding, e.g. 44099.998 for 44100.
+                Some(rates) => rates.iter().any(|rate| (rate - opt).abs() < 0.5),
+                None => true,
+            };
+            if supported {
+                #[cfg(feature = "profiling")]
+                unsafe { &(*plugin_cache(effect)).stats }.set_sample_rate(opt);
+                get_plugin().set_sample_rate(opt);
+            } else {
+                warn!("Host set unsupported sample rate ({})", opt);
+            }
+        }
+        OpCode::SetBlockSize => {
+            if value > 0 && value <= MAX_BLOCK_SIZE {
+                let cache = unsafe { plugin_cache(effect) };
+                unsafe { &(*cache).block_size }.store(value as usize, Ordering::Relaxed);
+                get_plugin().set_block_size(value as i64);
+            } else {
+                debug!("Host sent invalid block size ({})", value);
+            }
+        }
         OpCode::StateChanged => {
+            let cache = unsafe { plugin_cache(effect) };
+            // Processing is stopped while suspended, so the audio thread's fields can be used.
+            let events = unsafe { &mut *ptr::addr_of_mut!((*cache).events) };
+            unsafe { refresh_info(effect) };
             if value == 1 {
+                let info = get_info();
+                if info.process_context {
+                    events.reserve();
+                }
+                if info.soft_bypass {
+                    let (inputs, outputs) = (info.inputs as usize, info.outputs as usize);
+                    let block_size = unsafe { &(*cache).block_size }.load(Ordering::Relaxed);
+                    let latency = unsafe { &(*cache).latency }.load(Ordering::Acquire).max(0) as usize;
+                    unsafe { &mut *ptr::addr_of_mut!((*cache).bypass) }.prepare(inputs, outputs, block_size, latency);
+                    if info.f64_precision {
+                        unsafe { &mut *ptr::addr_of_mut!((*cache).bypass_f64) }
+                            .prepare(inputs, outputs, block_size, latency);
+                    }
+                }
                 get_plugin().resume();
             } else {
-                get_plugin().suspend();
+                get_plugin().reset();
+                events.clear();
+                unsafe { &mut *ptr::addr_of_mut!((*cache).bypass) }.clear();
+                unsafe { &mut *ptr::addr_of_mut!((*cache).bypass_f64) }.clear();
             }
         }
 
@@ -150,19 +456,27 @@ pub extern "C" fn dispatch(
                 // `ptr` is a window handle to the parent window.
                 // See the documentation for `Editor::open` for details.
                 if editor.open(ptr) {
-                    return 1;
+                    editor_open.store(true, Ordering::Release);
+                    return opened_window(&**editor);
                 }
             }
         }
         OpCode::EditorClose => {
             if let Some(ref mut editor) = get_editor() {
-                editor.close();
+                // Some hosts close editors that were never opened, e.g. after a failed open.
+                if editor_open.swap(false, Ordering::AcqRel) {
+                    editor.close();
+                } else {
+                    debug!("Host closed an editor that is not open");
+                }
             }
         }
 
         OpCode::EditorIdle => {
             if let Some(ref mut editor) = get_editor() {
-                editor.idle();
+                if editor_open.load(Ordering::Acquire) {
+                    editor.idle();
+                }
             }
         }
 
@@ -194,15 +508,29 @@ pub extern "C" fn dispatch(
         }
 
         OpCode::ProcessEvents => {
-            get_plugin().process_events(unsafe { &*(ptr as *const api::Events) });
+            let events = unsafe { &*(ptr as *const api::Events) };
+            let info = get_info();
+            if !info.midi_cc_parameters.is_empty() {
+                set_mapped_parameters(events, info, &**params);
+            }
+            if info.process_context {
+                // Kept for the next `process_ctx` call. Only used on the audio thread.
+                unsafe { &mut *ptr::addr_of_mut!((*plugin_cache(effect)).events) }.store(events);
+            } else {
+                get_plugin().process_events(events);
+            }
         }
         OpCode::CanBeAutomated => return params.can_be_automated(index) as isize,
         OpCode::StringToParameter => return params.string_to_parameter(index, read_string(ptr)) as isize,
 
-        OpCode::GetPresetName => return copy_string(ptr, &params.get_preset_name(index), MAX_PRESET_NAME_LEN),
+        OpCode::GetPresetName => {
+            if index >= 0 && index < get_info().presets {
+                return copy_string(ptr, &params.get_preset_name(index), MAX_PRESET_NAME_LEN);
+            }
+        }
 
         OpCode::GetInputInfo => {
-            if index >= 0 && index < get_plugin().get_info().inputs {
+            if index >= 0 && index < get_info().inputs {
                 unsafe {
                     let ptr = ptr as *mut api::ChannelProperties;
                     *ptr = get_plugin().get_input_info(index).into();
@@ -210,7 +538,7 @@ pub extern "C" fn dispatch(
             }
         }
         OpCode::GetOutputInfo => {
-            if index >= 0 && index < get_plugin().get_info().outputs {
+            if index >= 0 && index < get_info().outputs {
                 unsafe {
                     let ptr = ptr as *mut api::ChannelProperties;
                     *ptr = get_plugin().get_output_info(index).into();
@@ -218,35 +546,69 @@ pub extern "C" fn dispatch(
             }
         }
         OpCode::GetCategory => {
-            return get_plugin().get_info().category.into();
+            return get_info().category.into();
         }
 
-        OpCode::GetEffectName => return copy_string(ptr, &get_plugin().get_info().name, MAX_VENDOR_STR_LEN),
+        OpCode::GetEffectName => return copy_ascii_string(ptr, &get_info().name, MAX_EFFECT_NAME_LEN),
 
-        OpCode::GetVendorName => return copy_string(ptr, &get_plugin().get_info().vendor, MAX_VENDOR_STR_LEN),
-        OpCode::GetProductName => return copy_string(ptr, &get_plugin().get_info().name, MAX_PRODUCT_STR_LEN),
-        OpCode::GetVendorVersion => return get_plugin().get_info().version as isize,
+        OpCode::GetVendorName => return copy_ascii_string(ptr, &get_info().vendor, MAX_VENDOR_STR_LEN),
+        OpCode::GetProductName => {
+            let info = get_info();
+            let product = info.product.as_ref().unwrap_or(&info.name);
+            return copy_ascii_string(ptr, product, MAX_PRODUCT_STR_LEN);
+        }
+        OpCode::GetVendorVersion => return get_info().version as isize,
         OpCode::VendorSpecific => return get_plugin().vendor_specific(index, value, ptr, opt),
         OpCode::CanDo => {
             let can_do = CanDo::from_str(&read_string(ptr));
-            return get_plugin().can_do(can_do).into();
+            let answers = unsafe { &(*plugin_cache(effect)).can_do };
+            return answers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get_or_query(can_do, |can_do| get_plugin().can_do(can_do).into());
         }
-        OpCode::GetTailSize => {
-            if get_plugin().get_tail_size() == 0 {
+        OpCode::SoftBypass => {
+            let bypass = value != 0;
+            let supported = get_plugin().set_bypass(bypass);
+            if get_info().soft_bypass {
+                unsafe { &(*plugin_cache(effect)).bypassed }.store(bypass, Ordering::Relaxed);
                 return 1;
-            } else {
-                return get_plugin().get_tail_size();
             }
+            return supported as isize;
+        }
+        OpCode::GetTailSize => {
+            let tail_size = match get_plugin().get_tail_size() {
+                0 => get_info().tail_samples as isize,
+                tail_size => tail_size,
+            };
+            // 0 means that the tail size is unknown, 1 that there is no tail.
+            return if tail_size == 0 { 1 } else { tail_size };
+        }
+
+        OpCode::GetParamInfo => {
+            if index >= 0 && index < get_info().parameters {
+                if let Some(info) = params.get_parameter_info(index) {
+                    unsafe { *(ptr as *mut api::ParameterProperties) = info.into() };
+                    return 1;
+                }
+            }
+        }
+        OpCode::KeysRequired => {
+            // 0 means that keys are required, 1 that they aren't.
+            let wants_keys = match get_editor() {
+                Some(ref editor) => editor.wants_keyboard_focus(),
+                None => false,
+            };
+            return !wants_keys as isize;
         }
 
-        //OpCode::GetParamInfo => { /*TODO*/ }
         OpCode::GetApiVersion => return 2400,
 
         OpCode::EditorKeyDown => {
             if let Some(ref mut editor) = get_editor() {
                 editor.key_down(KeyCode {
                     character: index as u8 as char,
-                    key: Key::from(value),
+                    key: Key::checked_from(value as i64).unwrap_or(Key::None),
                     modifier: opt.to_bits() as u8,
                 });
             }
@@ -255,27 +617,56 @@ pub extern "C" fn dispatch(
             if let Some(ref mut editor) = get_editor() {
                 editor.key_up(KeyCode {
                     character: index as u8 as char,
-                    key: Key::from(value),
+                    key: Key::checked_from(value as i64).unwrap_or(Key::None),
                     modifier: opt.to_bits() as u8,
                 });
             }
         }
         OpCode::EditorSetKnobMode => {
             if let Some(ref mut editor) = get_editor() {
-                editor.set_knob_mode(KnobMode::from(value));
+                if let Some(mode) = KnobMode::checked_from(value as i64) {
+                    return editor.set_knob_mode(mode) as isize;
+                }
+                debug!("Host sent invalid knob mode ({})", value);
+            }
+        }
+
+        OpCode::GetMidiKeyName => {
+            if ptr.is_null() {
+                return 0;
+            }
+            let key_name = unsafe { &mut *(ptr as *mut api::MidiKeyName) };
+            if let Some(name) = get_plugin().get_midi_key_name(index, key_name.this_key_number) {
+                copy_string(key_name.key_name.as_mut_ptr() as *mut c_void, &name, MAX_NAME_LEN);
+                return 1;
             }
         }
 
         OpCode::StartProcess => get_plugin().start_process(),
         OpCode::StopProcess => get_plugin().stop_process(),
 
-        OpCode::GetNumMidiInputs => return unsafe { (*effect).get_info() }.midi_inputs as isize,
-        OpCode::GetNumMidiOutputs => return unsafe { (*effect).get_info() }.midi_outputs as isize,
+        OpCode::GetNumMidiInputs => return get_info().midi_inputs as isize,
+        OpCode::GetNumMidiOutputs => return get_info().midi_outputs as isize,
+
+        OpCode::SetPanLaw => match api::PanLaw::checked_from(value as i64) {
+            Some(law) => get_plugin().set_pan_law(law, opt),
+            None => debug!("Host sent unknown pan law ({})", value),
+        },
+
+        OpCode::ProcessVarIo => {
+            if !ptr.is_null() {
+                let io = unsafe { &mut *(ptr as *mut api::VariableIo) };
+                return get_plugin().process_var_io(io) as isize;
+            }
+        }
+
+        // Sent once per processing block (or once per idle tick) by some hosts, don't log them.
+        OpCode::SetTotalSampleToProcess | OpCode::_Idle => {}
 
         _ => {
-            debug!("Unimplemented opcode ({:?})", opcode);
             trace!(
-                "Arguments; index: {}, value: {}, ptr: {:?}, opt: {}",
+                "Unimplemented opcode ({:?}); index: {}, value: {}, ptr: {:?}, opt: {}",
+                opcode,
                 index,
                 value,
                 ptr,
@@ -298,7 +689,15 @@ pub fn host_dispatch(
 ) -> isize {
     use host::OpCode;
 
-    match OpCode::from(opcode) {
+    let opcode = match OpCode::checked_from(i64::from(opcode)) {
+        Some(opcode) => opcode,
+        None => {
+            trace!("Unknown host opcode ({})", opcode);
+            return 0;
+        }
+    };
+
+    match opcode {
         OpCode::Version => return 2400,
         OpCode::Automate => host.automate(index, opt),
         OpCode::BeginEdit => host.begin_edit(index),
@@ -307,13 +706,20 @@ pub fn host_dispatch(
         OpCode::Idle => host.idle(),
 
         // ...
-        OpCode::CanDo => {
-            info!("Plugin is asking if host can: {}.", read_string(ptr));
-        }
+        OpCode::CanDo => return host.can_do(&read_string(ptr)).into(),
 
         OpCode::GetVendorVersion => return host.get_info().0,
         OpCode::GetVendorString => return copy_string(ptr, &host.get_info().1, MAX_VENDOR_STR_LEN),
         OpCode::GetProductString => return copy_string(ptr, &host.get_info().2, MAX_PRODUCT_STR_LEN),
+        OpCode::VendorSpecific => return host.vendor_specific(index, value, ptr, opt),
+        OpCode::CloseWindow => return host.close_window() as isize,
+        OpCode::GetChunkFile => {
+            return match host.get_chunk_file() {
+                Some(path) => copy_string(ptr, &path.to_string_lossy(), MAX_PATH_LEN),
+                None => 0,
+            };
+        }
+        OpCode::IOChanged => return host.io_changed() as isize,
         OpCode::ProcessEvents => {
             host.process_events(unsafe { &*(ptr as *const api::Events) });
         }
@@ -333,7 +739,10 @@ pub fn host_dispatch(
                 }
             };
         }
+        OpCode::GetSampleRate => return host.get_sample_rate(),
         OpCode::GetBlockSize => return host.get_block_size(),
+        OpCode::GetParameterQuantization => return host.get_parameter_quantization() as isize,
+        OpCode::GetAutomationState => return host.get_automation_state().into(),
 
         unimplemented => {
             trace!("VST: Got unimplemented host opcode ({:?})", unimplemented);
@@ -356,3 +765,83 @@ fn read_string(ptr: *mut c_void) -> String {
 
     String::from_utf8_lossy(unsafe { CStr::from_ptr(ptr as *mut c_char).to_bytes() }).into_owned()
 }
+
+/// The value returned to the host for a successfully opened editor. See `Editor::window_handle`.
+#[cfg(target_os = "windows")]
+fn opened_window(editor: &dyn Editor) -> isize {
+    match editor.window_handle() {
+        Some(handle) if !handle.is_null() => handle as isize,
+        _ => 1,
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn opened_window(_editor: &dyn Editor) -> isize {
+    1
+}
+
+/// Set the parameters mapped to incoming MIDI CCs in `Info::midi_cc_parameters`.
+fn set_mapped_parameters(events: &api::Events, info: &Info, params: &dyn PluginParameters) {
+    for event in events.events() {
+        if let Event::Midi(MidiEvent {
+            data: [status, controller, value],
+            ..
+        }) = event
+        {
+            if status & 0xf0 != 0xb0 {
+                continue;
+            }
+            for &(cc, index) in &info.midi_cc_parameters {
+                if cc == controller && index >= 0 && index < info.parameters {
+                    params.set_parameter(index, f32::from(value.min(127)) / 127.0);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::raw::c_void;
+
+    use api::consts::MAX_PRESET_NAME_LEN;
+
+    use super::{copy_ascii_string, copy_string, truncate};
+
+    #[test]
+    fn copy_string_char_boundary() {
+        let mut buf = [0xffu8; 4];
+        copy_string(buf.as_mut_ptr() as *mut c_void, "aé€", 4);
+        // "€" would not fit completely so it is left out.
+        assert_eq!(&buf, "aé\0".as_bytes());
+    }
+
+    #[test]
+    fn long_preset_name() {
+        let name = "Very Long Preset Name for Pads — Ünïcode";
+        assert!(name.len() > MAX_PRESET_NAME_LEN);
+
+        // Written past the end of the buffer if the length isn't respected.
+        let mut buf = [0xffu8; MAX_PRESET_NAME_LEN + 1];
+        copy_string(buf.as_mut_ptr() as *mut c_void, name, MAX_PRESET_NAME_LEN);
+        assert_eq!(&buf[..MAX_PRESET_NAME_LEN], b"Very Long Preset Name f\0");
+        assert_eq!(buf[MAX_PRESET_NAME_LEN], 0xff);
+
+        // "—" takes bytes 31 to 33, so cutting at 32 leaves it out.
+        assert_eq!(truncate(name, 32), "Very Long Preset Name for Pads ");
+        assert_eq!(truncate("short", MAX_PRESET_NAME_LEN), "short");
+    }
+
+    #[test]
+    fn copy_ascii_multibyte_name() {
+        let mut buf = [0xffu8; 32];
+        copy_ascii_string(
+            buf.as_mut_ptr() as *mut c_void,
+            "Crème Brûlée Ünïcode Synth — Déluxe Edition",
+            32,
+        );
+        let len = buf.iter().position(|&b| b == 0).unwrap();
+        assert_eq!(len, 31);
+        assert_eq!(&buf[..len], b"Creme Brulee Unicode Synth  Del");
+    }
+}
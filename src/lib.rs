@@ -116,11 +116,33 @@ extern crate log;
 #[macro_use]
 extern crate bitflags;
 
+use std::panic;
 use std::ptr;
+use std::sync::Once;
 
 /// Implements `From` and `Into` for enums with `#[repr(usize)]`. Useful for interfacing with C
 /// enums.
+///
+/// The variants must be numbered contiguously from 0. `From` transmutes without checking, so
+/// only use it for values known to be in range. With `impl_clike!(Enum; last = LastVariant)`, a
+/// `checked_from` method returning `None` for out of range values is generated as well, which
+/// should be used for values coming from the host or plugin.
 macro_rules! impl_clike {
+    ($t:ident; last = $last:ident) => {
+        impl_clike!($t);
+
+        impl $t {
+            /// Convert a raw value to this enum, returning `None` if it is out of range.
+            pub fn checked_from(value: i64) -> Option<$t> {
+                if value >= 0 && value <= $t::$last as i64 {
+                    Some($t::from(value as usize))
+                } else {
+                    None
+                }
+            }
+        }
+    };
+
     ($t:ty, $($c:ty) +) => {
         $(
             impl From<$c> for $t {
@@ -145,19 +167,31 @@ macro_rules! impl_clike {
 
 pub mod api;
 pub mod buffer;
+mod bypass;
 mod cache;
 pub mod channels;
 pub mod editor;
 pub mod event;
+#[cfg(feature = "gui")]
+pub mod gui;
 pub mod host;
 mod interfaces;
+pub mod parameter;
 pub mod plugin;
+pub mod prelude;
+pub mod process;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+mod rt_check;
+mod thread_check;
 
 pub mod util;
 
 use api::consts::VST_MAGIC;
 use api::{AEffect, HostCallbackProc};
 use cache::PluginCache;
+#[cfg(debug_assertions)]
+use plugin::PluginParameters;
 use plugin::{HostCallback, Plugin};
 
 /// Exports the necessary symbols for the plugin to be used by a VST host.
@@ -187,9 +221,53 @@ macro_rules! plugin_main {
     };
 }
 
+/// Answers `Plugin::can_do` from a table of `CanDo` variants, falling back to
+/// `CanDo::default_support` for everything not in the table.
+///
+/// The first argument is the `CanDo` to answer and the second the plugin `Info` for the fallback,
+/// which is only evaluated if needed. Entries are one or more `CanDo` variants, separated by `|`,
+/// and the `Supported` answer for them. Both can be written without their enum names. Misspelled
+/// variants are compile errors, and capabilities added to `CanDo` later on keep getting an answer.
+/// Channel configurations and unknown strings (`CanDo::Other`) always use the fallback.
+///
+/// # Example
+/// ```
+/// # #[macro_use] extern crate vst;
+/// # use vst::api::Supported;
+/// # use vst::plugin::{CanDo, HostCallback, Info, Plugin};
+/// # struct MidiPlugin;
+/// impl Plugin for MidiPlugin {
+/// #     fn new(_host: HostCallback) -> Self { MidiPlugin }
+/// #     fn get_info(&self) -> Info { Default::default() }
+///     // ...
+///
+///     fn can_do(&self, can_do: CanDo) -> Supported {
+///         can_do!(can_do, self.get_info(), {
+///             ReceiveEvents | ReceiveMidiEvent => Yes,
+///             SendEvents | SendMidiEvent => No,
+///         })
+///     }
+/// }
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! can_do {
+    ($can_do:expr, $info:expr, { $($($variant:ident)|+ => $support:expr),* $(,)* }) => {{
+        #[allow(unused_imports)]
+        use $crate::api::Supported::*;
+
+        match $can_do {
+            $($($crate::plugin::CanDo::$variant)|+ => $support,)*
+            other => other.default_support(&$info),
+        }
+    }};
+}
+
 /// Initializes a VST plugin and returns a raw pointer to an AEffect struct.
 #[doc(hidden)]
 pub fn main<T: Plugin>(callback: HostCallbackProc) -> *mut AEffect {
+    install_panic_hook();
+
     // Initialize as much of the AEffect as we can before creating the plugin.
     // In particular, initialize all the function pointers, since initializing
     // these to zero is undefined behavior.
@@ -238,9 +316,40 @@ pub fn main<T: Plugin>(callback: HostCallbackProc) -> *mut AEffect {
     }
 
     trace!("Creating VST plugin instance...");
-    let mut plugin = T::new(host);
-    let info = plugin.get_info();
+    let mut plugin = match T::try_new(host) {
+        Ok(plugin) => plugin,
+        Err(err) => {
+            error!("Failed to create the plugin: {}", err);
+            drop(unsafe { Box::from_raw(raw_effect) });
+            return ptr::null_mut();
+        }
+    };
+    let mut info = plugin.get_info();
+    #[cfg(debug_assertions)]
+    {
+        if let Err(err) = info.validate() {
+            warn!("Invalid plugin info for \"{}\": {}", info.name, err);
+        }
+    }
+    // Hosts confuse plugins without an ID, so warn about it in release builds as well.
+    #[cfg(not(debug_assertions))]
+    {
+        if info.unique_id == 0 {
+            warn!("Plugin \"{}\" has no unique ID, see `Info::id_from_str`", info.name);
+        }
+    }
     let params = plugin.get_parameter_object();
+    info.sync_parameter_count(&*params);
+    #[cfg(debug_assertions)]
+    {
+        let unnamed = unnamed_parameters(&*params, info.parameters);
+        if !unnamed.is_empty() {
+            warn!(
+                "\"{}\" declares {} parameters but has no names for parameters {:?}",
+                info.name, info.parameters, unnamed
+            );
+        }
+    }
     let editor = plugin.get_editor();
 
     // Update AEffect in place
@@ -277,24 +386,51 @@ pub fn main<T: Plugin>(callback: HostCallbackProc) -> *mut AEffect {
         flag.bits()
     };
     effect.initialDelay = info.initial_delay;
-    effect.object = Box::into_raw(Box::new(Box::new(plugin) as Box<dyn Plugin>)) as *mut _;
-    effect.user = Box::into_raw(Box::new(PluginCache::new(&info, params, editor))) as *mut _;
     effect.uniqueId = info.unique_id;
     effect.version = info.version;
+    effect.object = Box::into_raw(Box::new(Box::new(plugin) as Box<dyn Plugin>)) as *mut _;
+    effect.user = Box::into_raw(Box::new(PluginCache::new(info, params, editor, host))) as *mut _;
 
     effect
 }
 
+/// Log panics through the `log` facade, as plugins usually have no visible stderr.
+///
+/// The hook is only installed once per loaded library, and still calls the previous hook.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            error!("{}", info);
+            previous_hook(info);
+        }));
+    });
+}
+
+/// Find the parameters below `count` without a name, which usually means `Info::parameters` is
+/// larger than the number of parameters the plugin actually implements.
+#[cfg(debug_assertions)]
+fn unnamed_parameters(params: &dyn PluginParameters, count: i32) -> Vec<i32> {
+    (0..count)
+        .filter(|&index| params.get_parameter_name(index).is_empty())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use std::ptr;
+    use std::sync::atomic::{AtomicI32, Ordering};
 
     use std::os::raw::c_void;
 
     use api::consts::VST_MAGIC;
     use api::AEffect;
+    use editor::Editor;
     use interfaces;
-    use plugin::{HostCallback, Info, Plugin};
+    use main;
+    use plugin::{HostCallback, Info, OpCode, Plugin};
 
     struct TestPlugin;
 
@@ -345,6 +481,46 @@ mod tests {
         0
     }
 
+    /// Create an instance of `P` for a host answering every call with 1.
+    fn load<P: Plugin>() -> *mut AEffect {
+        let aeffect = main::<P>(pass_callback);
+        assert!(!aeffect.is_null());
+        aeffect
+    }
+
+    /// Call the dispatcher of `effect`, like a host would.
+    fn dispatch(effect: *mut AEffect, opcode: OpCode, index: i32, value: isize, ptr: *mut c_void, opt: f32) -> isize {
+        unsafe { ((*effect).dispatcher)(effect, opcode.into(), index, value, ptr, opt) }
+    }
+
+    /// Last preset passed to `TestEditor::preset_changed`.
+    static EDITOR_PRESET: AtomicI32 = AtomicI32::new(-1);
+
+    /// An editor which always opens.
+    struct TestEditor;
+
+    impl Editor for TestEditor {
+        fn size(&self) -> (i32, i32) {
+            (100, 100)
+        }
+
+        fn position(&self) -> (i32, i32) {
+            (0, 0)
+        }
+
+        fn open(&mut self, _parent: *mut c_void) -> bool {
+            true
+        }
+
+        fn is_open(&mut self) -> bool {
+            true
+        }
+
+        fn preset_changed(&mut self, preset: i32) {
+            EDITOR_PRESET.store(preset, Ordering::SeqCst);
+        }
+    }
+
     #[cfg(target_os = "windows")]
     #[test]
     fn old_hosts() {
@@ -368,6 +544,51 @@ mod tests {
         assert!(!aeffect.is_null());
     }
 
+    #[test]
+    fn plugin_flags() {
+        use api::PluginFlags;
+        use plugin::Category;
+
+        let aeffect = VSTPluginMain(pass_callback);
+        assert_eq!(unsafe { (*aeffect).plugin_flags() }, PluginFlags::CAN_REPLACING);
+        unsafe { (*aeffect).drop_plugin() };
+
+        #[derive(Default)]
+        struct SynthPlugin;
+
+        impl Plugin for SynthPlugin {
+            fn new(_host: HostCallback) -> Self {
+                SynthPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    category: Category::Synth,
+                    preset_chunks: true,
+                    f64_precision: true,
+                    silent_when_stopped: true,
+                    ..Default::default()
+                }
+            }
+
+            fn get_editor(&mut self) -> Option<Box<dyn Editor>> {
+                Some(Box::new(TestEditor))
+            }
+        }
+
+        let aeffect = load::<SynthPlugin>();
+        let flags = unsafe { (*aeffect).plugin_flags() };
+        assert_eq!(flags, PluginFlags::all());
+        assert_eq!(flags.bits(), unsafe { (*aeffect).flags });
+        unsafe { (*aeffect).drop_plugin() };
+
+        // Deprecated bits like `effFlagsHasClip` are dropped.
+        let aeffect = VSTPluginMain(pass_callback);
+        unsafe { (*aeffect).flags |= 1 << 1 };
+        assert_eq!(unsafe { (*aeffect).plugin_flags() }, PluginFlags::CAN_REPLACING);
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
     #[test]
     fn plugin_drop() {
         static mut DROP_TEST: bool = false;
@@ -409,31 +630,1361 @@ mod tests {
     }
 
     #[test]
-    fn aeffect_params() {
-        // Assert that 2 function pointers are equal.
-        macro_rules! assert_fn_eq {
-            ($a:expr, $b:expr) => {
-                assert_eq!($a as usize, $b as usize);
-            };
+    fn process_context() {
+        use std::sync::atomic::AtomicUsize;
+        use std::sync::Mutex;
+
+        use api;
+        use buffer::SendEventBuffer;
+        use event::{Event, MidiEvent};
+        use host;
+        use process::ProcessContext;
+
+        static SEEN: Mutex<Vec<(i32, [u8; 3])>> = Mutex::new(Vec::new());
+        static TIME_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+        /// Like `pass_callback`, but without time info.
+        extern "C" fn no_time_callback(
+            _effect: *mut AEffect,
+            opcode: i32,
+            _index: i32,
+            _value: isize,
+            _ptr: *mut c_void,
+            _opt: f32,
+        ) -> isize {
+            if opcode == host::OpCode::GetTime as i32 {
+                TIME_REQUESTS.fetch_add(1, Ordering::SeqCst);
+                return 0;
+            }
+            1
         }
 
-        let aeffect = unsafe { &mut *VSTPluginMain(pass_callback) };
+        #[derive(Default)]
+        struct ContextPlugin;
 
-        assert_eq!(aeffect.magic, VST_MAGIC);
-        assert_fn_eq!(aeffect.dispatcher, interfaces::dispatch);
-        assert_fn_eq!(aeffect._process, interfaces::process_deprecated);
-        assert_fn_eq!(aeffect.setParameter, interfaces::set_parameter);
-        assert_fn_eq!(aeffect.getParameter, interfaces::get_parameter);
-        assert_eq!(aeffect.numPrograms, 1);
-        assert_eq!(aeffect.numParams, 1);
-        assert_eq!(aeffect.numInputs, 2);
-        assert_eq!(aeffect.numOutputs, 2);
-        assert_eq!(aeffect.reserved1, 0);
-        assert_eq!(aeffect.reserved2, 0);
-        assert_eq!(aeffect.initialDelay, 123);
-        assert_eq!(aeffect.uniqueId, 5678);
-        assert_eq!(aeffect.version, 1234);
-        assert_fn_eq!(aeffect.processReplacing, interfaces::process_replacing);
-        assert_fn_eq!(aeffect.processReplacingF64, interfaces::process_replacing_f64);
+        impl Plugin for ContextPlugin {
+            fn new(_host: HostCallback) -> Self {
+                ContextPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    inputs: 0,
+                    outputs: 0,
+                    process_context: true,
+                    ..Default::default()
+                }
+            }
+
+            fn process_ctx(&mut self, ctx: ProcessContext<f32>) {
+                assert!(ctx.time_info.is_none());
+                assert_eq!(ctx.buffer.samples(), 64);
+                for event in ctx.events() {
+                    if let Event::Midi(event) = event {
+                        SEEN.lock().unwrap().push((event.delta_frames, event.data));
+                    }
+                }
+            }
+        }
+
+        let aeffect = main::<ContextPlugin>(no_time_callback);
+        assert!(!aeffect.is_null());
+        dispatch(aeffect, OpCode::StateChanged, 0, 1, ptr::null_mut(), 0.0);
+        let inputs: [*const f32; 0] = [];
+        let mut outputs: [*mut f32; 0] = [];
+        let mut process =
+            || unsafe { ((*aeffect).processReplacing)(aeffect, inputs.as_ptr(), outputs.as_mut_ptr(), 64) };
+
+        let note_on = MidiEvent {
+            data: [0x90, 60, 100],
+            delta_frames: 12,
+            live: false,
+            note_length: None,
+            note_offset: None,
+            detune: 0,
+            note_off_velocity: 0,
+        };
+        let mut send_buffer = SendEventBuffer::new(1);
+        send_buffer.store_events(&[note_on]);
+        let events = send_buffer.events() as *const api::Events as *mut c_void;
+        dispatch(aeffect, OpCode::ProcessEvents, 0, 0, events, 0.0);
+
+        // The events are passed to the next block only.
+        process();
+        process();
+        assert_eq!(*SEEN.lock().unwrap(), vec![(12, [0x90, 60, 100])]);
+        // The plugin didn't set `uses_time_info`.
+        assert_eq!(TIME_REQUESTS.load(Ordering::SeqCst), 0);
+
+        // Hosts predating VST 2.4 get the same handling through the accumulating process call.
+        dispatch(aeffect, OpCode::ProcessEvents, 0, 0, events, 0.0);
+        for _ in 0..2 {
+            unsafe { ((*aeffect)._process)(aeffect, inputs.as_ptr(), outputs.as_mut_ptr(), 64) };
+        }
+        assert_eq!(SEEN.lock().unwrap().len(), 2);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn midi_cc_parameters() {
+        use std::sync::{Arc, Mutex};
+
+        use api;
+        use buffer::SendEventBuffer;
+        use event::MidiEvent;
+        use plugin::PluginParameters;
+
+        #[derive(Default)]
+        struct Params {
+            values: Mutex<[f32; 2]>,
+        }
+
+        impl PluginParameters for Params {
+            fn set_parameter(&self, index: i32, value: f32) {
+                self.values.lock().unwrap()[index as usize] = value;
+            }
+        }
+
+        #[derive(Default)]
+        struct CcPlugin {
+            params: Arc<Params>,
+        }
+
+        impl Plugin for CcPlugin {
+            fn new(_host: HostCallback) -> Self {
+                Default::default()
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    parameters: 2,
+                    // CC 2 is mapped to a parameter that doesn't exist.
+                    midi_cc_parameters: vec![(1, 0), (7, 1), (2, 5)],
+                    ..Default::default()
+                }
+            }
+
+            fn get_parameter_object(&mut self) -> Arc<dyn PluginParameters> {
+                Arc::clone(&self.params) as Arc<dyn PluginParameters>
+            }
+        }
+
+        let aeffect = load::<CcPlugin>();
+
+        let mut send_buffer = SendEventBuffer::new(4);
+        send_buffer.store_events(&[
+            MidiEvent::control_change(0, 1, 127, 0),
+            MidiEvent::control_change(3, 7, 127, 0),
+            MidiEvent::control_change(0, 2, 64, 0),
+            MidiEvent::note_on(0, 1, 64, 0),
+        ]);
+        let events = send_buffer.events() as *const api::Events as *mut c_void;
+        dispatch(aeffect, OpCode::ProcessEvents, 0, 0, events, 0.0);
+
+        let params = unsafe { (*aeffect).get_params() };
+        let params = unsafe { &*(&**params as *const dyn PluginParameters as *const Params) };
+        assert_eq!(*params.values.lock().unwrap(), [1.0, 1.0]);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn process_deprecated_accumulates() {
+        const SIZE: usize = 64;
+
+        let aeffect = VSTPluginMain(pass_callback);
+        assert!(!aeffect.is_null());
+
+        let in1 = vec![2.0f32; SIZE];
+        let in2 = vec![3.0f32; SIZE];
+        let mut out1 = vec![1.0f32; SIZE];
+        let mut out2 = vec![1.0f32; SIZE];
+
+        let inputs = [in1.as_ptr(), in2.as_ptr()];
+        let mut outputs = [out1.as_mut_ptr(), out2.as_mut_ptr()];
+
+        // The default `Plugin::process` copies inputs to outputs, which must be accumulated.
+        unsafe { ((*aeffect)._process)(aeffect, inputs.as_ptr(), outputs.as_mut_ptr(), SIZE as i32) };
+
+        assert_eq!(out1, vec![3.0; SIZE]);
+        assert_eq!(out2, vec![4.0; SIZE]);
+    }
+
+    #[test]
+    fn preset_bounds() {
+        use std::sync::Arc;
+
+        use plugin::PluginParameters;
+
+        struct PresetParams {
+            preset: AtomicI32,
+        }
+
+        impl PluginParameters for PresetParams {
+            fn change_preset(&self, preset: i32) {
+                assert!(preset >= 0 && preset < 4);
+                self.preset.store(preset, Ordering::Relaxed);
+            }
+
+            fn get_preset_num(&self) -> i32 {
+                self.preset.load(Ordering::Relaxed)
+            }
+        }
+
+        struct PresetPlugin {
+            params: Arc<PresetParams>,
+        }
+
+        impl Plugin for PresetPlugin {
+            fn new(_host: HostCallback) -> Self {
+                PresetPlugin {
+                    params: Arc::new(PresetParams {
+                        preset: AtomicI32::new(0),
+                    }),
+                }
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    name: "Preset Plugin".to_string(),
+                    unique_id: 1234,
+                    presets: 4,
+                    ..Default::default()
+                }
+            }
+
+            fn get_parameter_object(&mut self) -> Arc<dyn PluginParameters> {
+                Arc::clone(&self.params) as Arc<dyn PluginParameters>
+            }
+        }
+
+        let aeffect = load::<PresetPlugin>();
+        let change_preset = |preset: isize| dispatch(aeffect, OpCode::ChangePreset, 0, preset, ptr::null_mut(), 0.0);
+        let current_preset = || dispatch(aeffect, OpCode::GetCurrentPresetNum, 0, 0, ptr::null_mut(), 0.0);
+
+        change_preset(3);
+        assert_eq!(current_preset(), 3);
+
+        // Out of range indices must not reach the plugin.
+        change_preset(4);
+        change_preset(-1);
+        assert_eq!(current_preset(), 3);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn invalidate_info() {
+        static INPUTS: AtomicI32 = AtomicI32::new(2);
+
+        #[derive(Default)]
+        struct DynamicPlugin;
+
+        impl Plugin for DynamicPlugin {
+            fn new(_host: HostCallback) -> Self {
+                DynamicPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    name: "Dynamic Plugin".to_string(),
+                    inputs: INPUTS.load(Ordering::SeqCst),
+                    ..Default::default()
+                }
+            }
+        }
+
+        let aeffect = load::<DynamicPlugin>();
+
+        let set_state = |resumed: isize| dispatch(aeffect, OpCode::StateChanged, 0, resumed, ptr::null_mut(), 0.0);
+
+        // The cached info is used until it is invalidated.
+        INPUTS.store(4, Ordering::SeqCst);
+        set_state(1);
+        assert_eq!(unsafe { (*aeffect).numInputs }, 2);
+
+        // The new info is applied when the state changes, never between other calls.
+        let info = unsafe { (*aeffect).get_info() };
+        HostCallback::wrap(pass_callback, aeffect).invalidate_info();
+        dispatch(aeffect, OpCode::GetVendorVersion, 0, 0, ptr::null_mut(), 0.0);
+        assert_eq!(unsafe { (*aeffect).get_info() }.inputs, 2);
+        set_state(0);
+        assert_eq!(unsafe { (*aeffect).numInputs }, 4);
+        assert_eq!(unsafe { (*aeffect).get_info() }.inputs, 4);
+        // References to the previous info stay valid.
+        assert_eq!(info.inputs, 2);
+
+        INPUTS.store(6, Ordering::SeqCst);
+        HostCallback::wrap(pass_callback, aeffect).invalidate_info();
+        set_state(1);
+        assert_eq!(unsafe { (*aeffect).get_info() }.inputs, 6);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn can_do_table() {
+        use api::Supported;
+        use plugin::CanDo;
+
+        let info = Info {
+            inputs: 1,
+            outputs: 1,
+            ..Default::default()
+        };
+        let can_do = |can_do: CanDo| {
+            can_do!(can_do, info, {
+                ReceiveEvents | ReceiveMidiEvent => Yes,
+                Bypass => No,
+                MidiProgramNames => Supported::Custom(2),
+            })
+        };
+
+        assert_eq!(can_do(CanDo::ReceiveMidiEvent), Supported::Yes);
+        assert_eq!(can_do(CanDo::ReceiveEvents), Supported::Yes);
+        assert_eq!(can_do(CanDo::Bypass), Supported::No);
+        assert_eq!(can_do(CanDo::MidiProgramNames), Supported::Custom(2));
+        // Everything else is answered by the defaults.
+        assert_eq!(can_do(CanDo::Offline), Supported::No);
+        assert_eq!(can_do(CanDo::SendEvents), Supported::Maybe);
+        assert_eq!(can_do(CanDo::from_str("1in1out")), Supported::Yes);
+        assert_eq!(can_do(CanDo::from_str("hasCockosExtensions")), Supported::Maybe);
+    }
+
+    #[test]
+    fn refresh_info() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize};
+
+        use host;
+
+        static FILTER_MODE: AtomicBool = AtomicBool::new(false);
+        static DISPLAY_UPDATES: AtomicUsize = AtomicUsize::new(0);
+
+        extern "C" fn callback(
+            _effect: *mut AEffect,
+            opcode: i32,
+            _index: i32,
+            _value: isize,
+            _ptr: *mut c_void,
+            _opt: f32,
+        ) -> isize {
+            if opcode == host::OpCode::UpdateDisplay as i32 {
+                DISPLAY_UPDATES.fetch_add(1, Ordering::SeqCst);
+            }
+            1
+        }
+
+        #[derive(Default)]
+        struct MultimodePlugin;
+
+        impl Plugin for MultimodePlugin {
+            fn new(_host: HostCallback) -> Self {
+                MultimodePlugin
+            }
+
+            fn get_info(&self) -> Info {
+                // The band pass mode has an additional bandwidth parameter.
+                let band_pass = FILTER_MODE.load(Ordering::SeqCst);
+                Info {
+                    parameters: if band_pass { 3 } else { 2 },
+                    ..Default::default()
+                }
+            }
+        }
+
+        let aeffect = main::<MultimodePlugin>(callback);
+        assert_eq!(unsafe { (*aeffect).numParams }, 2);
+
+        FILTER_MODE.store(true, Ordering::SeqCst);
+        HostCallback::wrap(callback, aeffect).refresh();
+        assert_eq!(DISPLAY_UPDATES.load(Ordering::SeqCst), 1);
+
+        // The host picks up the new count when it restarts processing.
+        dispatch(aeffect, OpCode::StateChanged, 0, 0, ptr::null_mut(), 0.0);
+        dispatch(aeffect, OpCode::StateChanged, 0, 1, ptr::null_mut(), 0.0);
+        let mut name = [0u8; 64];
+        dispatch(
+            aeffect,
+            OpCode::GetParameterName,
+            2,
+            0,
+            name.as_mut_ptr() as *mut c_void,
+            0.0,
+        );
+        assert_eq!(unsafe { (*aeffect).numParams }, 3);
+        assert_eq!(unsafe { (*aeffect).get_info() }.parameters, 3);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn invalid_sample_rate_and_block_size() {
+        use std::sync::Mutex;
+
+        static CONFIG: Mutex<(f32, i64)> = Mutex::new((0.0, 0));
+
+        #[derive(Default)]
+        struct ConfigPlugin;
+
+        impl Plugin for ConfigPlugin {
+            fn new(_host: HostCallback) -> Self {
+                ConfigPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Default::default()
+            }
+
+            fn set_sample_rate(&mut self, rate: f32) {
+                CONFIG.lock().unwrap().0 = rate;
+            }
+
+            fn set_block_size(&mut self, size: i64) {
+                CONFIG.lock().unwrap().1 = size;
+            }
+        }
+
+        let aeffect = load::<ConfigPlugin>();
+        let set_rate = |rate: f32| dispatch(aeffect, OpCode::SetSampleRate, 0, 0, ptr::null_mut(), rate);
+        let set_size = |size: isize| dispatch(aeffect, OpCode::SetBlockSize, 0, size, ptr::null_mut(), 0.0);
+
+        set_rate(48000.0);
+        set_size(512);
+        assert_eq!(*CONFIG.lock().unwrap(), (48000.0, 512));
+
+        // Invalid values must not reach the plugin.
+        for &rate in &[0.0, -44100.0, f32::NAN, f32::INFINITY, 1e12] {
+            set_rate(rate);
+        }
+        for &size in &[0, -1, isize::MAX] {
+            set_size(size);
+        }
+        assert_eq!(*CONFIG.lock().unwrap(), (48000.0, 512));
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn unsupported_sample_rate() {
+        use std::sync::atomic::AtomicU32;
+
+        static RATE: AtomicU32 = AtomicU32::new(0);
+
+        #[derive(Default)]
+        struct FixedRatePlugin;
+
+        impl Plugin for FixedRatePlugin {
+            fn new(_host: HostCallback) -> Self {
+                FixedRatePlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Default::default()
+            }
+
+            fn set_sample_rate(&mut self, rate: f32) {
+                RATE.store(rate as u32, Ordering::SeqCst);
+            }
+
+            fn supported_sample_rates(&self) -> Option<Vec<f32>> {
+                Some(vec![44100.0, 48000.0])
+            }
+        }
+
+        let aeffect = load::<FixedRatePlugin>();
+        let set_rate = |rate: f32| dispatch(aeffect, OpCode::SetSampleRate, 0, 0, ptr::null_mut(), rate);
+
+        set_rate(48000.0);
+        assert_eq!(RATE.load(Ordering::SeqCst), 48000);
+        set_rate(96000.0);
+        assert_eq!(RATE.load(Ordering::SeqCst), 48000);
+        set_rate(44099.998);
+        assert_eq!(RATE.load(Ordering::SeqCst), 44099);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn unnamed_parameters() {
+        use plugin::PluginParameters;
+
+        struct TwoNames;
+
+        impl PluginParameters for TwoNames {
+            fn get_parameter_name(&self, index: i32) -> String {
+                match index {
+                    0 => "Cutoff".to_string(),
+                    1 => "Resonance".to_string(),
+                    _ => String::new(),
+                }
+            }
+        }
+
+        assert_eq!(super::unnamed_parameters(&TwoNames, 2), Vec::<i32>::new());
+        assert_eq!(super::unnamed_parameters(&TwoNames, 4), vec![2, 3]);
+    }
+
+    #[test]
+    fn set_latency() {
+        let aeffect = VSTPluginMain(pass_callback);
+        assert!(!aeffect.is_null());
+        assert_eq!(unsafe { (*aeffect).initialDelay }, 123);
+
+        let host = HostCallback::wrap(pass_callback, aeffect);
+        assert!(host.set_latency(456));
+        assert_eq!(unsafe { (*aeffect).initialDelay }, 456);
+
+        // Re-querying the info keeps the latency set at runtime.
+        host.invalidate_info();
+        dispatch(aeffect, OpCode::StateChanged, 0, 0, ptr::null_mut(), 0.0);
+        assert_eq!(unsafe { (*aeffect).initialDelay }, 456);
+        assert_eq!(unsafe { (*aeffect).get_info() }.initial_delay, 456);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn editor_open_state() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize};
+
+        static IDLE_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static CLOSE_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static CAN_OPEN: AtomicBool = AtomicBool::new(false);
+
+        struct CountingEditor;
+
+        impl Editor for CountingEditor {
+            fn size(&self) -> (i32, i32) {
+                (100, 100)
+            }
+
+            fn position(&self) -> (i32, i32) {
+                (0, 0)
+            }
+
+            fn idle(&mut self) {
+                IDLE_CALLS.fetch_add(1, Ordering::SeqCst);
+            }
+
+            fn close(&mut self) {
+                CLOSE_CALLS.fetch_add(1, Ordering::SeqCst);
+            }
+
+            fn open(&mut self, _parent: *mut c_void) -> bool {
+                CAN_OPEN.load(Ordering::SeqCst)
+            }
+
+            fn is_open(&mut self) -> bool {
+                false
+            }
+        }
+
+        #[derive(Default)]
+        struct EditorPlugin;
+
+        impl Plugin for EditorPlugin {
+            fn new(_host: HostCallback) -> Self {
+                EditorPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Default::default()
+            }
+
+            fn get_editor(&mut self) -> Option<Box<dyn Editor>> {
+                Some(Box::new(CountingEditor))
+            }
+        }
+
+        let aeffect = load::<EditorPlugin>();
+        let host = HostCallback::wrap(pass_callback, aeffect);
+        let send = |opcode: OpCode| dispatch(aeffect, opcode, 0, 0, ptr::null_mut(), 0.0);
+
+        // Idle and close calls for a closed editor must not reach it.
+        send(OpCode::EditorIdle);
+        send(OpCode::EditorClose);
+        assert!(!host.is_editor_open());
+        assert_eq!(IDLE_CALLS.load(Ordering::SeqCst), 0);
+        assert_eq!(CLOSE_CALLS.load(Ordering::SeqCst), 0);
+
+        // A failed open is reported to the host and leaves the editor closed.
+        assert_eq!(send(OpCode::EditorOpen), 0);
+        assert!(!host.is_editor_open());
+        send(OpCode::EditorIdle);
+        assert_eq!(IDLE_CALLS.load(Ordering::SeqCst), 0);
+
+        CAN_OPEN.store(true, Ordering::SeqCst);
+        assert_eq!(send(OpCode::EditorOpen), 1);
+        assert!(host.is_editor_open());
+        send(OpCode::EditorIdle);
+        assert_eq!(IDLE_CALLS.load(Ordering::SeqCst), 1);
+
+        send(OpCode::EditorClose);
+        send(OpCode::EditorClose);
+        assert!(!host.is_editor_open());
+        assert_eq!(CLOSE_CALLS.load(Ordering::SeqCst), 1);
+
+        // Reopening works after a repeated close.
+        assert_eq!(send(OpCode::EditorOpen), 1);
+        assert!(host.is_editor_open());
+        send(OpCode::EditorClose);
+        assert_eq!(CLOSE_CALLS.load(Ordering::SeqCst), 2);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn refresh_on_preset_change() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize};
+
+        use host;
+
+        static REFRESH: AtomicBool = AtomicBool::new(false);
+        static DISPLAY_UPDATES: AtomicUsize = AtomicUsize::new(0);
+
+        extern "C" fn callback(
+            _effect: *mut AEffect,
+            opcode: i32,
+            _index: i32,
+            _value: isize,
+            _ptr: *mut c_void,
+            _opt: f32,
+        ) -> isize {
+            if opcode == host::OpCode::UpdateDisplay as i32 {
+                DISPLAY_UPDATES.fetch_add(1, Ordering::SeqCst);
+            }
+            1
+        }
+
+        #[derive(Default)]
+        struct PresetPlugin;
+
+        impl Plugin for PresetPlugin {
+            fn new(_host: HostCallback) -> Self {
+                PresetPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    presets: 4,
+                    refresh_on_preset_change: REFRESH.load(Ordering::SeqCst),
+                    ..Default::default()
+                }
+            }
+
+            fn get_editor(&mut self) -> Option<Box<dyn Editor>> {
+                Some(Box::new(TestEditor))
+            }
+        }
+
+        let send = |aeffect: *mut AEffect, opcode: OpCode, value: isize| {
+            dispatch(aeffect, opcode, 0, value, ptr::null_mut(), 0.0)
+        };
+
+        // Off by default.
+        let aeffect = main::<PresetPlugin>(callback);
+        send(aeffect, OpCode::ChangePreset, 1);
+        assert_eq!(DISPLAY_UPDATES.load(Ordering::SeqCst), 0);
+        unsafe { (*aeffect).drop_plugin() };
+
+        REFRESH.store(true, Ordering::SeqCst);
+        let aeffect = main::<PresetPlugin>(callback);
+        send(aeffect, OpCode::ChangePreset, 2);
+        assert_eq!(DISPLAY_UPDATES.load(Ordering::SeqCst), 1);
+        // The editor is only told while it is open.
+        assert_eq!(EDITOR_PRESET.load(Ordering::SeqCst), -1);
+
+        send(aeffect, OpCode::EditorOpen, 0);
+        send(aeffect, OpCode::ChangePreset, 3);
+        assert_eq!(DISPLAY_UPDATES.load(Ordering::SeqCst), 2);
+        assert_eq!(EDITOR_PRESET.load(Ordering::SeqCst), 3);
+
+        // Out of range presets are ignored entirely.
+        send(aeffect, OpCode::ChangePreset, 4);
+        assert_eq!(DISPLAY_UPDATES.load(Ordering::SeqCst), 2);
+        send(aeffect, OpCode::EditorClose, 0);
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn soft_bypass() {
+        use std::ffi::CString;
+        use std::sync::atomic::AtomicUsize;
+
+        use buffer::AudioBuffer;
+
+        static PROCESS_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Default)]
+        struct LatencyPlugin;
+
+        impl Plugin for LatencyPlugin {
+            fn new(_host: HostCallback) -> Self {
+                LatencyPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    inputs: 1,
+                    outputs: 1,
+                    initial_delay: 4,
+                    soft_bypass: true,
+                    ..Default::default()
+                }
+            }
+
+            fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
+                PROCESS_CALLS.fetch_add(1, Ordering::SeqCst);
+                for (_, output) in buffer.zip() {
+                    for sample in output.iter_mut() {
+                        *sample = 0.5;
+                    }
+                }
+            }
+        }
+
+        let aeffect = load::<LatencyPlugin>();
+        let set_bypass = |bypass: isize| dispatch(aeffect, OpCode::SoftBypass, 0, bypass, ptr::null_mut(), 0.0);
+        let process = |input: &[f32]| {
+            let mut output = vec![0.0f32; input.len()];
+            let inputs = [input.as_ptr()];
+            let mut outputs = [output.as_mut_ptr()];
+            unsafe {
+                ((*aeffect).processReplacing)(aeffect, inputs.as_ptr(), outputs.as_mut_ptr(), input.len() as i32)
+            };
+            output
+        };
+
+        // The bypass buffers are allocated for the block size on resume.
+        dispatch(aeffect, OpCode::SetBlockSize, 0, 512, ptr::null_mut(), 0.0);
+        dispatch(aeffect, OpCode::StateChanged, 0, 1, ptr::null_mut(), 0.0);
+
+        let bypass = CString::new("bypass").unwrap();
+        assert_eq!(
+            dispatch(aeffect, OpCode::CanDo, 0, 0, bypass.as_ptr() as *mut c_void, 0.0),
+            1
+        );
+        assert_eq!(set_bypass(1), 1);
+
+        // Still processed while crossfading to the dry signal.
+        process(&[0.0; 512]);
+        assert_eq!(PROCESS_CALLS.load(Ordering::SeqCst), 1);
+
+        // Bypassed, the input is passed through delayed by the latency.
+        let input: Vec<f32> = (1..=8).map(|i| i as f32).collect();
+        assert_eq!(process(&input), vec![0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(PROCESS_CALLS.load(Ordering::SeqCst), 1);
+
+        set_bypass(0);
+        process(&[0.0; 512]);
+        assert_eq!(process(&input), vec![0.5; 8]);
+        assert_eq!(PROCESS_CALLS.load(Ordering::SeqCst), 3);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn suspend_resets() {
+        use std::sync::atomic::AtomicUsize;
+
+        static SUSPEND_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Default)]
+        struct DelayPlugin {
+            delay_line: Vec<f32>,
+        }
+
+        impl Plugin for DelayPlugin {
+            fn new(_host: HostCallback) -> Self {
+                DelayPlugin {
+                    delay_line: vec![1.0; 4],
+                }
+            }
+
+            fn get_info(&self) -> Info {
+                Default::default()
+            }
+
+            fn suspend(&mut self) {
+                SUSPEND_CALLS.fetch_add(1, Ordering::SeqCst);
+            }
+
+            fn reset(&mut self) {
+                self.suspend();
+                for sample in &mut self.delay_line {
+                    *sample = 0.0;
+                }
+            }
+        }
+
+        let aeffect = load::<DelayPlugin>();
+        dispatch(aeffect, OpCode::StateChanged, 0, 1, ptr::null_mut(), 0.0);
+        dispatch(aeffect, OpCode::StateChanged, 0, 0, ptr::null_mut(), 0.0);
+        assert_eq!(SUSPEND_CALLS.load(Ordering::SeqCst), 1);
+        let plugin = unsafe { &*(&**(*aeffect).get_plugin() as *const dyn Plugin as *const DelayPlugin) };
+        assert_eq!(plugin.delay_line, vec![0.0; 4]);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn default_process_f64_warning() {
+        use plugin::DEFAULT_PROCESS_F64_CALLED;
+
+        #[derive(Default)]
+        struct SinglePrecisionPlugin;
+
+        impl Plugin for SinglePrecisionPlugin {
+            fn new(_host: HostCallback) -> Self {
+                SinglePrecisionPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    inputs: 1,
+                    outputs: 1,
+                    f64_precision: true,
+                    ..Default::default()
+                }
+            }
+        }
+
+        let aeffect = load::<SinglePrecisionPlugin>();
+        let input = [0.5f64; 16];
+        let mut output = [0.0f64; 16];
+        let inputs = [input.as_ptr()];
+        let mut outputs = [output.as_mut_ptr()];
+        unsafe { ((*aeffect).processReplacingF64)(aeffect, inputs.as_ptr(), outputs.as_mut_ptr(), 16) };
+
+        // The audio is passed through, and the marker for the warning is set.
+        assert_eq!(output, input);
+        assert!(DEFAULT_PROCESS_F64_CALLED.load(Ordering::Relaxed));
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn tail_size() {
+        use plugin::TailSize;
+
+        #[derive(Default)]
+        struct DelayPlugin;
+
+        impl Plugin for DelayPlugin {
+            fn new(_host: HostCallback) -> Self {
+                DelayPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Default::default()
+            }
+
+            fn get_tail_size(&self) -> isize {
+                TailSize::Samples(480).into()
+            }
+        }
+
+        let get_tail_size = |aeffect: *mut AEffect| dispatch(aeffect, OpCode::GetTailSize, 0, 0, ptr::null_mut(), 0.0);
+
+        // Without a tail, "no tail" is reported instead of "unknown".
+        let aeffect = VSTPluginMain(pass_callback);
+        assert_eq!(get_tail_size(aeffect), isize::from(TailSize::None));
+        assert_eq!(get_tail_size(aeffect), 1);
+        unsafe { (*aeffect).drop_plugin() };
+
+        let aeffect = load::<DelayPlugin>();
+        assert_eq!(get_tail_size(aeffect), 480);
+        unsafe { (*aeffect).drop_plugin() };
+
+        assert_eq!(isize::from(TailSize::Samples(0)), 1);
+    }
+
+    #[test]
+    fn product_name_defaults_to_name() {
+        let aeffect = VSTPluginMain(pass_callback);
+        assert!(!aeffect.is_null());
+
+        let mut buf = [0xffu8; 64];
+        dispatch(
+            aeffect,
+            OpCode::GetProductName,
+            0,
+            0,
+            buf.as_mut_ptr() as *mut c_void,
+            0.0,
+        );
+        assert_eq!(&buf[..12], b"Test Plugin\0");
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn stereo_pin_properties() {
+        use api::{ChannelFlags, ChannelProperties, SpeakerArrangementType};
+
+        let plugin = TestPlugin;
+        assert_eq!(plugin.get_info().inputs, 2);
+        assert_eq!(plugin.get_info().outputs, 2);
+
+        let pairs = vec![
+            (plugin.get_input_info(0), plugin.get_input_info(1)),
+            (plugin.get_output_info(0), plugin.get_output_info(1)),
+        ];
+        for (left, right) in pairs {
+            let left: ChannelProperties = left.into();
+            let right: ChannelProperties = right.into();
+            // Only the first channel of a pair is flagged, both share the arrangement.
+            assert_eq!(left.flags, (ChannelFlags::ACTIVE | ChannelFlags::STEREO).bits());
+            assert_eq!(right.flags, ChannelFlags::ACTIVE.bits());
+            assert_eq!(left.arrangement_type as i32, SpeakerArrangementType::Stereo as i32);
+            assert_eq!(right.arrangement_type as i32, SpeakerArrangementType::Stereo as i32);
+        }
+    }
+
+    #[test]
+    fn impl_clike_round_trip() {
+        #[repr(usize)]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        enum Letter {
+            A,
+            B,
+            C,
+        }
+        impl_clike!(Letter; last = C);
+
+        for &letter in &[Letter::A, Letter::B, Letter::C] {
+            let raw: i32 = letter.into();
+            assert_eq!(Letter::from(raw), letter);
+            assert_eq!(Letter::checked_from(i64::from(raw)), Some(letter));
+        }
+        assert_eq!(Letter::checked_from(3), None);
+        assert_eq!(Letter::checked_from(-1), None);
+
+        // The real enums end at the declared variant.
+        use plugin::Category;
+        assert_eq!(Category::checked_from(11), Some(Category::Generator));
+        assert_eq!(Category::checked_from(12), None);
+        assert!(OpCode::checked_from(OpCode::GetNumMidiOutputs as i64).is_some());
+        assert!(OpCode::checked_from(OpCode::GetNumMidiOutputs as i64 + 1).is_none());
+    }
+
+    #[test]
+    fn set_pan_law() {
+        use api::PanLaw;
+        use std::sync::atomic::{AtomicU32, AtomicUsize};
+
+        static PAN_LAW: AtomicUsize = AtomicUsize::new(usize::MAX);
+        static GAIN: AtomicU32 = AtomicU32::new(0);
+
+        #[derive(Default)]
+        struct PannerPlugin;
+
+        impl Plugin for PannerPlugin {
+            fn new(_host: HostCallback) -> Self {
+                PannerPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info::default()
+            }
+
+            fn set_pan_law(&mut self, law: PanLaw, gain: f32) {
+                PAN_LAW.store(law as usize, Ordering::SeqCst);
+                GAIN.store(gain.to_bits(), Ordering::SeqCst);
+            }
+        }
+
+        let aeffect = load::<PannerPlugin>();
+        let set_pan_law = |law: isize, gain: f32| dispatch(aeffect, OpCode::SetPanLaw, 0, law, ptr::null_mut(), gain);
+
+        set_pan_law(1, 0.5);
+        assert_eq!(PAN_LAW.load(Ordering::SeqCst), PanLaw::EqualPower as usize);
+        assert_eq!(f32::from_bits(GAIN.load(Ordering::SeqCst)), 0.5);
+
+        // Unknown pan laws don't reach the plugin.
+        set_pan_law(2, 1.0);
+        assert_eq!(f32::from_bits(GAIN.load(Ordering::SeqCst)), 0.5);
+
+        set_pan_law(0, 1.0);
+        assert_eq!(PAN_LAW.load(Ordering::SeqCst), PanLaw::Linear as usize);
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn process_var_io() {
+        use api::VariableIo;
+        use plugin::Category;
+
+        struct StretchPlugin;
+
+        impl Plugin for StretchPlugin {
+            fn new(_host: HostCallback) -> Self {
+                StretchPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    category: Category::OfflineProcess,
+                    inputs: 1,
+                    outputs: 1,
+                    ..Default::default()
+                }
+            }
+
+            // Doubles the length by repeating every sample.
+            fn process_var_io(&mut self, io: &mut VariableIo) -> bool {
+                unsafe {
+                    let input = *io.inputs;
+                    let output = *io.outputs;
+                    let samples = (io.num_samples_output / 2).min(io.num_samples_input);
+                    for i in 0..samples as isize {
+                        *output.offset(2 * i) = *input.offset(i);
+                        *output.offset(2 * i + 1) = *input.offset(i);
+                    }
+                    *io.num_samples_input_processed = samples;
+                    *io.num_samples_output_processed = samples * 2;
+                }
+                true
+            }
+        }
+
+        let aeffect = load::<StretchPlugin>();
+
+        let mut input = [1.0f32, 2.0];
+        let mut output = [0.0f32; 4];
+        let mut inputs = [input.as_mut_ptr()];
+        let mut outputs = [output.as_mut_ptr()];
+        let (mut consumed, mut written) = (0, 0);
+        let mut io = VariableIo {
+            inputs: inputs.as_mut_ptr(),
+            outputs: outputs.as_mut_ptr(),
+            num_samples_input: 2,
+            num_samples_output: 4,
+            num_samples_input_processed: &mut consumed,
+            num_samples_output_processed: &mut written,
+        };
+
+        let ptr = &mut io as *mut VariableIo as *mut c_void;
+        assert_eq!(dispatch(aeffect, OpCode::ProcessVarIo, 0, 0, ptr, 0.0), 1);
+        assert_eq!((consumed, written), (2, 4));
+        assert_eq!(output, [1.0, 1.0, 2.0, 2.0]);
+
+        // Without buffers, and for plugins without variable I/O.
+        assert_eq!(dispatch(aeffect, OpCode::ProcessVarIo, 0, 0, ptr::null_mut(), 0.0), 0);
+        unsafe { (*aeffect).drop_plugin() };
+
+        let aeffect = VSTPluginMain(pass_callback);
+        assert_eq!(dispatch(aeffect, OpCode::ProcessVarIo, 0, 0, ptr, 0.0), 0);
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn midi_key_name_without_buffer() {
+        #[derive(Default)]
+        struct DrumPlugin;
+
+        impl Plugin for DrumPlugin {
+            fn new(_host: HostCallback) -> Self {
+                DrumPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info::default()
+            }
+
+            fn get_midi_key_name(&self, _channel: i32, _key: i32) -> Option<String> {
+                Some("Kick".to_string())
+            }
+        }
+
+        let aeffect = load::<DrumPlugin>();
+        assert_eq!(dispatch(aeffect, OpCode::GetMidiKeyName, 9, 0, ptr::null_mut(), 0.0), 0);
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn unknown_opcode() {
+        let aeffect = VSTPluginMain(pass_callback);
+        assert!(!aeffect.is_null());
+
+        for &opcode in &[-1, 80, 0xdead_beef_u32 as i32] {
+            let result = unsafe { ((*aeffect).dispatcher)(aeffect, opcode, 0, 0, ptr::null_mut(), 0.0) };
+            assert_eq!(result, 0);
+        }
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn aeffect_params() {
+        // Assert that 2 function pointers are equal.
+        macro_rules! assert_fn_eq {
+            ($a:expr, $b:expr) => {
+                assert_eq!($a as usize, $b as usize);
+            };
+        }
+
+        let aeffect = unsafe { &mut *VSTPluginMain(pass_callback) };
+
+        assert_eq!(aeffect.magic, VST_MAGIC);
+        assert_fn_eq!(aeffect.dispatcher, interfaces::dispatch);
+        assert_fn_eq!(aeffect._process, interfaces::process_deprecated);
+        assert_fn_eq!(aeffect.setParameter, interfaces::set_parameter);
+        assert_fn_eq!(aeffect.getParameter, interfaces::get_parameter);
+        assert_eq!(aeffect.numPrograms, 1);
+        assert_eq!(aeffect.numParams, 1);
+        assert_eq!(aeffect.numInputs, 2);
+        assert_eq!(aeffect.numOutputs, 2);
+        assert_eq!(aeffect.reserved1, 0);
+        assert_eq!(aeffect.reserved2, 0);
+        assert_eq!(aeffect.initialDelay, 123);
+        assert_eq!(aeffect.uniqueId, 5678);
+        assert_eq!(aeffect.version, 1234);
+        assert_fn_eq!(aeffect.processReplacing, interfaces::process_replacing);
+        assert_fn_eq!(aeffect.processReplacingF64, interfaces::process_replacing_f64);
+        assert_eq!(aeffect.future, [0u8; 56]);
+    }
+
+    #[test]
+    fn parameter_collection() {
+        use std::sync::Arc;
+
+        use parameter::{Parameter, Parameters};
+        use plugin::PluginParameters;
+
+        #[derive(Default)]
+        struct CollectionPlugin;
+
+        impl Plugin for CollectionPlugin {
+            fn new(_host: HostCallback) -> Self {
+                CollectionPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info::default()
+            }
+
+            fn get_parameter_object(&mut self) -> Arc<dyn PluginParameters> {
+                Arc::new(Parameters::new(vec![
+                    Parameter::new("Cutoff", 1.0).with_label("Hz"),
+                    Parameter::new("Resonance", 0.0),
+                ]))
+            }
+        }
+
+        let aeffect = load::<CollectionPlugin>();
+        assert_eq!(unsafe { (*aeffect).numParams }, 2);
+
+        unsafe {
+            ((*aeffect).setParameter)(aeffect, 1, 0.5);
+            assert_eq!(((*aeffect).getParameter)(aeffect, 1), 0.5);
+        }
+        let mut name = [0u8; 64];
+        dispatch(
+            aeffect,
+            OpCode::GetParameterName,
+            1,
+            0,
+            name.as_mut_ptr() as *mut c_void,
+            0.0,
+        );
+        assert_eq!(&name[..10], b"Resonance\0");
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn can_do_cache() {
+        use std::ffi::CString;
+        use std::sync::atomic::AtomicUsize;
+
+        use api::Supported;
+        use plugin::CanDo;
+
+        static CAN_DO_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Default)]
+        struct ProbedPlugin;
+
+        impl Plugin for ProbedPlugin {
+            fn new(_host: HostCallback) -> Self {
+                ProbedPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info::default()
+            }
+
+            fn can_do(&self, can_do: CanDo) -> Supported {
+                CAN_DO_CALLS.fetch_add(1, Ordering::SeqCst);
+                match can_do {
+                    CanDo::ReceiveMidiEvent => Supported::Yes,
+                    _ => Supported::No,
+                }
+            }
+        }
+
+        let aeffect = load::<ProbedPlugin>();
+        let can_do = |s: &str| {
+            let s = CString::new(s).unwrap();
+            dispatch(aeffect, OpCode::CanDo, 0, 0, s.as_ptr() as *mut c_void, 0.0)
+        };
+
+        assert_eq!(can_do("receiveVstMidiEvent"), 1);
+        assert_eq!(can_do("receiveVstMidiEvent"), 1);
+        assert_eq!(can_do("bypass"), -1);
+        assert_eq!(can_do("bypass"), -1);
+        assert_eq!(CAN_DO_CALLS.load(Ordering::SeqCst), 2);
+
+        // Custom strings aren't cached.
+        assert_eq!(can_do("hasCockosExtensions"), -1);
+        assert_eq!(can_do("hasCockosExtensions"), -1);
+        assert_eq!(CAN_DO_CALLS.load(Ordering::SeqCst), 4);
+
+        HostCallback::wrap(pass_callback, aeffect).invalidate_info();
+        dispatch(aeffect, OpCode::StateChanged, 0, 0, ptr::null_mut(), 0.0);
+        assert_eq!(can_do("receiveVstMidiEvent"), 1);
+        assert_eq!(CAN_DO_CALLS.load(Ordering::SeqCst), 5);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn empty_blocks() {
+        use std::sync::atomic::AtomicUsize;
+
+        use buffer::AudioBuffer;
+
+        static PROCESS_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Default)]
+        struct AveragingPlugin;
+
+        impl Plugin for AveragingPlugin {
+            fn new(_host: HostCallback) -> Self {
+                AveragingPlugin
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    inputs: 1,
+                    outputs: 1,
+                    ..Default::default()
+                }
+            }
+
+            fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
+                PROCESS_CALLS.fetch_add(1, Ordering::SeqCst);
+                let samples = buffer.samples();
+                for (input, output) in buffer.zip() {
+                    let average = input.iter().sum::<f32>() / samples as f32;
+                    for sample in output.iter_mut() {
+                        *sample = average;
+                    }
+                }
+            }
+        }
+
+        let aeffect = load::<AveragingPlugin>();
+        let input = [1.0f32, 0.0];
+        let mut output = [0.0f32; 2];
+        let inputs = [input.as_ptr()];
+        let mut outputs = [output.as_mut_ptr()];
+        unsafe {
+            ((*aeffect).processReplacing)(aeffect, inputs.as_ptr(), outputs.as_mut_ptr(), 0);
+            ((*aeffect).processReplacing)(aeffect, inputs.as_ptr(), outputs.as_mut_ptr(), -1);
+            assert_eq!(PROCESS_CALLS.load(Ordering::SeqCst), 0);
+
+            ((*aeffect).processReplacing)(aeffect, inputs.as_ptr(), outputs.as_mut_ptr(), 2);
+            assert_eq!(PROCESS_CALLS.load(Ordering::SeqCst), 1);
+            assert_eq!(output, [0.5, 0.5]);
+            (*aeffect).drop_plugin();
+        }
+    }
+
+    #[test]
+    fn midi_channels() {
+        static MIDI_INPUTS: AtomicI32 = AtomicI32::new(1);
+
+        #[derive(Default)]
+        struct MonoSynth;
+
+        impl Plugin for MonoSynth {
+            fn new(_host: HostCallback) -> Self {
+                MonoSynth
+            }
+
+            fn get_info(&self) -> Info {
+                Info {
+                    midi_inputs: MIDI_INPUTS.load(Ordering::SeqCst),
+                    ..Default::default()
+                }
+            }
+        }
+
+        let aeffect = load::<MonoSynth>();
+        let midi_channels = |opcode: OpCode| dispatch(aeffect, opcode, 0, 0, ptr::null_mut(), 0.0);
+        assert_eq!(midi_channels(OpCode::GetNumMidiInputs), 1);
+        assert_eq!(midi_channels(OpCode::GetNumMidiOutputs), 0);
+
+        MIDI_INPUTS.store(16, Ordering::SeqCst);
+        HostCallback::wrap(pass_callback, aeffect).invalidate_info();
+        dispatch(aeffect, OpCode::StateChanged, 0, 0, ptr::null_mut(), 0.0);
+        assert_eq!(midi_channels(OpCode::GetNumMidiInputs), 16);
+
+        unsafe { (*aeffect).drop_plugin() };
+    }
+
+    #[test]
+    fn failed_construction() {
+        use std::sync::atomic::AtomicBool;
+
+        use buffer::AudioBuffer;
+        use plugin::PluginError;
+
+        static LICENSED: AtomicBool = AtomicBool::new(false);
+
+        struct LicensedPlugin {
+            licensed: bool,
+        }
+
+        impl Plugin for LicensedPlugin {
+            // Without a license, the plugin outputs silence.
+            fn new(_host: HostCallback) -> Self {
+                LicensedPlugin { licensed: false }
+            }
+
+            fn try_new(_host: HostCallback) -> Result<Self, PluginError> {
+                if LICENSED.load(Ordering::SeqCst) {
+                    Ok(LicensedPlugin { licensed: true })
+                } else {
+                    Err(PluginError::new("License file not found"))
+                }
+            }
+
+            fn get_info(&self) -> Info {
+                Info::default()
+            }
+
+            fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
+                for (input, output) in buffer.zip() {
+                    for (in_sample, out_sample) in input.iter().zip(output) {
+                        *out_sample = if self.licensed { *in_sample } else { 0.0 };
+                    }
+                }
+            }
+        }
+
+        assert!(main::<LicensedPlugin>(pass_callback).is_null());
+        assert_eq!(
+            PluginError::new("License file not found").to_string(),
+            "License file not found"
+        );
+
+        LICENSED.store(true, Ordering::SeqCst);
+        let aeffect = main::<LicensedPlugin>(pass_callback);
+        assert!(!aeffect.is_null());
+        unsafe { (*aeffect).drop_plugin() };
     }
 }
@@ -0,0 +1,446 @@
+//! Parameter declarations and display properties.
+//!
+//! `Parameters` is a ready-made `PluginParameters` implementation holding a list of `Parameter`s,
+//! from which `Info::parameters` is taken automatically. `ParameterInfo` describes display
+//! properties, which hosts use to render switches and stepped controls in their generic editor;
+//! not all hosts support them.
+
+use api;
+use api::consts::{MAX_CATEGORY_LABEL_LEN, MAX_LABEL, MAX_SHORT_LABEL};
+use plugin::PluginParameters;
+use util::AtomicFloat;
+
+pub use api::ParameterFlags;
+
+/// Display properties of a parameter, returned from `PluginParameters::get_parameter_info`.
+///
+/// Only the fields matching the set `flags` are used by the host.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterInfo {
+    /// Which of the fields below are valid, and whether the parameter is a switch.
+    pub flags: ParameterFlags,
+
+    /// Parameter label, limited to `MAX_LABEL` bytes.
+    pub label: String,
+    /// Short parameter label, limited to `MAX_SHORT_LABEL` bytes.
+    pub short_label: String,
+
+    /// Integer range, used with `ParameterFlags::USES_INTEGER_MIN_MAX`.
+    pub min_integer: i32,
+    /// See `min_integer`.
+    pub max_integer: i32,
+    /// Integer steps, used with `ParameterFlags::USES_INT_STEP`.
+    pub step_integer: i32,
+    /// See `step_integer`.
+    pub large_step_integer: i32,
+
+    /// Float steps, used with `ParameterFlags::USES_FLOAT_STEP`.
+    pub step_float: f32,
+    /// See `step_float`.
+    pub small_step_float: f32,
+    /// See `step_float`.
+    pub large_step_float: f32,
+
+    /// Index in which the parameter is displayed, used with
+    /// `ParameterFlags::SUPPORTS_DISPLAY_INDEX`.
+    pub display_index: i16,
+
+    /// Category of the parameter (starting at 1), used with
+    /// `ParameterFlags::SUPPORTS_DISPLAY_CATEGORY`.
+    pub category: i16,
+    /// Number of parameters in `category`.
+    pub num_parameters_in_category: i16,
+    /// Label of `category`, limited to `MAX_CATEGORY_LABEL_LEN` bytes.
+    pub category_label: String,
+}
+
+impl Default for ParameterInfo {
+    fn default() -> ParameterInfo {
+        ParameterInfo {
+            flags: ParameterFlags::empty(),
+            label: String::new(),
+            short_label: String::new(),
+            min_integer: 0,
+            max_integer: 0,
+            step_integer: 0,
+            large_step_integer: 0,
+            step_float: 0.0,
+            small_step_float: 0.0,
+            large_step_float: 0.0,
+            display_index: 0,
+            category: 0,
+            num_parameters_in_category: 0,
+            category_label: String::new(),
+        }
+    }
+}
+
+impl ParameterInfo {
+    /// Properties of an on/off switch.
+    pub fn switch() -> ParameterInfo {
+        ParameterInfo {
+            flags: ParameterFlags::IS_SWITCH,
+            ..Default::default()
+        }
+    }
+
+    /// Properties of a parameter with integer values in `min..=max`, e.g. an octave selector.
+    pub fn stepped(min: i32, max: i32) -> ParameterInfo {
+        ParameterInfo {
+            flags: ParameterFlags::USES_INTEGER_MIN_MAX | ParameterFlags::USES_INT_STEP,
+            min_integer: min,
+            max_integer: max,
+            step_integer: 1,
+            large_step_integer: 1,
+            ..Default::default()
+        }
+    }
+}
+
+/// A parameter in a `Parameters` collection, holding its current value.
+#[derive(Debug)]
+pub struct Parameter {
+    name: String,
+    label: String,
+    default: f32,
+    decimals: Option<usize>,
+    info: Option<ParameterInfo>,
+    automatable: bool,
+    value: AtomicFloat,
+}
+
+impl Parameter {
+    /// Automatable parameter named `name`, with the initial value `default` between 0.0 and 1.0.
+    pub fn new(name: &str, default: f32) -> Parameter {
+        Parameter {
+            name: name.to_string(),
+            label: String::new(),
+            default,
+            decimals: None,
+            info: None,
+            automatable: true,
+            value: AtomicFloat::new(default),
+        }
+    }
+
+    /// Set the unit label, e.g. "dB" or "ms", see `PluginParameters::get_parameter_label`.
+    pub fn with_label(mut self, label: &str) -> Parameter {
+        self.label = label.to_string();
+        self
+    }
+
+    /// Set the decimals shown in the host, see `PluginParameters::get_parameter_decimals`.
+    pub fn with_decimals(mut self, decimals: usize) -> Parameter {
+        self.decimals = Some(decimals);
+        self
+    }
+
+    /// Set the display properties, see `PluginParameters::get_parameter_info`.
+    pub fn with_info(mut self, info: ParameterInfo) -> Parameter {
+        self.info = Some(info);
+        self
+    }
+
+    /// Prevent the host from automating this parameter.
+    pub fn not_automatable(mut self) -> Parameter {
+        self.automatable = false;
+        self
+    }
+
+    /// The name of the parameter.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The initial value of the parameter.
+    pub fn default(&self) -> f32 {
+        self.default
+    }
+
+    /// The current value of the parameter.
+    pub fn get(&self) -> f32 {
+        self.value.get()
+    }
+
+    /// Set the current value of the parameter.
+    pub fn set(&self, value: f32) {
+        self.value.set(value)
+    }
+}
+
+/// A fixed list of parameters, implementing `PluginParameters`.
+///
+/// Return this (or a struct delegating to it) from `Plugin::get_parameter_object` to declare
+/// parameters in one place. The library then takes `Info::parameters` from the length of the
+/// list, so the count can't get out of sync with the parameter indices the plugin handles.
+///
+/// ```
+/// use vst::parameter::{Parameter, ParameterInfo, Parameters};
+/// use vst::plugin::PluginParameters;
+///
+/// let params = Parameters::new(vec![
+///     Parameter::new("Gain", 0.5).with_label("dB"),
+///     Parameter::new("Bypass", 0.0).with_info(ParameterInfo::switch()),
+/// ]);
+/// assert_eq!(params.parameter_count(), Some(2));
+///
+/// params.set_parameter(0, 0.75);
+/// assert_eq!(params[0].get(), 0.75);
+/// ```
+#[derive(Debug)]
+pub struct Parameters {
+    parameters: Vec<Parameter>,
+}
+
+impl Parameters {
+    /// Collection of `parameters`, with the indices used by the host in list order.
+    pub fn new(parameters: Vec<Parameter>) -> Parameters {
+        Parameters { parameters }
+    }
+
+    /// The number of parameters, as reported to the host.
+    pub fn count(&self) -> i32 {
+        self.parameters.len() as i32
+    }
+
+    /// The parameter at `index`, or `None` if `index` is out of range.
+    pub fn get(&self, index: i32) -> Option<&Parameter> {
+        if index < 0 {
+            return None;
+        }
+        self.parameters.get(index as usize)
+    }
+
+    /// Iterate over all parameters, in index order.
+    pub fn iter(&self) -> ::std::slice::Iter<'_, Parameter> {
+        self.parameters.iter()
+    }
+}
+
+impl ::std::ops::Index<usize> for Parameters {
+    type Output = Parameter;
+
+    fn index(&self, index: usize) -> &Parameter {
+        &self.parameters[index]
+    }
+}
+
+impl PluginParameters for Parameters {
+    fn parameter_count(&self) -> Option<i32> {
+        Some(self.count())
+    }
+
+    fn get_parameter_label(&self, index: i32) -> String {
+        self.get(index).map(|p| p.label.clone()).unwrap_or_default()
+    }
+
+    fn get_parameter_decimals(&self, index: i32) -> Option<usize> {
+        self.get(index).and_then(|p| p.decimals)
+    }
+
+    fn get_parameter_name(&self, index: i32) -> String {
+        self.get(index).map(|p| p.name.clone()).unwrap_or_default()
+    }
+
+    fn get_parameter_info(&self, index: i32) -> Option<ParameterInfo> {
+        self.get(index).and_then(|p| p.info.clone())
+    }
+
+    fn get_parameter(&self, index: i32) -> f32 {
+        self.get(index).map_or(0.0, Parameter::get)
+    }
+
+    fn set_parameter(&self, index: i32, value: f32) {
+        if let Some(parameter) = self.get(index) {
+            parameter.set(value);
+        }
+    }
+
+    fn can_be_automated(&self, index: i32) -> bool {
+        self.get(index).map(|p| p.automatable).unwrap_or(false)
+    }
+}
+
+/// Format a parameter value for display, e.g. in the default `PluginParameters::get_parameter_text`.
+///
+/// With `Some(decimals)`, the value is shown with exactly that many decimals. With `None`, the
+/// number of decimals adapts to the magnitude of the value: three below 1, two below 10, one
+/// below 100 and none from 100 on. Trailing zeros are left out, so 0.5 shows as "0.5", 12.345
+/// as "12.3" and 1250.0 as "1250".
+///
+/// ```
+/// use vst::parameter::format_value;
+///
+/// assert_eq!(format_value(0.5, None), "0.5");
+/// assert_eq!(format_value(0.123_45, None), "0.123");
+/// assert_eq!(format_value(12.345, None), "12.3");
+/// assert_eq!(format_value(0.5, Some(2)), "0.50");
+/// ```
+pub fn format_value(value: f32, decimals: Option<usize>) -> String {
+    match decimals {
+        Some(decimals) => format!("{:.*}", decimals, value),
+        None => {
+            let magnitude = value.abs();
+            let decimals = if magnitude >= 100.0 {
+                0
+            } else if magnitude >= 10.0 {
+                1
+            } else if magnitude >= 1.0 {
+                2
+            } else {
+                3
+            };
+            let text = format!("{:.*}", decimals, value);
+            if text.contains('.') {
+                let text = text.trim_end_matches('0').trim_end_matches('.');
+                // Don't show tiny negative values as "-0".
+                if text == "-0" {
+                    "0".to_string()
+                } else {
+                    text.to_string()
+                }
+            } else {
+                text
+            }
+        }
+    }
+}
+
+/// Copy `src` into `dst`, leaving room for a null terminator.
+fn copy_label(dst: &mut [u8], src: &str) {
+    let max = dst.len() - 1;
+    for (c, b) in dst[..max].iter_mut().zip(src.bytes()) {
+        *c = b;
+    }
+}
+
+fn read_label(label: &[u8]) -> String {
+    let end = label.iter().position(|&b| b == 0).unwrap_or(label.len());
+    String::from_utf8_lossy(&label[..end]).into_owned()
+}
+
+impl From<ParameterInfo> for api::ParameterProperties {
+    /// Convert to the VST api equivalent of this structure.
+    fn from(info: ParameterInfo) -> api::ParameterProperties {
+        let mut props = api::ParameterProperties {
+            step_float: info.step_float,
+            small_step_float: info.small_step_float,
+            large_step_float: info.large_step_float,
+            label: [0; MAX_LABEL],
+            flags: info.flags.bits(),
+            min_integer: info.min_integer,
+            max_integer: info.max_integer,
+            step_integer: info.step_integer,
+            large_step_integer: info.large_step_integer,
+            short_label: [0; MAX_SHORT_LABEL],
+            display_index: info.display_index,
+            category: info.category,
+            num_parameters_in_category: info.num_parameters_in_category,
+            _reserved: 0,
+            category_label: [0; MAX_CATEGORY_LABEL_LEN],
+            future: [0; 16],
+        };
+        copy_label(&mut props.label, &info.label);
+        copy_label(&mut props.short_label, &info.short_label);
+        copy_label(&mut props.category_label, &info.category_label);
+        props
+    }
+}
+
+impl From<api::ParameterProperties> for ParameterInfo {
+    fn from(api: api::ParameterProperties) -> ParameterInfo {
+        ParameterInfo {
+            flags: ParameterFlags::from_bits_truncate(api.flags),
+            label: read_label(&api.label),
+            short_label: read_label(&api.short_label),
+            min_integer: api.min_integer,
+            max_integer: api.max_integer,
+            step_integer: api.step_integer,
+            large_step_integer: api.large_step_integer,
+            step_float: api.step_float,
+            small_step_float: api.small_step_float,
+            large_step_float: api.large_step_float,
+            display_index: api.display_index,
+            category: api.category,
+            num_parameters_in_category: api.num_parameters_in_category,
+            category_label: read_label(&api.category_label),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use api;
+    use parameter::{format_value, Parameter, ParameterFlags, ParameterInfo, Parameters};
+    use plugin::PluginParameters;
+
+    #[test]
+    fn format_value_precision() {
+        assert_eq!(format_value(0.0, None), "0");
+        assert_eq!(format_value(1.0, None), "1");
+        assert_eq!(format_value(0.25, None), "0.25");
+        assert_eq!(format_value(-0.0001, None), "0");
+        assert_eq!(format_value(-3.14159, None), "-3.14");
+        assert_eq!(format_value(440.0, None), "440");
+        assert_eq!(format_value(19_999.6, None), "20000");
+
+        assert_eq!(format_value(0.5, Some(0)), "0");
+        assert_eq!(format_value(440.0, Some(1)), "440.0");
+        assert_eq!(format_value(0.123_45, Some(4)), "0.1235");
+    }
+
+    #[test]
+    fn round_trip() {
+        let info = ParameterInfo {
+            label: "Octave".to_string(),
+            short_label: "a very long short label".to_string(),
+            category: 1,
+            num_parameters_in_category: 2,
+            category_label: "Osc 1".to_string(),
+            ..ParameterInfo::stepped(-2, 2)
+        };
+        let props: api::ParameterProperties = info.clone().into();
+        assert_eq!(
+            props.flags,
+            (ParameterFlags::USES_INTEGER_MIN_MAX | ParameterFlags::USES_INT_STEP).bits()
+        );
+        assert_eq!(props.short_label[7], 0);
+
+        let back = ParameterInfo::from(props);
+        assert_eq!(back.short_label, "a very ");
+        assert_eq!(
+            back,
+            ParameterInfo {
+                short_label: "a very ".to_string(),
+                ..info
+            }
+        );
+    }
+
+    #[test]
+    fn parameters() {
+        let params = Parameters::new(vec![
+            Parameter::new("Gain", 0.5).with_label("dB").with_decimals(1),
+            Parameter::new("Mode", 0.0)
+                .with_info(ParameterInfo::stepped(0, 3))
+                .not_automatable(),
+        ]);
+        assert_eq!(params.parameter_count(), Some(2));
+        assert_eq!(params.get_parameter_name(1), "Mode");
+        assert_eq!(params.get_parameter_label(0), "dB");
+        assert_eq!(params.get_parameter_text(0), "0.5");
+        assert_eq!(params.get_parameter_info(1), Some(ParameterInfo::stepped(0, 3)));
+        assert!(params.can_be_automated(0) && !params.can_be_automated(1));
+
+        params.set_parameter(1, 0.25);
+        assert_eq!(params[1].get(), 0.25);
+        assert_eq!(params[1].default(), 0.0);
+
+        // Out of range indices are ignored.
+        params.set_parameter(2, 1.0);
+        params.set_parameter(-1, 1.0);
+        assert_eq!(params.get_parameter(2), 0.0);
+        assert_eq!(params.get_parameter_name(2), "");
+        assert!(params.get(-1).is_none());
+    }
+}
@@ -1,23 +1,37 @@
 //! Plugin specific structures.
 
+use std::error::Error;
+use std::ffi::CString;
+use std::fmt;
 use std::os::raw::c_void;
+use std::path::PathBuf;
 use std::ptr;
+use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Arc;
 
+use num_traits::Float;
+
 use api;
-use api::consts::VST_MAGIC;
-use api::{AEffect, HostCallbackProc, Supported, TimeInfo};
+use api::consts::{MAX_PATH_LEN, VST_MAGIC};
+use api::{
+    AEffect, AutomationState, HostCallbackProc, SpeakerArrangement, SpeakerArrangementType, Supported, TimeInfo,
+};
 use buffer::AudioBuffer;
-use channels::ChannelInfo;
+use cache::PluginCache;
+use channels::{self, ChannelInfo};
 use editor::Editor;
 use host::{self, Host};
+use parameter::{self, ParameterInfo};
+use process::ProcessContext;
+#[cfg(feature = "profiling")]
+use profiling::ProcessStats;
 
 /// Plugin type. Generally either Effect or Synth.
 ///
 /// Other types are not necessary to build a plugin and are only useful for the host to categorize
 /// the plugin.
 #[repr(usize)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Category {
     /// Unknown / not implemented
     Unknown,
@@ -38,13 +52,19 @@ pub enum Category {
     /// Denoiser, etc.
     Restoration,
     /// Offline processing.
+    ///
+    /// For plugins which can't run in real time, e.g. analysis or mastering tools that need the
+    /// whole file. Hosts list them in their offline processing menus. VST 2.4 has no plugin flag
+    /// for this, so besides the category the plugin answers the `"noRealTime"` `can_do` query
+    /// with `Yes` by default. Variable length processing (e.g. time stretching) is done in
+    /// `Plugin::process_var_io`.
     OfflineProcess,
     /// Contains other plugins.
     Shell,
     /// Tone generator, etc.
     Generator,
 }
-impl_clike!(Category);
+impl_clike!(Category; last = Generator);
 
 #[repr(usize)]
 #[derive(Clone, Copy, Debug)]
@@ -199,7 +219,7 @@ pub enum OpCode {
     /// [ptr]: "Can do" string.
     /// [return]: 1 = yes, 0 = maybe, -1 = no.
     CanDo,
-    /// [return]: tail size (e.g. reverb time). 0 is defualt, 1 means no tail.
+    /// [return]: tail size (e.g. reverb time). 0 is default (unknown), 1 means no tail.
     GetTailSize,
 
     /// Deprecated.
@@ -210,12 +230,12 @@ pub enum OpCode {
     _SetVewPosition,
 
     /// [index]: param index
-    /// [ptr]: `*mut VstParamInfo` //TODO: Implement
+    /// [ptr]: `*mut ParameterProperties`
     /// [return]: 1 if supported
     GetParamInfo,
 
-    /// Deprecated.
-    _KeysRequired,
+    /// [return]: 0 if the editor needs keyboard input, 1 if it doesn't.
+    KeysRequired,
 
     /// [return]: 2400 for vst 2.4.
     GetApiVersion,
@@ -249,7 +269,7 @@ pub enum OpCode {
     /// [return]: 1 if `MidiProgramName` or `MidiKeyName` has changed. //TODO: Implement
     HasMidiProgramsChanged,
     /// [index]: MIDI channel.
-    /// [ptr]: `*mut MidiKeyName`. //TODO: Implement
+    /// [ptr]: `*mut MidiKeyName`.
     /// [return]: 1 = supported 0 = not.
     GetMidiKeyName,
 
@@ -273,7 +293,7 @@ pub enum OpCode {
     StopProcess,
     /// [value]: number of samples to process. Called in offline mode before process.
     SetTotalSampleToProcess,
-    /// [value]: pan law `PanLaw`. //TODO: Implement
+    /// [value]: pan law `PanLaw`.
     /// [opt]: gain.
     SetPanLaw,
 
@@ -292,7 +312,7 @@ pub enum OpCode {
     /// [return]: number of used MIDI Outputs (1-15).
     GetNumMidiOutputs,
 }
-impl_clike!(OpCode);
+impl_clike!(OpCode; last = GetNumMidiOutputs);
 
 /// A structure representing static plugin information.
 #[derive(Clone, Debug)]
@@ -303,10 +323,19 @@ pub struct Info {
     /// Plugin Vendor.
     pub vendor: String,
 
+    /// Product name, e.g. of a plugin suite shared by several effects. Reported to the host
+    /// separately from `name`.
+    ///
+    /// Default is `None`, which reports `name` as the product name.
+    pub product: Option<String>,
+
     /// Number of different presets.
     pub presets: i32,
 
     /// Number of parameters.
+    ///
+    /// This is overridden by the count of the parameter object if it reports one through
+    /// `PluginParameters::parameter_count`, as `parameter::Parameters` does.
     pub parameters: i32,
 
     /// Number of inputs.
@@ -315,12 +344,34 @@ pub struct Info {
     /// Number of outputs.
     pub outputs: i32,
 
+    /// Alternative `(inputs, outputs)` channel configurations the plugin supports besides
+    /// `inputs` and `outputs`, reported to hosts probing them with `can_do` strings like
+    /// `"1in1out"`. Switch to one by changing `inputs` and `outputs` and calling
+    /// `HostCallback::invalidate_info`.
+    ///
+    /// Default is empty.
+    pub io_configs: Vec<(i32, i32)>,
+
     /// Number of MIDI input channels (1-16), or 0 for the default of 16 channels.
+    ///
+    /// Reported to hosts asking for the number of MIDI channels. To change it at runtime, call
+    /// `HostCallback::invalidate_info`.
     pub midi_inputs: i32,
 
-    /// Number of MIDI output channels (1-16), or 0 for the default of 16 channels.
+    /// Number of MIDI output channels (1-16), or 0 for the default of 16 channels. See
+    /// `midi_inputs`.
     pub midi_outputs: i32,
 
+    /// MIDI CCs mapped to parameters, as `(controller, parameter index)` pairs.
+    ///
+    /// Incoming control change messages for a mapped controller (on any channel) set the
+    /// parameter through `PluginParameters::set_parameter` before they are passed on to
+    /// `Plugin::process_events`. CC values from 0 to 127 are normalized to `0.0..=1.0`. Note that
+    /// VST 2.4 has no way to report the mapping to the host.
+    ///
+    /// Default is empty.
+    pub midi_cc_parameters: Vec<(u8, i32)>,
+
     /// Unique plugin ID. Can be registered with Steinberg to prevent conflicts with other plugins.
     ///
     /// This ID is used to identify a plugin during save and load of a preset and project.
@@ -346,6 +397,9 @@ pub struct Info {
 
     /// Indicates whether this plugin can process f64 based `AudioBuffer` buffers.
     ///
+    /// Only set this if the plugin implements `Plugin::process_f64`, otherwise hosts processing
+    /// in double precision get the unprocessed input. Debug builds log a warning for that.
+    ///
     /// Default is `false`.
     pub f64_precision: bool,
 
@@ -353,6 +407,41 @@ pub struct Info {
     ///
     /// Default is `false`.
     pub silent_when_stopped: bool,
+
+    /// Fixed length of the plugin's tail in samples (e.g. reverb time), reported to the host when
+    /// `Plugin::get_tail_size` returns 0.
+    ///
+    /// Default is `0` (no tail).
+    pub tail_samples: i32,
+
+    /// Process through `Plugin::process_ctx` (and `process_ctx_f64`) instead of
+    /// `Plugin::process_events` and `Plugin::process`.
+    ///
+    /// Default is `false`.
+    pub process_context: bool,
+
+    /// After the host changes the preset, tell the host to update its display (e.g. its generic
+    /// editor) through `Host::update_display`, and an open editor through
+    /// `Editor::preset_changed`.
+    ///
+    /// This is opt-in, as plugins may already do this in `PluginParameters::change_preset`, and
+    /// some hosts query all parameters again from within `update_display`. Default is `false`.
+    pub refresh_on_preset_change: bool,
+
+    /// Let the library handle the host's soft bypass: while bypassed, the inputs are passed
+    /// through delayed by the plugin latency (`initial_delay`, or the value set through
+    /// `HostCallback::set_latency`) instead of calling `process`, with a crossfade of 256 samples
+    /// when switching. `Plugin::set_bypass` is still called. `CanDo::Bypass` is answered with
+    /// `Yes` by default.
+    ///
+    /// Default is `false`.
+    pub soft_bypass: bool,
+
+    /// The plugin calls `Host::get_time_info`. `CanDo::ReceiveTimeInfo` is answered with `Yes` by
+    /// default, as some hosts don't provide time info to plugins that don't ask for it.
+    ///
+    /// Default is `false`.
+    pub uses_time_info: bool,
 }
 
 impl Default for Info {
@@ -360,14 +449,17 @@ impl Default for Info {
         Info {
             name: "VST".to_string(),
             vendor: String::new(),
+            product: None,
 
             presets: 1, // default preset
             parameters: 0,
             inputs: 2, // Stereo in,out
             outputs: 2,
+            io_configs: Vec::new(),
 
             midi_inputs: 0,
             midi_outputs: 0,
+            midi_cc_parameters: Vec::new(),
 
             unique_id: 0, // This must be changed.
             version: 1,   // v0.0.0.1
@@ -379,6 +471,158 @@ impl Default for Info {
             preset_chunks: false,
             f64_precision: false,
             silent_when_stopped: false,
+
+            tail_samples: 0,
+
+            process_context: false,
+            refresh_on_preset_change: false,
+            soft_bypass: false,
+            uses_time_info: false,
+        }
+    }
+}
+
+impl Info {
+    /// Pack a four character code like `"MyPl"` into a `unique_id`, the way VST IDs are
+    /// conventionally written (and `consts::VST_MAGIC` is built from `"VstP"`).
+    ///
+    /// ```
+    /// use vst::plugin::Info;
+    ///
+    /// const UNIQUE_ID: i32 = Info::id_from_str("MyPl");
+    /// assert_eq!(UNIQUE_ID, 0x4d79_506c);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics (at compile time when used in a constant) if `id` isn't exactly four bytes long.
+    pub const fn id_from_str(id: &str) -> i32 {
+        let bytes = id.as_bytes();
+        assert!(bytes.len() == 4, "Unique IDs are packed from exactly four characters");
+        (bytes[0] as i32) << 24 | (bytes[1] as i32) << 16 | (bytes[2] as i32) << 8 | (bytes[3] as i32)
+    }
+
+    /// Take `parameters` from `params`, if it reports its own count.
+    pub(crate) fn sync_parameter_count(&mut self, params: &dyn PluginParameters) {
+        if let Some(count) = params.parameter_count() {
+            #[cfg(debug_assertions)]
+            {
+                if self.parameters != 0 && self.parameters != count {
+                    warn!(
+                        "\"{}\" declares {} parameters but its parameter object has {}",
+                        self.name, self.parameters, count
+                    );
+                }
+            }
+            self.parameters = count;
+        }
+    }
+
+    /// Check this `Info` for common mistakes which may cause hosts to reject the plugin.
+    ///
+    /// This is called in debug builds when the plugin is loaded, logging any problem found.
+    pub fn validate(&self) -> Result<(), InfoError> {
+        if self.unique_id == 0 {
+            return Err(InfoError::ZeroUniqueId);
+        }
+        if self.name.is_empty() {
+            return Err(InfoError::EmptyName);
+        }
+        if self.presets < 0 || self.parameters < 0 || self.inputs < 0 || self.outputs < 0 {
+            return Err(InfoError::NegativeCount);
+        }
+        if !(0..=16).contains(&self.midi_inputs) || !(0..=16).contains(&self.midi_outputs) {
+            return Err(InfoError::InvalidMidiChannels);
+        }
+        if self.preset_chunks && self.presets == 0 {
+            return Err(InfoError::ChunksWithoutPresets);
+        }
+        Ok(())
+    }
+}
+
+/// Problems found by `Info::validate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InfoError {
+    /// `unique_id` is 0, which will collide with other plugins that did not set an ID.
+    ZeroUniqueId,
+
+    /// `name` is empty.
+    EmptyName,
+
+    /// `presets`, `parameters`, `inputs` or `outputs` is negative.
+    NegativeCount,
+
+    /// `midi_inputs` or `midi_outputs` is not in the range 0-16.
+    InvalidMidiChannels,
+
+    /// `preset_chunks` is set but `presets` is 0.
+    ChunksWithoutPresets,
+}
+
+impl fmt::Display for InfoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::InfoError::*;
+        let description = match self {
+            ZeroUniqueId => "The unique ID is 0 and may collide with other plugins",
+            EmptyName => "The plugin name is empty",
+            NegativeCount => "The number of presets, parameters, inputs or outputs is negative",
+            InvalidMidiChannels => "The number of MIDI input or output channels is not between 0 and 16",
+            ChunksWithoutPresets => "Preset chunks are enabled but the plugin has no presets",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+impl Error for InfoError {}
+
+/// Reason why a plugin couldn't be created, returned from `Plugin::try_new`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PluginError {
+    message: String,
+}
+
+impl PluginError {
+    /// Error with a message for the log, e.g. "License file not found".
+    pub fn new<S: Into<String>>(message: S) -> PluginError {
+        PluginError {
+            message: message.into(),
+        }
+    }
+
+    /// The message of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for PluginError {}
+
+/// Tail length of a plugin, for returning from `Plugin::get_tail_size`.
+///
+/// In VST 2.4, a tail size of 0 means that the tail length is unknown, which makes some hosts
+/// keep processing the plugin for as long as it is loaded, and 1 means that there is no tail.
+/// This library never reports 0: plugins without a tail report 1, so hosts can stop processing
+/// them (and save the CPU) once their input is silent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TailSize {
+    /// No tail, the output is silent as soon as the input is. Reported as 1.
+    None,
+    /// A tail of this many samples, e.g. the decay time of a reverb. `Samples(0)` is the same as
+    /// `None`.
+    Samples(usize),
+}
+
+impl From<TailSize> for isize {
+    fn from(tail_size: TailSize) -> isize {
+        match tail_size {
+            TailSize::None | TailSize::Samples(0) => 1,
+            TailSize::Samples(samples) => samples as isize,
         }
     }
 }
@@ -395,12 +639,20 @@ pub enum CanDo {
     Offline,
     MidiProgramNames,
     Bypass,
+    /// The plugin can't process in real time, see `Category::OfflineProcess`.
+    NoRealTime,
     ReceiveSysExEvent,
 
     //Bitwig specific?
     MidiSingleNoteTuningChange,
     MidiKeyBasedInstrumentControl,
 
+    /// A channel configuration, e.g. `"2in2out"`.
+    Channels {
+        inputs: i32,
+        outputs: i32,
+    },
+
     Other(String),
 }
 
@@ -421,15 +673,56 @@ impl CanDo {
             "offline" => Offline,
             "midiProgramNames" => MidiProgramNames,
             "bypass" => Bypass,
+            "noRealTime" => NoRealTime,
 
             "receiveVstSysexEvent" => ReceiveSysExEvent,
             "midiSingleNoteTuningChange" => MidiSingleNoteTuningChange,
             "midiKeyBasedInstrumentControl" => MidiKeyBasedInstrumentControl,
-            otherwise => Other(otherwise.to_string()),
+            otherwise => match parse_channels(otherwise) {
+                Some((inputs, outputs)) => Channels { inputs, outputs },
+                None => Other(otherwise.to_string()),
+            },
+        }
+    }
+
+    /// Whether a plugin with `info` supports this, if it doesn't override `Plugin::can_do`.
+    ///
+    /// Returns `No` for features this library has no interface for (offline processing and MIDI
+    /// program names), and `Maybe` otherwise. MIDI support can't be derived from `Info`, since 0
+    /// MIDI channels means the default of 16, so plugins without MIDI should answer `No` for the
+    /// MIDI capabilities themselves.
+    ///
+    /// Channel configurations are answered with `Yes` if they match `Info::inputs` and
+    /// `Info::outputs` or one of `Info::io_configs`, and `No` otherwise. `NoRealTime` is answered
+    /// with `Yes` for plugins in `Category::OfflineProcess`, `Bypass` for plugins setting
+    /// `Info::soft_bypass`, and `ReceiveTimeInfo` for plugins setting `Info::uses_time_info`.
+    pub fn default_support(&self, info: &Info) -> Supported {
+        use self::CanDo::*;
+
+        match *self {
+            Offline | MidiProgramNames => Supported::No,
+            NoRealTime if info.category == Category::OfflineProcess => Supported::Yes,
+            Bypass if info.soft_bypass => Supported::Yes,
+            ReceiveTimeInfo if info.uses_time_info => Supported::Yes,
+            Channels { inputs, outputs } => {
+                let config = (inputs, outputs);
+                if config == (info.inputs, info.outputs) || info.io_configs.contains(&config) {
+                    Supported::Yes
+                } else {
+                    Supported::No
+                }
+            }
+            _ => Supported::Maybe,
         }
     }
 }
 
+/// Parse a channel configuration `can_do` string like `"2in2out"`.
+fn parse_channels(s: &str) -> Option<(i32, i32)> {
+    let (inputs, outputs) = s.strip_suffix("out")?.split_once("in")?;
+    Some((inputs.parse().ok()?, outputs.parse().ok()?))
+}
+
 impl Into<String> for CanDo {
     fn into(self) -> String {
         use self::CanDo::*;
@@ -443,10 +736,12 @@ impl Into<String> for CanDo {
             Offline => "offline".to_string(),
             MidiProgramNames => "midiProgramNames".to_string(),
             Bypass => "bypass".to_string(),
+            NoRealTime => "noRealTime".to_string(),
 
             ReceiveSysExEvent => "receiveVstSysexEvent".to_string(),
             MidiSingleNoteTuningChange => "midiSingleNoteTuningChange".to_string(),
             MidiKeyBasedInstrumentControl => "midiKeyBasedInstrumentControl".to_string(),
+            Channels { inputs, outputs } => format!("{}in{}out", inputs, outputs),
             Other(other) => other,
         }
     }
@@ -469,9 +764,16 @@ impl Into<String> for CanDo {
 /// processing thread. For this reason, the plugin API is separated into two
 /// traits: The `Plugin` trait containing setup and processing methods, and
 /// the `PluginParameters` trait containing methods for parameter access.
+///
+/// The processing thread calls `process`, `process_f64`, `process_ctx`, `process_ctx_f64` and
+/// `process_events`. All other `Plugin` methods, and the `Editor` returned from `get_editor`, are
+/// called on the UI thread. `PluginParameters` methods can be called on either thread.
 #[allow(unused_variables)]
 pub trait Plugin: Send {
     /// This method must return an `Info` struct.
+    ///
+    /// It is called once when the plugin is created and the result is cached for answering host
+    /// queries. Plugins whose `Info` changes later on should call `HostCallback::invalidate_info`.
     fn get_info(&self) -> Info;
 
     /// Called during initialization to pass a `HostCallback` to the plugin.
@@ -513,10 +815,66 @@ pub trait Plugin: Send {
     ///
     /// # fn main() {}
     /// ```
+    ///
+    /// Plugins whose construction can fail should override `try_new` instead.
     fn new(host: HostCallback) -> Self
     where
         Self: Sized;
 
+    /// Create the plugin, or fail with the reason why it can't be loaded, e.g. a failed license
+    /// check or missing resources.
+    ///
+    /// This is what the library calls when the host loads the plugin. On error, the reason is
+    /// logged and the host is told that the plugin couldn't be created, instead of panicking
+    /// across the FFI boundary. The default calls `new`.
+    ///
+    /// Plugins overriding this still implement `new`, which the library then doesn't call. Have
+    /// it create the plugin in a state without the resources that can fail to load, such as an
+    /// empty sample set which outputs silence, so that it is still a working plugin.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate vst;
+    /// # use vst::plugin::{Info, Plugin};
+    /// use vst::plugin::{HostCallback, PluginError};
+    ///
+    /// struct Sampler {
+    ///     samples: Vec<f32>,
+    /// }
+    ///
+    /// # fn load_samples() -> Option<Vec<f32>> { Some(vec![0.0; 64]) }
+    /// impl Plugin for Sampler {
+    ///     // Without samples, the plugin plays nothing.
+    ///     fn new(_host: HostCallback) -> Sampler {
+    ///         Sampler { samples: Vec::new() }
+    ///     }
+    ///
+    ///     fn try_new(_host: HostCallback) -> Result<Sampler, PluginError> {
+    ///         match load_samples() {
+    ///             Some(samples) => Ok(Sampler { samples }),
+    ///             None => Err(PluginError::new("Sample library not found")),
+    ///         }
+    ///     }
+    ///
+    ///     // ...
+    /// #     fn get_info(&self) -> Info {
+    /// #         Info {
+    /// #             name: "Sampler".to_string(),
+    /// #             ..Default::default()
+    /// #         }
+    /// #     }
+    /// }
+    ///
+    /// # fn main() {}
+    /// ```
+    fn try_new(host: HostCallback) -> Result<Self, PluginError>
+    where
+        Self: Sized,
+    {
+        Ok(Self::new(host))
+    }
+
     /// Called when plugin is fully initialized.
     ///
     /// This method is only called while the plugin is in the *suspended* state.
@@ -526,40 +884,101 @@ pub trait Plugin: Send {
 
     /// Called when sample rate is changed by host.
     ///
-    /// This method is only called while the plugin is in the *suspended* state.
+    /// This method is only called while the plugin is in the *suspended* state. Zero, negative and
+    /// absurdly high rates sent by the host are filtered out before reaching the plugin, as are
+    /// rates not in `supported_sample_rates`.
     fn set_sample_rate(&mut self, rate: f32) {}
 
+    /// Return the sample rates the plugin can run at, e.g. because it uses precomputed
+    /// coefficient tables. Other rates sent by the host are logged and not passed to
+    /// `set_sample_rate`. VST 2.4 has no way to report them to the host.
+    ///
+    /// Default is `None`, meaning any rate.
+    fn supported_sample_rates(&self) -> Option<Vec<f32>> {
+        None
+    }
+
     /// Called when block size is changed by host.
     ///
-    /// This method is only called while the plugin is in the *suspended* state.
+    /// This method is only called while the plugin is in the *suspended* state. Sizes that are
+    /// zero, negative or larger than 2^24 samples are filtered out before reaching the plugin.
     fn set_block_size(&mut self, size: i64) {}
 
+    /// Called when the host informs the plugin of the pan law of the project, so panners can
+    /// match the gain staging of the host's own panners. `gain` is the linear gain of a signal
+    /// panned to the center, e.g. about 0.707 (-3 dB) for an equal power pan law.
+    ///
+    /// Unknown pan laws sent by the host are ignored.
+    fn set_pan_law(&mut self, law: api::PanLaw, gain: f32) {}
+
     /// Called to transition the plugin into the *resumed* state.
+    ///
+    /// Processing starts after this. State from before the last suspend should already have been
+    /// cleared in `reset`.
     fn resume(&mut self) {}
 
     /// Called to transition the plugin into the *suspended* state.
+    ///
+    /// This is called through `reset`, so plugins overriding `reset` are responsible for calling
+    /// it themselves if needed.
     fn suspend(&mut self) {}
 
+    /// Called when the host suspends the plugin. Clear all state that produces audio without
+    /// input here, e.g. delay lines, reverb tails and playing voices, so no stale audio is played
+    /// on the next `resume`.
+    ///
+    /// Default calls `suspend`.
+    fn reset(&mut self) {
+        self.suspend();
+    }
+
     /// Vendor specific handling.
+    ///
+    /// See `util::VendorSpecificHandlers` for handling known messages with typed handlers.
     fn vendor_specific(&mut self, index: i32, value: isize, ptr: *mut c_void, opt: f32) -> isize {
         0
     }
 
     /// Return whether plugin supports specified action.
     ///
-    /// This method is only called while the plugin is in the *suspended* state.
+    /// The default implementation answers with `CanDo::default_support`, e.g. `No` for offline
+    /// processing. `Info` is only queried for the capabilities declared through it (channel
+    /// configurations, `NoRealTime`, `Bypass` and `ReceiveTimeInfo`). Override this to answer
+    /// differently, including returning `Supported::Maybe` for edge cases.
+    ///
+    /// This method is only called while the plugin is in the *suspended* state. The answers for
+    /// the standard capabilities (all `CanDo` variants except `Channels` and `Other`) are cached,
+    /// as hosts tend to ask repeatedly; call `HostCallback::invalidate_info` to have them queried
+    /// again after they changed.
     fn can_do(&self, can_do: CanDo) -> Supported {
+        use self::CanDo::*;
+
         info!("Host is asking if plugin can: {:?}.", can_do);
-        Supported::Maybe
+        match can_do {
+            NoRealTime | Bypass | ReceiveTimeInfo | Channels { .. } => can_do.default_support(&self.get_info()),
+            Offline | MidiProgramNames => Supported::No,
+            _ => Supported::Maybe,
+        }
     }
 
     /// Get the tail size of plugin when it is stopped. Used in offline processing as well.
+    ///
+    /// Plugins with a fixed tail can set `Info::tail_samples` instead. A non-zero value returned
+    /// here takes precedence over it. Tail state should be cleared in `reset`.
+    ///
+    /// Return a `TailSize` converted with `into()`. The default of 0 falls back to
+    /// `Info::tail_samples`, which reports "no tail" (`TailSize::None`) to the host if it is 0 as
+    /// well, so effects without a tail don't need to implement this.
     fn get_tail_size(&self) -> isize {
         0
     }
 
     /// Process an audio buffer containing `f32` values.
     ///
+    /// Blocks contain at least one sample. Some hosts process zero samples, e.g. while probing the
+    /// plugin, which the library skips instead of calling this method, so dividing by
+    /// `buffer.samples()` is safe.
+    ///
     /// # Example
     /// ```no_run
     /// # use vst::plugin::{HostCallback, Info, Plugin};
@@ -591,18 +1010,22 @@ pub trait Plugin: Send {
     /// ```
     ///
     /// This method is only called while the plugin is in the *resumed* state.
+    ///
+    /// Plugins only need to implement `process` (and `process_f64` if supported). Legacy hosts
+    /// which only use the deprecated accumulating process call are handled by this library, which
+    /// calls `process` and adds the result to the host's output buffers.
+    ///
+    /// The default implementation passes the audio through: the first `min(inputs, outputs)`
+    /// channels are copied and any extra outputs are silenced, so it works for every channel
+    /// configuration.
     fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
-        // For each input and output
-        for (input, output) in buffer.zip() {
-            // For each input sample and output sample in buffer
-            for (in_frame, out_frame) in input.iter().zip(output.iter_mut()) {
-                *out_frame = *in_frame;
-            }
-        }
+        pass_through(buffer);
     }
 
     /// Process an audio buffer containing `f64` values.
     ///
+    /// Like `process`, this is never called with an empty block.
+    ///
     /// # Example
     /// ```no_run
     /// # use vst::plugin::{HostCallback, Info, Plugin};
@@ -634,14 +1057,30 @@ pub trait Plugin: Send {
     /// ```
     ///
     /// This method is only called while the plugin is in the *resumed* state.
+    ///
+    /// The default implementation passes the audio through like `process`. Debug builds warn
+    /// the first time it is called, as hosts only call this for plugins setting
+    /// `Info::f64_precision`, which would then pass their audio through unprocessed.
     fn process_f64(&mut self, buffer: &mut AudioBuffer<f64>) {
-        // For each input and output
-        for (input, output) in buffer.zip() {
-            // For each input sample and output sample in buffer
-            for (in_frame, out_frame) in input.iter().zip(output.iter_mut()) {
-                *out_frame = *in_frame;
+        #[cfg(debug_assertions)]
+        {
+            if !DEFAULT_PROCESS_F64_CALLED.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "`Info::f64_precision` is set but `Plugin::process_f64` is not implemented, passing audio through"
+                );
             }
         }
+        pass_through(buffer);
+    }
+
+    /// Process audio with differing input and output lengths, e.g. for offline time stretching.
+    ///
+    /// Sent by hosts with offline processing for plugins in `Category::OfflineProcess`. Set
+    /// `num_samples_input_processed` and `num_samples_output_processed` to the number of samples
+    /// consumed and written, and return `true` if the buffers were processed. The default
+    /// implementation returns `false`.
+    fn process_var_io(&mut self, io: &mut api::VariableIo) -> bool {
+        false
     }
 
     /// Handle incoming events sent from the host.
@@ -651,36 +1090,127 @@ pub trait Plugin: Send {
     /// This method is only called while the plugin is in the *resumed* state.
     fn process_events(&mut self, events: &api::Events) {}
 
+    /// Process a block of audio together with its time info and events.
+    ///
+    /// Only called instead of `process_events` and `process` if `Info::process_context` is set.
+    /// The time info is queried from the host for every block, and the events received since the
+    /// previous block are kept until this is called, including across empty blocks, which are
+    /// skipped like for `process`. The default implementation ignores the events and calls
+    /// `process`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use vst::plugin::{HostCallback, Info, Plugin};
+    /// # use vst::event::Event;
+    /// # use vst::process::ProcessContext;
+    /// #
+    /// # struct ExamplePlugin { notes: u8 }
+    /// # impl Plugin for ExamplePlugin {
+    /// #     fn new(_host: HostCallback) -> Self { Self { notes: 0 } }
+    /// #
+    /// fn get_info(&self) -> Info {
+    ///     Info {
+    ///         process_context: true,
+    ///         ..Default::default()
+    ///     }
+    /// }
+    ///
+    /// fn process_ctx(&mut self, mut ctx: ProcessContext<f32>) {
+    ///     for event in ctx.events() {
+    ///         if let Event::Midi(event) = event {
+    ///             match event.data[0] & 0xF0 {
+    ///                 0x90 => self.notes += 1,
+    ///                 0x80 => self.notes -= 1,
+    ///                 _ => (),
+    ///             }
+    ///         }
+    ///     }
+    ///
+    ///     let tempo = ctx.time_info.map_or(120.0, |time_info| time_info.tempo);
+    ///     // ...
+    /// #     let _ = (tempo, &mut ctx.buffer);
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// This method is only called while the plugin is in the *resumed* state.
+    fn process_ctx(&mut self, mut ctx: ProcessContext<f32>) {
+        self.process(&mut ctx.buffer);
+    }
+
+    /// Process a block of `f64` audio together with its time info and events.
+    ///
+    /// See `process_ctx`. The default implementation ignores the events and calls `process_f64`.
+    ///
+    /// This method is only called while the plugin is in the *resumed* state.
+    fn process_ctx_f64(&mut self, mut ctx: ProcessContext<f64>) {
+        self.process_f64(&mut ctx.buffer);
+    }
+
     /// Get a reference to the shared parameter object.
     fn get_parameter_object(&mut self) -> Arc<dyn PluginParameters> {
         Arc::new(DummyPluginParameters)
     }
 
     /// Get information about an input channel. Only used by some hosts.
+    ///
+    /// By default, even and odd channels are paired up as stereo pairs so hosts route them
+    /// together, see `channels::SpeakerArrangementType::stereo_pair`. Override this to declare
+    /// other pairings, e.g. passing `None` as the arrangement for independent mono channels.
     fn get_input_info(&self, input: i32) -> ChannelInfo {
         ChannelInfo::new(
             format!("Input channel {}", input),
             Some(format!("In {}", input)),
             true,
-            None,
+            Some(channels::SpeakerArrangementType::stereo_pair(
+                input,
+                self.get_info().inputs,
+            )),
         )
     }
 
     /// Get information about an output channel. Only used by some hosts.
+    ///
+    /// Channels are paired up like in `get_input_info`.
     fn get_output_info(&self, output: i32) -> ChannelInfo {
         ChannelInfo::new(
             format!("Output channel {}", output),
             Some(format!("Out {}", output)),
             true,
-            None,
+            Some(channels::SpeakerArrangementType::stereo_pair(
+                output,
+                self.get_info().outputs,
+            )),
         )
     }
 
+    /// Get the name of the MIDI `key` on `channel`, e.g. "Kick" or "Snare" for a drum machine.
+    ///
+    /// Return `None` to have the host show the regular note name. Names are limited to
+    /// `consts::MAX_NAME_LEN` bytes.
+    fn get_midi_key_name(&self, channel: i32, key: i32) -> Option<String> {
+        None
+    }
+
+    /// Called when the host switches the soft bypass on or off, e.g. from an automatable bypass
+    /// button. While bypassed, the host keeps calling `process`, which should pass the inputs
+    /// through. Return `true` if the plugin supports this.
+    ///
+    /// Plugins setting `Info::soft_bypass` don't need to implement this, the library bypasses
+    /// them and reports support to the host.
+    fn set_bypass(&mut self, bypass: bool) -> bool {
+        false
+    }
+
     /// Called one time before the start of process call.
     ///
     /// This indicates that the process call will be interrupted (due to Host reconfiguration
     /// or bypass state when the plug-in doesn't support softBypass).
     ///
+    /// Processing may be stopped and started again without suspending the plugin in between, so
+    /// plugins with a tail (e.g. delays and reverbs) should clear their delay lines here as well
+    /// as in `reset` to avoid clicks on playback start. See the `reverb` example.
+    ///
     /// This method is only called while the plugin is in the *resumed* state.
     fn start_process(&mut self) {}
 
@@ -707,6 +1237,9 @@ pub trait Plugin: Send {
 pub trait PluginParameters: Sync {
     /// Set the current preset to the index specified by `preset`.
     ///
+    /// `preset` is always a valid index below `Info::presets`; out of range requests from the host
+    /// are ignored by this library.
+    ///
     /// This method can be called on the processing thread for automation.
     fn change_preset(&self, preset: i32) {}
 
@@ -715,22 +1248,54 @@ pub trait PluginParameters: Sync {
         0
     }
 
+    /// Called by the host before it loads a preset, e.g. by setting its parameters one by one.
+    ///
+    /// Together with `end_set_preset`, this allows switching the processing thread over to the
+    /// complete preset at once, see `util::PresetSwap`.
+    fn begin_set_preset(&self) {}
+
+    /// Called by the host after it loaded a preset.
+    fn end_set_preset(&self) {}
+
     /// Set the current preset name.
     fn set_preset_name(&self, name: String) {}
 
     /// Get the name of the preset at the index specified by `preset`.
+    ///
+    /// `preset` is always a valid index below `Info::presets`.
     fn get_preset_name(&self, preset: i32) -> String {
         "".to_string()
     }
 
+    /// Get the number of parameters, if this object knows it.
+    ///
+    /// When this returns `Some`, the count is used instead of `Info::parameters`. The default is
+    /// `None`, which keeps `Info::parameters`; see `parameter::Parameters` for an implementation
+    /// deriving the count from the declared parameters.
+    fn parameter_count(&self) -> Option<i32> {
+        None
+    }
+
     /// Get parameter label for parameter at `index` (e.g. "db", "sec", "ms", "%").
     fn get_parameter_label(&self, index: i32) -> String {
         "".to_string()
     }
 
     /// Get the parameter value for parameter at `index` (e.g. "1.0", "150", "Plate", "Off").
+    ///
+    /// The default implementation formats `get_parameter` with the decimals returned by
+    /// `get_parameter_decimals`, see `parameter::format_value`.
     fn get_parameter_text(&self, index: i32) -> String {
-        format!("{:.3}", self.get_parameter(index))
+        parameter::format_value(self.get_parameter(index), self.get_parameter_decimals(index))
+    }
+
+    /// Get the number of decimals shown by the default `get_parameter_text` for the parameter at
+    /// `index`.
+    ///
+    /// The default is `None`, which adapts the decimals to the value and leaves out trailing
+    /// zeros.
+    fn get_parameter_decimals(&self, index: i32) -> Option<usize> {
+        None
     }
 
     /// Get the name of parameter at `index`.
@@ -738,6 +1303,12 @@ pub trait PluginParameters: Sync {
         format!("Param {}", index)
     }
 
+    /// Get the display properties of the parameter at `index`, e.g. whether it is a switch or
+    /// has stepped values. Return `None` for regular continuous parameters.
+    fn get_parameter_info(&self, index: i32) -> Option<ParameterInfo> {
+        None
+    }
+
     /// Get the value of paramater at `index`. Should be value between 0.0 and 1.0.
     fn get_parameter(&self, index: i32) -> f32 {
         0.0
@@ -843,6 +1414,104 @@ impl HostCallback {
         }
     }
 
+    /// Mark the cached `Info` of the plugin as outdated, so that `Plugin::get_info` is queried again
+    /// the next time the host suspends or resumes the plugin.
+    ///
+    /// The channel, parameter and preset counts reported to the host are updated as well, and the
+    /// cached answers of `Plugin::can_do` are discarded. Applying the new `Info` only on these
+    /// state changes keeps it from changing while a block is processed.
+    pub fn invalidate_info(&self) {
+        if self.effect.is_null() {
+            return;
+        }
+        let user = unsafe { (*self.effect).user } as *const PluginCache;
+        if !user.is_null() {
+            unsafe { (*user).info_changed.store(true, Ordering::Release) };
+        }
+    }
+
+    /// Re-query `Plugin::get_info` and tell the host to update its display through
+    /// `Host::update_display`, e.g. after the parameter names of a multimode filter changed with
+    /// its mode.
+    ///
+    /// The cached `Info` is recomputed when the host next suspends or resumes the plugin (see
+    /// `invalidate_info`), so this can be called from any thread. What can change at runtime:
+    ///
+    /// - Parameter names, labels and texts, which are read from `PluginParameters` and never
+    ///   cached, so they are updated right away.
+    /// - The effect, vendor and product names and the parameter and preset counts, although not
+    ///   all hosts pick them up.
+    /// - The channel counts, followed by `Host::io_changed`.
+    ///
+    /// `initial_delay` is only read when the plugin is created, change the latency through
+    /// `set_latency` instead.
+    ///
+    /// `unique_id`, `version`, `category`, `f64_precision`, `preset_chunks` and
+    /// `silent_when_stopped` are read by hosts when the plugin is scanned or created, so changes
+    /// to them are logged as a mistake.
+    pub fn refresh(&self) {
+        self.invalidate_info();
+        self.update_display();
+    }
+
+    /// Return whether the plugin editor is currently open.
+    ///
+    /// This is tracked around the host's open and close calls, so parameter updates and other
+    /// code outside the editor can avoid touching GUI state while the editor is closed.
+    pub fn is_editor_open(&self) -> bool {
+        if self.effect.is_null() {
+            return false;
+        }
+        let user = unsafe { (*self.effect).user } as *const PluginCache;
+        !user.is_null() && unsafe { (*user).editor_open.load(Ordering::Acquire) }
+    }
+
+    /// Get the timing statistics of the plugin's process calls, see `vst::profiling`.
+    #[cfg(feature = "profiling")]
+    pub fn process_stats(&self) -> Option<ProcessStats> {
+        if self.effect.is_null() {
+            return None;
+        }
+        let user = unsafe { (*self.effect).user } as *const PluginCache;
+        if user.is_null() {
+            return None;
+        }
+        Some(unsafe { (*user).stats.stats() })
+    }
+
+    /// Reset the statistics returned from `process_stats`.
+    #[cfg(feature = "profiling")]
+    pub fn reset_process_stats(&self) {
+        if self.effect.is_null() {
+            return;
+        }
+        let user = unsafe { (*self.effect).user } as *const PluginCache;
+        if !user.is_null() {
+            unsafe { (*user).stats.reset() };
+        }
+    }
+
+    /// Change the latency (`Info::initial_delay`) reported to the host and notify it.
+    ///
+    /// Returns `true` if the host handled the change. Most hosts only pick up the new latency
+    /// while the plugin is suspended, or after a restart of playback.
+    pub fn set_latency(&self, samples: i32) -> bool {
+        if self.effect.is_null() {
+            return false;
+        }
+        // Other threads may read the latency concurrently. `AtomicI32` has the same layout as
+        // `i32`, so the field read by the host is stored atomically as well.
+        unsafe {
+            let cache = (*self.effect).user as *const PluginCache;
+            if !cache.is_null() {
+                (*cache).latency.store(samples, Ordering::Release);
+            }
+            let initial_delay = ptr::addr_of_mut!((*self.effect).initialDelay) as *const AtomicI32;
+            (*initial_delay).store(samples, Ordering::Release);
+        }
+        self.io_changed()
+    }
+
     /// Get the VST API version supported by the host e.g. `2400 = VST 2.4`.
     pub fn vst_version(&self) -> i32 {
         self.callback(self.effect, host::OpCode::Version, 0, 0, ptr::null_mut(), 0.0) as i32
@@ -941,6 +1610,9 @@ impl Host for HostCallback {
     ///
     /// Also please be aware that requesting information does not necessarily mean that that information is provided in return.
     /// Check the flags field in the `TimeInfo` structure to see if your request was actually met.
+    ///
+    /// Set `Info::uses_time_info`, as some hosts only provide time info to plugins announcing
+    /// that they receive it.
     fn get_time_info(&self, mask: i32) -> Option<TimeInfo> {
         let opcode = host::OpCode::GetTime;
         let mask = mask as isize;
@@ -953,15 +1625,133 @@ impl Host for HostCallback {
         }
     }
 
+    /// Get the current sample rate.
+    fn get_sample_rate(&self) -> isize {
+        self.callback(self.effect, host::OpCode::GetSampleRate, 0, 0, ptr::null_mut(), 0.0)
+    }
+
     /// Get block size.
     fn get_block_size(&self) -> isize {
         self.callback(self.effect, host::OpCode::GetBlockSize, 0, 0, ptr::null_mut(), 0.0)
     }
 
+    /// Vendor specific handling.
+    fn vendor_specific(&self, index: i32, value: isize, ptr: *mut c_void, opt: f32) -> isize {
+        self.callback(self.effect, host::OpCode::VendorSpecific, index, value, ptr, opt)
+    }
+
+    /// Get the automation state of the host.
+    fn get_automation_state(&self) -> AutomationState {
+        let state = self.callback(
+            self.effect,
+            host::OpCode::GetAutomationState,
+            0,
+            0,
+            ptr::null_mut(),
+            0.0,
+        );
+        AutomationState::checked_from(state as i64).unwrap_or(AutomationState::Unsupported)
+    }
+
+    /// Get the automation resolution of the host, or -1 if the host doesn't support this.
+    fn get_parameter_quantization(&self) -> i32 {
+        match self.callback(
+            self.effect,
+            host::OpCode::GetParameterQuantization,
+            0,
+            -1,
+            ptr::null_mut(),
+            0.0,
+        ) {
+            0 => -1,
+            quantization => quantization as i32,
+        }
+    }
+
+    /// Get the speaker arrangement the host routes the plugin's outputs into.
+    fn get_output_speaker_arrangement(&self) -> Option<(SpeakerArrangementType, i32)> {
+        let opcode = host::OpCode::GetOutputSpeakerArrangement;
+        match self.callback(self.effect, opcode, 0, 0, ptr::null_mut(), 0.0) {
+            0 => None,
+            ptr => {
+                // Hosts may send arrangement types unknown to the enum, so read the raw value.
+                let arrangement = ptr as *const SpeakerArrangement;
+                let (arrangement_type, num_channels) = unsafe {
+                    (
+                        ptr::addr_of!((*arrangement).arrangement_type).cast::<i32>().read(),
+                        (*arrangement).num_channels,
+                    )
+                };
+                SpeakerArrangementType::checked_from(i64::from(arrangement_type)).map(|t| (t, num_channels))
+            }
+        }
+    }
+
     /// Refresh UI after the plugin's parameters changed.
     fn update_display(&self) {
         self.callback(self.effect, host::OpCode::UpdateDisplay, 0, 0, ptr::null_mut(), 0.0);
     }
+
+    /// Tell the host that the number of inputs or outputs, or the latency of the plugin changed.
+    fn io_changed(&self) -> bool {
+        self.callback(self.effect, host::OpCode::IOChanged, 0, 0, ptr::null_mut(), 0.0) != 0
+    }
+
+    /// Check whether the host supports a feature.
+    fn can_do(&self, can_do: &str) -> Supported {
+        let can_do = match CString::new(can_do) {
+            Ok(can_do) => can_do,
+            Err(_) => return Supported::No,
+        };
+        let ptr = can_do.as_ptr() as *mut c_void;
+        let result = self.callback(self.effect, host::OpCode::CanDo, 0, 0, ptr, 0.0);
+        Supported::from(result).unwrap_or(Supported::Custom(result))
+    }
+
+    /// Ask the host to close the plugin's editor. Best-effort, see `Host::close_window`.
+    fn close_window(&self) -> bool {
+        self.callback(self.effect, host::OpCode::CloseWindow, 0, 0, ptr::null_mut(), 0.0) != 0
+    }
+
+    /// Get the path of the bank or project file being loaded or saved, see `Host::get_chunk_file`.
+    fn get_chunk_file(&self) -> Option<PathBuf> {
+        let mut buf = vec![0u8; MAX_PATH_LEN];
+        let supported = self.callback(
+            self.effect,
+            host::OpCode::GetChunkFile,
+            0,
+            0,
+            buf.as_mut_ptr() as *mut c_void,
+            0.0,
+        );
+        let len = buf.iter().position(|&b| b == 0).unwrap_or(0);
+        if supported != 1 || len == 0 {
+            return None;
+        }
+        Some(PathBuf::from(String::from_utf8_lossy(&buf[..len]).into_owned()))
+    }
+}
+
+/// Set the first time the default `Plugin::process_f64` is called, so its warning is only logged
+/// once.
+#[cfg(debug_assertions)]
+pub(crate) static DEFAULT_PROCESS_F64_CALLED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Copy the inputs of `buffer` to its outputs and silence the outputs without a matching input.
+fn pass_through<T: Float>(buffer: &mut AudioBuffer<T>) {
+    let (inputs, mut outputs) = buffer.split();
+    for channel in 0..outputs.len() {
+        let output = outputs.get_mut(channel);
+        if channel < inputs.len() {
+            for (in_frame, out_frame) in inputs.get(channel).iter().zip(output.iter_mut()) {
+                *out_frame = *in_frame;
+            }
+        } else {
+            for out_frame in output.iter_mut() {
+                *out_frame = T::zero();
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -975,11 +1765,12 @@ mod tests {
     /// This is a macro to allow you to specify attributes on the created struct.
     macro_rules! make_plugin {
         ($($attr:meta) *) => {
-            use std::os::raw::c_void;
+            use std::ffi::CStr;
+            use std::os::raw::{c_char, c_void};
 
             use main;
-            use api::AEffect;
-            use host::{Host, OpCode};
+            use api::{AEffect, Supported};
+            use host::{can_do, Host, OpCode};
             use plugin::{HostCallback, Info, Plugin};
 
             $(#[$attr]) *
@@ -1005,11 +1796,15 @@ mod tests {
                     info!("Loaded with host vst version: {}", self.host.vst_version());
                     assert_eq!(2400, self.host.vst_version());
                     assert_eq!(9876, self.host.get_plugin_id());
+                    assert_eq!(48000, self.host.get_sample_rate());
                     // Callback will assert these.
                     self.host.begin_edit(123);
                     self.host.automate(123, 12.3);
                     self.host.end_edit(123);
                     self.host.idle();
+                    assert!(self.host.can_do(can_do::ACCEPT_IO_CHANGES) == Supported::Yes);
+                    assert!(self.host.can_do(can_do::OFFLINE) == Supported::No);
+                    assert_eq!(self.host.get_chunk_file(), Some("/projects/song.vstproj".into()));
                 }
             }
 
@@ -1020,11 +1815,15 @@ mod tests {
                     opcode: i32,
                     index: i32,
                     _value: isize,
-                    _ptr: *mut c_void,
+                    ptr: *mut c_void,
                     opt: f32,
                 ) -> isize {
                     let opcode = OpCode::from(opcode);
                     match opcode {
+                        OpCode::CanDo => {
+                            let can_do = unsafe { CStr::from_ptr(ptr as *const c_char) };
+                            if can_do.to_bytes() == b"acceptIOChanges" { 1 } else { -1 }
+                        },
                         OpCode::BeginEdit => {
                             assert_eq!(index, 123);
                             0
@@ -1040,7 +1839,13 @@ mod tests {
                         },
                         OpCode::Version => 2400,
                         OpCode::CurrentId => 9876,
+                        OpCode::GetSampleRate => 48000,
                         OpCode::Idle => 0,
+                        OpCode::GetChunkFile => {
+                            let path = b"/projects/song.vstproj\0";
+                            unsafe { ptr::copy_nonoverlapping(path.as_ptr(), ptr as *mut u8, path.len()) };
+                            1
+                        },
                         _ => 0
                     }
                 }
@@ -1074,6 +1879,230 @@ mod tests {
         TestPlugin::default();
     }
 
+    #[test]
+    fn info_validate() {
+        use plugin::{Info, InfoError};
+
+        let valid = Info {
+            name: "Test Plugin".to_string(),
+            unique_id: 1234,
+            ..Default::default()
+        };
+        assert_eq!(valid.validate(), Ok(()));
+
+        let invalid = |info: Info| info.validate().unwrap_err();
+        assert_eq!(
+            invalid(Info {
+                unique_id: 0,
+                ..valid.clone()
+            }),
+            InfoError::ZeroUniqueId
+        );
+        assert_eq!(
+            invalid(Info {
+                name: String::new(),
+                ..valid.clone()
+            }),
+            InfoError::EmptyName
+        );
+        assert_eq!(
+            invalid(Info {
+                outputs: -1,
+                ..valid.clone()
+            }),
+            InfoError::NegativeCount
+        );
+        assert_eq!(
+            invalid(Info {
+                midi_inputs: 17,
+                ..valid.clone()
+            }),
+            InfoError::InvalidMidiChannels
+        );
+        assert_eq!(
+            invalid(Info {
+                preset_chunks: true,
+                presets: 0,
+                ..valid.clone()
+            }),
+            InfoError::ChunksWithoutPresets
+        );
+    }
+
+    #[test]
+    fn id_from_str() {
+        use api::consts::VST_MAGIC;
+        use plugin::Info;
+
+        assert_eq!(Info::id_from_str("VstP"), VST_MAGIC);
+        assert_eq!(Info::id_from_str("abcd"), 0x6162_6364);
+        assert_ne!(Info::id_from_str("MyPl"), Info::id_from_str("MyPm"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn id_from_str_length() {
+        plugin::Info::id_from_str("MyPlugin");
+    }
+
+    #[test]
+    fn can_do_default_support() {
+        use api::Supported;
+        use plugin::{CanDo, Category, Info};
+
+        let info = Info::default();
+        assert_eq!(CanDo::Offline.default_support(&info), Supported::No);
+        assert_eq!(CanDo::MidiProgramNames.default_support(&info), Supported::No);
+        // 0 MIDI channels means 16, so nothing is known about MIDI support.
+        assert_eq!(CanDo::ReceiveMidiEvent.default_support(&info), Supported::Maybe);
+        assert_eq!(CanDo::SendMidiEvent.default_support(&info), Supported::Maybe);
+        assert_eq!(CanDo::Bypass.default_support(&info), Supported::Maybe);
+        assert_eq!(CanDo::NoRealTime.default_support(&info), Supported::Maybe);
+        assert_eq!(CanDo::ReceiveTimeInfo.default_support(&info), Supported::Maybe);
+
+        let offline = Info {
+            category: Category::OfflineProcess,
+            ..Default::default()
+        };
+        assert_eq!(CanDo::from_str("noRealTime").default_support(&offline), Supported::Yes);
+
+        let tempo_synced = Info {
+            uses_time_info: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            CanDo::from_str("receiveVstTimeInfo").default_support(&tempo_synced),
+            Supported::Yes
+        );
+    }
+
+    #[test]
+    fn can_do_channels() {
+        use api::Supported;
+        use plugin::{CanDo, Info};
+
+        let info = Info {
+            inputs: 2,
+            outputs: 2,
+            io_configs: vec![(1, 1)],
+            ..Default::default()
+        };
+        let support = |s: &str| CanDo::from_str(s).default_support(&info);
+
+        assert_eq!(support("2in2out"), Supported::Yes);
+        assert_eq!(support("1in1out"), Supported::Yes);
+        assert_eq!(support("1in2out"), Supported::No);
+        assert_eq!(support("8in8out"), Supported::No);
+
+        let s: String = CanDo::from_str("4in8out").into();
+        assert_eq!(s, "4in8out");
+        match CanDo::from_str("2inout") {
+            CanDo::Other(_) => (),
+            other => panic!("parsed {:?}", other),
+        }
+    }
+
+    #[test]
+    fn default_can_do_queries_info() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use api::Supported;
+        use plugin::{CanDo, HostCallback, Info, Plugin};
+
+        #[derive(Default)]
+        struct Counting {
+            queries: AtomicUsize,
+        }
+
+        impl Plugin for Counting {
+            fn new(_host: HostCallback) -> Self {
+                Counting::default()
+            }
+
+            fn get_info(&self) -> Info {
+                self.queries.fetch_add(1, Ordering::Relaxed);
+                Info {
+                    inputs: 2,
+                    outputs: 2,
+                    ..Default::default()
+                }
+            }
+        }
+
+        let plugin = Counting::default();
+        assert_eq!(plugin.can_do(CanDo::Offline), Supported::No);
+        assert_eq!(plugin.can_do(CanDo::ReceiveMidiEvent), Supported::Maybe);
+        assert_eq!(plugin.can_do(CanDo::from_str("hasCockosExtensions")), Supported::Maybe);
+        assert_eq!(plugin.queries.load(Ordering::Relaxed), 0);
+
+        assert_eq!(plugin.can_do(CanDo::from_str("2in2out")), Supported::Yes);
+        assert_eq!(plugin.queries.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn default_process_unequal_channels() {
+        use buffer::AudioBuffer;
+        use plugin::Plugin;
+
+        let mut plugin = TestPlugin::default();
+        let input = [0.5f32; 4];
+        let mut left = [1.0f32; 4];
+        let mut right = [1.0f32; 4];
+
+        // Mono to stereo: the second output has no input and is silenced.
+        {
+            let inputs = [input.as_ptr()];
+            let mut outputs = [left.as_mut_ptr(), right.as_mut_ptr()];
+            let mut buffer = unsafe { AudioBuffer::from_raw(1, 2, inputs.as_ptr(), outputs.as_mut_ptr(), 4) };
+            plugin.process(&mut buffer);
+        }
+        assert_eq!(left, [0.5; 4]);
+        assert_eq!(right, [0.0; 4]);
+
+        // Stereo to mono: the second input is dropped.
+        let first = [0.75f64; 4];
+        let second = [0.25f64; 4];
+        let mut output = [1.0f64; 4];
+        {
+            let inputs = [first.as_ptr(), second.as_ptr()];
+            let mut outputs = [output.as_mut_ptr()];
+            let mut buffer = unsafe { AudioBuffer::from_raw(2, 1, inputs.as_ptr(), outputs.as_mut_ptr(), 4) };
+            plugin.process_f64(&mut buffer);
+        }
+        assert_eq!(output, [0.75; 4]);
+    }
+
+    #[test]
+    fn output_speaker_arrangement() {
+        use std::os::raw::c_void;
+
+        use api::{AEffect, SpeakerArrangementType};
+        use host::{Host, OpCode};
+        use plugin::HostCallback;
+
+        // The type and channel count at the start of a `SpeakerArrangement`.
+        static SURROUND: [i32; 2] = [SpeakerArrangementType::Surround51 as i32, 6];
+        static UNKNOWN: [i32; 2] = [1000, 6];
+
+        extern "C" fn surround(_: *mut AEffect, opcode: i32, _: i32, _: isize, _: *mut c_void, _: f32) -> isize {
+            assert_eq!(opcode, OpCode::GetOutputSpeakerArrangement as i32);
+            SURROUND.as_ptr() as isize
+        }
+
+        extern "C" fn unknown(_: *mut AEffect, _: i32, _: i32, _: isize, _: *mut c_void, _: f32) -> isize {
+            UNKNOWN.as_ptr() as isize
+        }
+
+        let arrangement = HostCallback::wrap(surround, ptr::null_mut()).get_output_speaker_arrangement();
+        match arrangement {
+            Some((SpeakerArrangementType::Surround51, 6)) => {}
+            _ => panic!("Wrong speaker arrangement"),
+        }
+        assert!(HostCallback::wrap(unknown, ptr::null_mut())
+            .get_output_speaker_arrangement()
+            .is_none());
+    }
+
     #[test]
     fn host_callbacks() {
         let aeffect = instance();
@@ -0,0 +1,50 @@
+//! Re-exports of the types most plugins need.
+//!
+//! Instead of importing every type from its module, plugins can glob import the prelude:
+//!
+//! ```no_run
+//! #[macro_use]
+//! extern crate vst;
+//!
+//! use vst::prelude::*;
+//!
+//! #[derive(Default)]
+//! struct BasicPlugin;
+//!
+//! impl Plugin for BasicPlugin {
+//!     fn new(_host: HostCallback) -> Self {
+//!         BasicPlugin
+//!     }
+//!
+//!     fn get_info(&self) -> Info {
+//!         Info {
+//!             name: "Basic Plugin".to_string(),
+//!             unique_id: 1357,
+//!             category: Category::Effect,
+//!             ..Default::default()
+//!         }
+//!     }
+//!
+//!     fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
+//!         for (input, output) in buffer.zip() {
+//!             output.copy_from_slice(input);
+//!         }
+//!     }
+//! }
+//!
+//! plugin_main!(BasicPlugin);
+//! # fn main() {}
+//! ```
+//!
+//! The prelude only re-exports types, so importing it has no effect besides bringing them into
+//! scope. Less common types are imported from their modules as usual.
+
+pub use api::{Events, Supported, TimeInfo, TimeInfoFlags};
+pub use buffer::{AudioBuffer, SendEventBuffer};
+pub use editor::{Editor, Key, KeyCode, KnobMode};
+pub use event::{Event, MidiEvent, SysExEvent};
+pub use host::Host;
+pub use parameter::{Parameter, ParameterInfo, Parameters};
+pub use plugin::{CanDo, Category, HostCallback, Info, Plugin, PluginError, PluginParameters, TailSize};
+pub use process::ProcessContext;
+pub use util::AtomicFloat;
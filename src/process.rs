@@ -0,0 +1,190 @@
+//! Higher level processing interface, bundling the audio, time info and events of a block.
+
+use num_traits::Float;
+
+use api::{self, TimeInfo};
+use buffer::AudioBuffer;
+use event::{Event, MidiEvent, SysExEvent};
+
+/// Maximum number of events kept for the next block. Further events are dropped.
+pub const MAX_STORED_EVENTS: usize = 1024;
+
+/// Maximum total size of the SysEx payloads kept for the next block, in bytes. Further SysEx
+/// events are dropped.
+pub const MAX_STORED_SYSEX_BYTES: usize = 64 * 1024;
+
+/// Everything a plugin needs to process a single block.
+///
+/// Passed to `Plugin::process_ctx` and `Plugin::process_ctx_f64` when `Info::process_context` is
+/// set, instead of calling `Plugin::process_events` and `Plugin::process` separately.
+///
+/// The events are kept in buffers allocated when the plugin is resumed, for up to
+/// `MAX_STORED_EVENTS` events and `MAX_STORED_SYSEX_BYTES` of SysEx data per block.
+pub struct ProcessContext<'a, T: 'a + Float> {
+    /// The audio inputs and outputs of this block.
+    pub buffer: AudioBuffer<'a, T>,
+
+    /// The host's time info at the start of this block, if provided by the host.
+    ///
+    /// Only requested for plugins setting `Info::uses_time_info`, with the musical position,
+    /// tempo, cycle and time signature. Call `Host::get_time_info` for SMPTE or MIDI clock
+    /// information.
+    pub time_info: Option<TimeInfo>,
+
+    events: &'a [StoredEvent],
+    sysex: &'a [u8],
+}
+
+impl<'a, T: 'a + Float> ProcessContext<'a, T> {
+    pub(crate) fn new(buffer: AudioBuffer<'a, T>, time_info: Option<TimeInfo>, events: &'a EventStore) -> Self {
+        ProcessContext {
+            buffer,
+            time_info,
+            events: &events.events,
+            sysex: &events.sysex,
+        }
+    }
+
+    /// The events sent by the host for this block, in the order they were received.
+    pub fn events(&self) -> impl Iterator<Item = Event<'a>> + 'a {
+        let sysex = self.sysex;
+        self.events.iter().map(move |event| match *event {
+            StoredEvent::Midi(event) => Event::Midi(event),
+            StoredEvent::SysEx {
+                start,
+                len,
+                delta_frames,
+            } => Event::SysEx(SysExEvent {
+                payload: &sysex[start..start + len],
+                delta_frames,
+            }),
+            StoredEvent::Deprecated(event) => Event::Deprecated(event),
+        })
+    }
+}
+
+/// Copy of an event, kept until the next process call.
+pub(crate) enum StoredEvent {
+    Midi(MidiEvent),
+    /// The payload is at `start..start + len` in `EventStore::sysex`.
+    SysEx {
+        start: usize,
+        len: usize,
+        delta_frames: i32,
+    },
+    Deprecated(api::Event),
+}
+
+/// Events received from the host for the upcoming processing block.
+///
+/// The events passed to `ProcessEvents` are only valid during that call, so they are copied here.
+/// Storing them happens on the audio thread and never allocates: events that don't fit into the
+/// buffers allocated by `reserve` are dropped.
+#[derive(Default)]
+pub(crate) struct EventStore {
+    events: Vec<StoredEvent>,
+    /// The payloads of the stored SysEx events, one after another.
+    sysex: Vec<u8>,
+}
+
+impl EventStore {
+    /// Clear the store and allocate room for `MAX_STORED_EVENTS` events and
+    /// `MAX_STORED_SYSEX_BYTES` of SysEx payloads, e.g. when the plugin is resumed.
+    pub fn reserve(&mut self) {
+        self.clear();
+        self.events.reserve_exact(MAX_STORED_EVENTS);
+        self.sysex.reserve_exact(MAX_STORED_SYSEX_BYTES);
+    }
+
+    /// Copy `events`, dropping those that don't fit.
+    pub fn store(&mut self, events: &api::Events) {
+        let mut dropped = 0;
+        for event in events.events() {
+            if self.events.len() == self.events.capacity() {
+                dropped += 1;
+                continue;
+            }
+            let stored = match event {
+                Event::Midi(event) => StoredEvent::Midi(event),
+                Event::SysEx(event) => {
+                    let start = self.sysex.len();
+                    if self.sysex.capacity() - start < event.payload.len() {
+                        dropped += 1;
+                        continue;
+                    }
+                    self.sysex.extend_from_slice(event.payload);
+                    StoredEvent::SysEx {
+                        start,
+                        len: event.payload.len(),
+                        delta_frames: event.delta_frames,
+                    }
+                }
+                Event::Deprecated(event) => StoredEvent::Deprecated(event),
+            };
+            self.events.push(stored);
+        }
+        if dropped > 0 {
+            debug!(
+                "Dropped {} events which don't fit into the buffers for the next block",
+                dropped
+            );
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.sysex.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{AudioBuffer, SendEventBuffer};
+    use event::{Event, MidiEvent, SysExEvent};
+    use process::{EventStore, ProcessContext, MAX_STORED_EVENTS, MAX_STORED_SYSEX_BYTES};
+
+    #[test]
+    fn bounded_event_store() {
+        let mut store = EventStore::default();
+        store.reserve();
+
+        // The second SysEx event and the last note don't fit.
+        let sysex = vec![0xf0; MAX_STORED_SYSEX_BYTES / 2 + 1];
+        let events: Vec<Event> = (0..MAX_STORED_EVENTS + 2)
+            .map(|i| match i {
+                0 | 1 => Event::SysEx(SysExEvent {
+                    payload: &sysex,
+                    delta_frames: i as i32,
+                }),
+                _ => Event::Midi(MidiEvent::note_on(0, 60, 100, i as i32)),
+            })
+            .collect();
+        let mut send_buffer = SendEventBuffer::new(events.len());
+        send_buffer.store_events(&events);
+        let (events_capacity, sysex_capacity) = (store.events.capacity(), store.sysex.capacity());
+        store.store(send_buffer.events());
+        assert_eq!(store.events.capacity(), events_capacity);
+        assert_eq!(store.sysex.capacity(), sysex_capacity);
+
+        let buffer = unsafe { AudioBuffer::<f32>::from_raw(0, 0, [].as_ptr(), [].as_mut_ptr(), 0) };
+        let ctx = ProcessContext::new(buffer, None, &store);
+        let stored: Vec<Event> = ctx.events().collect();
+        assert_eq!(stored.len(), MAX_STORED_EVENTS);
+        match stored[0] {
+            Event::SysEx(event) => assert_eq!((event.payload, event.delta_frames), (&sysex[..], 0)),
+            _ => panic!("SysEx event missing"),
+        }
+        match stored[MAX_STORED_EVENTS - 1] {
+            Event::Midi(event) => assert_eq!(event.delta_frames, MAX_STORED_EVENTS as i32),
+            _ => panic!("Note missing"),
+        }
+
+        store.clear();
+        let ctx = ProcessContext::<f32>::new(
+            unsafe { AudioBuffer::from_raw(0, 0, [].as_ptr(), [].as_mut_ptr(), 0) },
+            None,
+            &store,
+        );
+        assert_eq!(ctx.events().count(), 0);
+    }
+}
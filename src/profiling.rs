@@ -0,0 +1,121 @@
+//! Per-block processing statistics, enabled with the `profiling` feature.
+//!
+//! Every `Plugin::process` and `Plugin::process_f64` call is timed and the results are
+//! accumulated, so the plugin or its editor can show them through
+//! `HostCallback::process_stats`. Without the feature, nothing is timed.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Timing statistics of the processing calls since the plugin was loaded or the statistics were
+/// last reset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ProcessStats {
+    /// Number of processed blocks.
+    pub blocks: u64,
+    /// Time spent processing the last block.
+    pub last: Duration,
+    /// Average time spent processing a block.
+    pub average: Duration,
+    /// Longest time spent processing a block.
+    pub peak: Duration,
+    /// Fraction of the real-time duration of the last block spent processing it, see
+    /// `peak_load`. VST 2.4 has no way to report it to the host, so show it in the editor.
+    pub load: f64,
+    /// Highest fraction of the real-time duration of a block spent processing it, e.g. 0.5 if
+    /// processing 10 ms of audio took 5 ms. Values close to or above 1.0 cause dropouts.
+    ///
+    /// This is 0.0 until the host sets the sample rate.
+    pub peak_load: f64,
+}
+
+/// Accumulates `ProcessStats` on the processing thread.
+#[derive(Default)]
+pub(crate) struct StatsRecorder {
+    blocks: AtomicU64,
+    total_nanos: AtomicU64,
+    last_nanos: AtomicU64,
+    peak_nanos: AtomicU64,
+    /// Bits of the `f64` load of the last block.
+    last_load: AtomicU64,
+    /// Bits of the `f64` peak load. Positive floats compare like their bits.
+    peak_load: AtomicU64,
+    /// Bits of the `f64` sample rate, 0 if unknown.
+    sample_rate: AtomicU64,
+}
+
+impl StatsRecorder {
+    pub fn set_sample_rate(&self, rate: f32) {
+        self.sample_rate.store(f64::from(rate).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Record a block of `samples` samples which started processing at `start`.
+    pub fn record(&self, start: Instant, samples: usize) {
+        let nanos = start.elapsed().as_nanos() as u64;
+        self.blocks.fetch_add(1, Ordering::Relaxed);
+        self.total_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.last_nanos.store(nanos, Ordering::Relaxed);
+        self.peak_nanos.fetch_max(nanos, Ordering::Relaxed);
+
+        let sample_rate = f64::from_bits(self.sample_rate.load(Ordering::Relaxed));
+        if sample_rate > 0.0 && samples > 0 {
+            let block_nanos = samples as f64 / sample_rate * 1e9;
+            let load = nanos as f64 / block_nanos;
+            self.last_load.store(load.to_bits(), Ordering::Relaxed);
+            self.peak_load.fetch_max(load.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn stats(&self) -> ProcessStats {
+        let blocks = self.blocks.load(Ordering::Relaxed);
+        let total = self.total_nanos.load(Ordering::Relaxed);
+        ProcessStats {
+            blocks,
+            last: Duration::from_nanos(self.last_nanos.load(Ordering::Relaxed)),
+            average: Duration::from_nanos(total.checked_div(blocks).unwrap_or(0)),
+            peak: Duration::from_nanos(self.peak_nanos.load(Ordering::Relaxed)),
+            load: f64::from_bits(self.last_load.load(Ordering::Relaxed)),
+            peak_load: f64::from_bits(self.peak_load.load(Ordering::Relaxed)),
+        }
+    }
+
+    pub fn reset(&self) {
+        for counter in &[
+            &self.blocks,
+            &self.total_nanos,
+            &self.last_nanos,
+            &self.peak_nanos,
+            &self.last_load,
+            &self.peak_load,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use profiling::StatsRecorder;
+
+    #[test]
+    fn record_and_reset() {
+        let recorder = StatsRecorder::default();
+        recorder.set_sample_rate(1000.0);
+
+        // A block of 1000 samples lasts a second, so the load stays far below 1.
+        let start = Instant::now() - Duration::from_millis(10);
+        recorder.record(start, 1000);
+        let stats = recorder.stats();
+        assert_eq!(stats.blocks, 1);
+        assert!(stats.last >= Duration::from_millis(10));
+        assert_eq!(stats.average, stats.last);
+        assert_eq!(stats.peak, stats.last);
+        assert!(stats.peak_load > 0.0 && stats.peak_load < 0.5);
+        assert_eq!(stats.load, stats.peak_load);
+
+        recorder.reset();
+        assert_eq!(recorder.stats(), Default::default());
+    }
+}
@@ -0,0 +1,96 @@
+//! Detection of allocations during audio processing.
+//!
+//! With the `rt-check` feature enabled in debug builds, a global allocator wrapper is installed
+//! which logs a backtrace for every allocation happening inside `Plugin::process` or
+//! `Plugin::process_f64`. Allocating on the processing thread is not real-time safe and can cause
+//! dropouts. Without the feature (or in release builds), this module does nothing.
+//!
+//! As the wrapper is a `#[global_allocator]`, the feature can't be used by plugins that define
+//! their own global allocator (or depend on a crate that does). Linking fails with two global
+//! allocators, so disable the feature for those plugins.
+
+#[cfg(all(feature = "rt-check", debug_assertions))]
+mod imp {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::backtrace::Backtrace;
+    use std::cell::Cell;
+
+    thread_local! {
+        static IN_PROCESS: Cell<bool> = const { Cell::new(false) };
+    }
+
+    struct RtCheckAllocator;
+
+    #[global_allocator]
+    static ALLOCATOR: RtCheckAllocator = RtCheckAllocator;
+
+    fn check(size: usize) {
+        // `try_with` fails during thread teardown, in which case we aren't processing anyway.
+        let in_process = IN_PROCESS.try_with(|p| p.replace(false)).unwrap_or(false);
+        if in_process {
+            // Logging allocates too, so the check stays disabled until we are done here.
+            error!(
+                "Allocation of {} bytes during process:\n{}",
+                size,
+                Backtrace::force_capture()
+            );
+            let _ = IN_PROCESS.try_with(|p| p.set(true));
+        }
+    }
+
+    unsafe impl GlobalAlloc for RtCheckAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            check(layout.size());
+            System.alloc(layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            check(layout.size());
+            System.alloc_zeroed(layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            check(new_size);
+            System.realloc(ptr, layout, new_size)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    /// Marks the current thread as processing until dropped.
+    pub struct ProcessGuard {
+        previous: bool,
+    }
+
+    impl ProcessGuard {
+        pub fn new() -> ProcessGuard {
+            ProcessGuard {
+                previous: IN_PROCESS.with(|p| p.replace(true)),
+            }
+        }
+    }
+
+    impl Drop for ProcessGuard {
+        fn drop(&mut self) {
+            let previous = self.previous;
+            let _ = IN_PROCESS.try_with(|p| p.set(previous));
+        }
+    }
+}
+
+#[cfg(not(all(feature = "rt-check", debug_assertions)))]
+mod imp {
+    /// Does nothing without the `rt-check` feature.
+    pub struct ProcessGuard;
+
+    impl ProcessGuard {
+        #[inline(always)]
+        pub fn new() -> ProcessGuard {
+            ProcessGuard
+        }
+    }
+}
+
+pub(crate) use self::imp::ProcessGuard;
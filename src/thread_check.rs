@@ -0,0 +1,86 @@
+//! Detection of editor and processing calls on the wrong thread.
+//!
+//! In debug builds, the threads on which the host processes audio and opens the editor are
+//! remembered, and a warning is logged (once for each kind of mistake) when an editor method is
+//! called on a processing thread or audio is processed on the editor thread. Hosts may process on
+//! the UI thread in some cases, e.g. when rendering offline, so this is only a hint. In release
+//! builds, this module does nothing.
+
+#[cfg(debug_assertions)]
+mod imp {
+    use std::cell::Cell;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    thread_local! {
+        static IS_PROCESS_THREAD: Cell<bool> = const { Cell::new(false) };
+        static IS_EDITOR_THREAD: Cell<bool> = const { Cell::new(false) };
+    }
+
+    static WARNED_EDITOR: AtomicBool = AtomicBool::new(false);
+    static WARNED_PROCESS: AtomicBool = AtomicBool::new(false);
+
+    /// Called at the start of every process call. Returns whether this is the editor thread.
+    pub fn check_process_thread() -> bool {
+        IS_PROCESS_THREAD.with(|p| p.set(true));
+        let wrong_thread = IS_EDITOR_THREAD.with(Cell::get);
+        if wrong_thread && !WARNED_PROCESS.swap(true, Ordering::Relaxed) {
+            warn!("Audio is processed on the thread that opened the editor");
+        }
+        wrong_thread
+    }
+
+    /// Called before every editor method the host calls, with the name of the method. Returns
+    /// whether this is a processing thread.
+    pub fn check_editor_thread(method: &str) -> bool {
+        let wrong_thread = IS_PROCESS_THREAD.with(Cell::get);
+        if wrong_thread {
+            if !WARNED_EDITOR.swap(true, Ordering::Relaxed) {
+                warn!("`Editor::{}` is called on an audio processing thread", method);
+            }
+        } else if method == "open" {
+            IS_EDITOR_THREAD.with(|e| e.set(true));
+        }
+        wrong_thread
+    }
+}
+
+#[cfg(not(debug_assertions))]
+mod imp {
+    /// Does nothing in release builds.
+    #[inline(always)]
+    pub fn check_process_thread() -> bool {
+        false
+    }
+
+    /// Does nothing in release builds.
+    #[inline(always)]
+    pub fn check_editor_thread(_method: &str) -> bool {
+        false
+    }
+}
+
+pub(crate) use self::imp::{check_editor_thread, check_process_thread};
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use std::thread;
+
+    use thread_check::{check_editor_thread, check_process_thread};
+
+    #[test]
+    fn wrong_threads() {
+        let editor = thread::spawn(|| {
+            assert!(!check_editor_thread("open"));
+            assert!(!check_editor_thread("idle"));
+            // Processing on the editor thread, and the editor afterwards.
+            assert!(check_process_thread());
+            assert!(check_editor_thread("idle"));
+        });
+        let process = thread::spawn(|| {
+            assert!(!check_process_thread());
+            assert!(check_editor_thread("open"));
+        });
+        editor.join().unwrap();
+        process.join().unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use num_traits::Float;
+
+use buffer::AudioBuffer;
+
+/// The last samples of each channel from the previous block.
+///
+/// Filters and interpolators often need the samples just before the current block, e.g. the
+/// previous output of a one-pole filter or the neighbours of a fractional delay read. Record each
+/// channel at the end of `process` with [`push`](#method.push) (or
+/// [`push_inputs`](#method.push_inputs) / [`push_outputs`](#method.push_outputs)), and read the
+/// history of the previous block in the next call.
+///
+/// Recording doesn't allocate, but [`set_channels`](#method.set_channels) does. Call it from
+/// `Plugin::resume` or `Plugin::new` once the channel count is known, not from `process`.
+pub struct BlockHistory<T: Float> {
+    len: usize,
+    channels: Vec<Vec<T>>,
+}
+
+impl<T: Float> BlockHistory<T> {
+    /// New history keeping the last `len` samples of every channel, without any channels yet.
+    pub fn new(len: usize) -> BlockHistory<T> {
+        BlockHistory {
+            len,
+            channels: Vec::new(),
+        }
+    }
+
+    /// Keep history for `channels` channels, cleared to silence. This allocates.
+    pub fn set_channels(&mut self, channels: usize) {
+        self.channels = vec![vec![T::zero(); self.len]; channels];
+    }
+
+    /// The number of channels with history.
+    pub fn channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Reset the history of every channel to silence, e.g. when the plugin is resumed.
+    pub fn clear(&mut self) {
+        for channel in &mut self.channels {
+            for sample in channel.iter_mut() {
+                *sample = T::zero();
+            }
+        }
+    }
+
+    /// The last samples recorded for `channel`, oldest first.
+    ///
+    /// # Panics
+    /// Panics if `channel` is not below `channels()`.
+    pub fn get(&self, channel: usize) -> &[T] {
+        &self.channels[channel]
+    }
+
+    /// The sample `offset` positions before the start of the current block in `channel`, where an
+    /// `offset` of 1 is the last sample of the previous block. Returns `None` if `offset` is 0,
+    /// longer than the history, or `channel` has no history.
+    pub fn before(&self, channel: usize, offset: usize) -> Option<T> {
+        let history = self.channels.get(channel)?;
+        if offset == 0 || offset > history.len() {
+            return None;
+        }
+        Some(history[history.len() - offset])
+    }
+
+    /// Record the samples of the current block of `channel`. Blocks shorter than the history are
+    /// appended to what was recorded before.
+    ///
+    /// # Panics
+    /// Panics if `channel` is not below `channels()`.
+    pub fn push(&mut self, channel: usize, block: &[T]) {
+        let history = &mut self.channels[channel];
+        let len = history.len();
+        if block.len() >= len {
+            history.copy_from_slice(&block[block.len() - len..]);
+        } else {
+            history.rotate_left(block.len());
+            history[len - block.len()..].copy_from_slice(block);
+        }
+    }
+
+    /// Record the inputs of `buffer`, for the channels that have history.
+    pub fn push_inputs(&mut self, buffer: &mut AudioBuffer<T>) {
+        let (inputs, _) = buffer.split();
+        for channel in 0..inputs.len().min(self.channels.len()) {
+            self.push(channel, inputs.get(channel));
+        }
+    }
+
+    /// Record the outputs of `buffer`, for the channels that have history. Call this after writing
+    /// the outputs.
+    pub fn push_outputs(&mut self, buffer: &mut AudioBuffer<T>) {
+        let (_, outputs) = buffer.split();
+        for channel in 0..outputs.len().min(self.channels.len()) {
+            self.push(channel, outputs.get(channel));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::AudioBuffer;
+    use util::BlockHistory;
+
+    #[test]
+    fn across_blocks() {
+        let mut history = BlockHistory::new(2);
+        history.set_channels(1);
+        assert_eq!(history.get(0), &[0.0, 0.0]);
+
+        // A one-pole lowpass, continued seamlessly across blocks.
+        let process = |history: &mut BlockHistory<f32>, input: &[f32]| {
+            let mut output = vec![0.0f32; input.len()];
+            let inputs = [input.as_ptr()];
+            let mut outputs = [output.as_mut_ptr()];
+            let mut buffer = unsafe { AudioBuffer::from_raw(1, 1, inputs.as_ptr(), outputs.as_mut_ptr(), input.len()) };
+
+            let mut previous = history.before(0, 1).unwrap();
+            for (input, output) in buffer.zip() {
+                for (x, y) in input.iter().zip(output.iter_mut()) {
+                    *y = 0.5 * x + 0.5 * previous;
+                    previous = *y;
+                }
+            }
+            history.push_outputs(&mut buffer);
+            output
+        };
+
+        assert_eq!(process(&mut history, &[1.0, 1.0, 1.0]), vec![0.5, 0.75, 0.875]);
+        assert_eq!(history.get(0), &[0.75, 0.875]);
+        assert_eq!(process(&mut history, &[1.0]), vec![0.9375]);
+        assert_eq!(history.get(0), &[0.875, 0.9375]);
+        assert_eq!(history.before(0, 2), Some(0.875));
+        assert_eq!(history.before(0, 3), None);
+        assert_eq!(history.before(1, 1), None);
+
+        history.clear();
+        assert_eq!(history.get(0), &[0.0, 0.0]);
+    }
+}
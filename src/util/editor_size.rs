@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// The last size of a resizable editor window, shared between the editor and the parameter
+/// object.
+///
+/// Hosts query the editor size (through `Editor::size`) before the editor is opened, so a
+/// resizable editor should return the size stored here rather than a fixed default. Update it
+/// with [`set`](#method.set) whenever the user resizes the window.
+///
+/// To make the size survive project reloads as well, keep the `EditorSize` in the parameter
+/// object and include [`to_bytes`](#method.to_bytes) in the preset or bank chunk data returned
+/// from `PluginParameters::get_preset_data` / `get_bank_data`, restoring it with
+/// [`from_bytes`](#method.from_bytes) in `load_preset_data` / `load_bank_data`.
+pub struct EditorSize {
+    width: AtomicI32,
+    height: AtomicI32,
+}
+
+impl EditorSize {
+    /// New editor size with the initial (default) dimensions `width` and `height`.
+    pub fn new(width: i32, height: i32) -> EditorSize {
+        EditorSize {
+            width: AtomicI32::new(width),
+            height: AtomicI32::new(height),
+        }
+    }
+
+    /// Get the last stored size as `(width, height)`.
+    pub fn get(&self) -> (i32, i32) {
+        (self.width.load(Ordering::Relaxed), self.height.load(Ordering::Relaxed))
+    }
+
+    /// Store a new size, e.g. after the user resized the editor window.
+    pub fn set(&self, width: i32, height: i32) {
+        self.width.store(width, Ordering::Relaxed);
+        self.height.store(height, Ordering::Relaxed);
+    }
+
+    /// Serialize the stored size for inclusion in chunk data.
+    pub fn to_bytes(&self) -> [u8; 8] {
+        let (width, height) = self.get();
+        let mut bytes = [0; 8];
+        bytes[..4].copy_from_slice(&width.to_le_bytes());
+        bytes[4..].copy_from_slice(&height.to_le_bytes());
+        bytes
+    }
+
+    /// Restore a size serialized with `to_bytes`.
+    ///
+    /// Returns `false` and leaves the stored size unchanged if `bytes` is too short.
+    pub fn from_bytes(&self, bytes: &[u8]) -> bool {
+        if bytes.len() < 8 {
+            return false;
+        }
+
+        let mut width = [0; 4];
+        let mut height = [0; 4];
+        width.copy_from_slice(&bytes[..4]);
+        height.copy_from_slice(&bytes[4..8]);
+        self.set(i32::from_le_bytes(width), i32::from_le_bytes(height));
+        true
+    }
+}
+
+impl std::fmt::Debug for EditorSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self.get(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use util::EditorSize;
+
+    #[test]
+    fn editor_size_roundtrip() {
+        let size = EditorSize::new(640, 480);
+        assert_eq!(size.get(), (640, 480));
+
+        size.set(800, 600);
+        let bytes = size.to_bytes();
+
+        let restored = EditorSize::new(640, 480);
+        assert!(!restored.from_bytes(&bytes[..7]));
+        assert_eq!(restored.get(), (640, 480));
+        assert!(restored.from_bytes(&bytes));
+        assert_eq!(restored.get(), (800, 600));
+    }
+}
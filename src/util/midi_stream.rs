@@ -0,0 +1,333 @@
+use event::Event;
+
+/// A complete MIDI message assembled by a `MidiStreamParser`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MidiMessage {
+    /// A channel or system common message with running status resolved.
+    ///
+    /// Unused data bytes are 0.
+    Short {
+        /// Status byte followed by up to two data bytes.
+        data: [u8; 3],
+        /// Number of samples into the current block that this message occurs on.
+        delta_frames: i32,
+    },
+
+    /// A single byte system realtime message (clock, start, stop, etc.).
+    Realtime {
+        /// Status byte (`0xF8` to `0xFF`).
+        status: u8,
+        /// Number of samples into the current block that this message occurs on.
+        delta_frames: i32,
+    },
+
+    /// A complete system exclusive message, assembled from one or more events.
+    SysEx {
+        /// The whole message, including the leading `0xF0` and trailing `0xF7`.
+        payload: Vec<u8>,
+        /// `delta_frames` of the event which started the message.
+        delta_frames: i32,
+    },
+}
+
+impl MidiMessage {
+    /// The system realtime message, if this is one with a defined meaning.
+    pub fn realtime(&self) -> Option<RealtimeMessage> {
+        match *self {
+            MidiMessage::Realtime { status, .. } => RealtimeMessage::from_status(status),
+            _ => None,
+        }
+    }
+}
+
+/// A system realtime message, as used for syncing to the MIDI clock of external gear.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RealtimeMessage {
+    /// Timing clock (`0xF8`), sent 24 times per quarter note.
+    Clock,
+    /// Start playback from the beginning (`0xFA`).
+    Start,
+    /// Continue playback from the current position (`0xFB`).
+    Continue,
+    /// Stop playback (`0xFC`).
+    Stop,
+    /// Active sensing (`0xFE`).
+    ActiveSensing,
+    /// System reset (`0xFF`).
+    Reset,
+}
+
+impl RealtimeMessage {
+    /// Number of `Clock` messages per quarter note.
+    pub const CLOCKS_PER_QUARTER_NOTE: u32 = 24;
+
+    /// Get the message for the given status byte, or `None` if it isn't a defined realtime
+    /// message (`0xF9` and `0xFD` are undefined).
+    pub fn from_status(status: u8) -> Option<RealtimeMessage> {
+        match status {
+            0xF8 => Some(RealtimeMessage::Clock),
+            0xFA => Some(RealtimeMessage::Start),
+            0xFB => Some(RealtimeMessage::Continue),
+            0xFC => Some(RealtimeMessage::Stop),
+            0xFE => Some(RealtimeMessage::ActiveSensing),
+            0xFF => Some(RealtimeMessage::Reset),
+            _ => None,
+        }
+    }
+
+    /// The status byte of this message.
+    pub fn status(self) -> u8 {
+        match self {
+            RealtimeMessage::Clock => 0xF8,
+            RealtimeMessage::Start => 0xFA,
+            RealtimeMessage::Continue => 0xFB,
+            RealtimeMessage::Stop => 0xFC,
+            RealtimeMessage::ActiveSensing => 0xFE,
+            RealtimeMessage::Reset => 0xFF,
+        }
+    }
+}
+
+/// Number of data bytes following the given status byte.
+fn data_len(status: u8) -> usize {
+    match status {
+        0x80..=0xBF | 0xE0..=0xEF | 0xF2 => 2,
+        0xC0..=0xDF | 0xF1 | 0xF3 => 1,
+        _ => 0,
+    }
+}
+
+/// Assembles incoming events into complete MIDI messages.
+///
+/// - Running status: `MidiEvent`s starting with a data byte reuse the last channel status byte.
+/// - System exclusive messages split across several `SysExEvent`s are joined until the
+///   terminating `0xF7`. A new `0xF0` discards an unterminated message.
+/// - Realtime bytes interleaved with system exclusive data are reported as separate messages.
+///   They don't affect running status or a system exclusive message in progress, so e.g. MIDI
+///   clock ticks can arrive between any other messages. See `MidiMessage::realtime`.
+///
+/// Only system exclusive messages allocate.
+#[derive(Default)]
+pub struct MidiStreamParser {
+    running_status: Option<u8>,
+    sysex: Option<(Vec<u8>, i32)>,
+}
+
+impl MidiStreamParser {
+    /// Create a new parser.
+    pub fn new() -> MidiStreamParser {
+        MidiStreamParser::default()
+    }
+
+    /// Feed an event into the parser, calling `on_message` for every message it completes.
+    pub fn push<F: FnMut(MidiMessage)>(&mut self, event: &Event, mut on_message: F) {
+        match *event {
+            Event::Midi(ref ev) => {
+                let status = ev.data[0];
+                if status == 0xF0 {
+                    self.push_sysex(&ev.data, ev.delta_frames, &mut on_message);
+                    return;
+                }
+
+                let data = match status {
+                    0xF8..=0xFF => {
+                        on_message(MidiMessage::Realtime {
+                            status,
+                            delta_frames: ev.delta_frames,
+                        });
+                        return;
+                    }
+                    0x80..=0xEF => {
+                        self.running_status = Some(status);
+                        ev.data
+                    }
+                    0xF1..=0xF7 => {
+                        self.running_status = None;
+                        ev.data
+                    }
+                    _ => match self.running_status {
+                        Some(running_status) => [running_status, ev.data[0], ev.data[1]],
+                        None => return,
+                    },
+                };
+
+                let mut short = [data[0], 0, 0];
+                let len = data_len(data[0]);
+                short[1..=len].copy_from_slice(&data[1..=len]);
+                on_message(MidiMessage::Short {
+                    data: short,
+                    delta_frames: ev.delta_frames,
+                });
+            }
+            Event::SysEx(ref ev) => self.push_sysex(ev.payload, ev.delta_frames, &mut on_message),
+            Event::Deprecated(_) => {}
+        }
+    }
+
+    fn push_sysex<F: FnMut(MidiMessage)>(&mut self, bytes: &[u8], delta_frames: i32, on_message: &mut F) {
+        for &byte in bytes {
+            match byte {
+                0xF8..=0xFF => on_message(MidiMessage::Realtime {
+                    status: byte,
+                    delta_frames,
+                }),
+                0xF0 => {
+                    if self.sysex.is_some() {
+                        debug!("Discarding unterminated SysEx message");
+                    }
+                    self.sysex = Some((vec![byte], delta_frames));
+                }
+                0xF7 => {
+                    if let Some((mut payload, delta_frames)) = self.sysex.take() {
+                        payload.push(byte);
+                        on_message(MidiMessage::SysEx { payload, delta_frames });
+                    }
+                }
+                0x80..=0xF6 => {
+                    // Any other status byte aborts a system exclusive message.
+                    if self.sysex.take().is_some() {
+                        debug!("Discarding unterminated SysEx message");
+                    }
+                    return;
+                }
+                _ => {
+                    if let Some((ref mut payload, _)) = self.sysex {
+                        payload.push(byte);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use event::{Event, MidiEvent, SysExEvent};
+    use util::{MidiMessage, MidiStreamParser, RealtimeMessage};
+
+    fn midi(data: [u8; 3], delta_frames: i32) -> Event<'static> {
+        Event::Midi(MidiEvent {
+            data,
+            delta_frames,
+            live: false,
+            note_length: None,
+            note_offset: None,
+            detune: 0,
+            note_off_velocity: 0,
+        })
+    }
+
+    fn parse(parser: &mut MidiStreamParser, events: &[Event]) -> Vec<MidiMessage> {
+        let mut messages = vec![];
+        for event in events {
+            parser.push(event, |m| messages.push(m));
+        }
+        messages
+    }
+
+    #[test]
+    fn running_status() {
+        let mut parser = MidiStreamParser::new();
+        let messages = parse(
+            &mut parser,
+            &[midi([0x90, 60, 100], 0), midi([64, 90, 0], 10), midi([0xC0, 5, 0], 20)],
+        );
+
+        assert_eq!(
+            messages,
+            vec![
+                MidiMessage::Short {
+                    data: [0x90, 60, 100],
+                    delta_frames: 0
+                },
+                MidiMessage::Short {
+                    data: [0x90, 64, 90],
+                    delta_frames: 10
+                },
+                MidiMessage::Short {
+                    data: [0xC0, 5, 0],
+                    delta_frames: 20
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn split_sysex() {
+        let first = [0xF0, 0x7E, 0x01];
+        let second = [0x02, 0xF8, 0x03, 0xF7];
+        let events = [
+            Event::SysEx(SysExEvent {
+                payload: &first,
+                delta_frames: 5,
+            }),
+            Event::SysEx(SysExEvent {
+                payload: &second,
+                delta_frames: 30,
+            }),
+        ];
+
+        let mut parser = MidiStreamParser::new();
+        let messages = parse(&mut parser, &events);
+
+        assert_eq!(
+            messages,
+            vec![
+                MidiMessage::Realtime {
+                    status: 0xF8,
+                    delta_frames: 30
+                },
+                MidiMessage::SysEx {
+                    payload: vec![0xF0, 0x7E, 0x01, 0x02, 0x03, 0xF7],
+                    delta_frames: 5
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn clock_ticks() {
+        let mut parser = MidiStreamParser::new();
+        let messages = parse(
+            &mut parser,
+            &[
+                midi([0xFA, 0, 0], 0),
+                midi([0xF8, 0, 0], 0),
+                midi([0x90, 60, 100], 4),
+                midi([0xF8, 0, 0], 8),
+                // Running status survives the clock tick.
+                midi([62, 100, 0], 9),
+                midi([0xF9, 0, 0], 10),
+                midi([0xFC, 0, 0], 16),
+            ],
+        );
+
+        let realtime: Vec<_> = messages.iter().filter_map(MidiMessage::realtime).collect();
+        assert_eq!(
+            realtime,
+            vec![
+                RealtimeMessage::Start,
+                RealtimeMessage::Clock,
+                RealtimeMessage::Clock,
+                RealtimeMessage::Stop
+            ]
+        );
+        assert_eq!(
+            messages[4],
+            MidiMessage::Short {
+                data: [0x90, 62, 100],
+                delta_frames: 9
+            }
+        );
+        // Undefined realtime status bytes are still reported.
+        assert_eq!(
+            messages[5],
+            MidiMessage::Realtime {
+                status: 0xF9,
+                delta_frames: 10
+            }
+        );
+        assert_eq!(RealtimeMessage::from_status(0xFB), Some(RealtimeMessage::Continue));
+        assert_eq!(RealtimeMessage::Continue.status(), 0xFB);
+    }
+}
@@ -1,7 +1,21 @@
 //! Structures for easing the implementation of VST plugins.
 
 mod atomic_float;
+mod block_history;
+mod editor_size;
+mod midi_stream;
 mod parameter_transfer;
+mod parameter_writer;
+mod preset_swap;
+mod time_info_cache;
+mod vendor_specific;
 
 pub use self::atomic_float::AtomicFloat;
+pub use self::block_history::BlockHistory;
+pub use self::editor_size::EditorSize;
+pub use self::midi_stream::{MidiMessage, MidiStreamParser, RealtimeMessage};
 pub use self::parameter_transfer::{ParameterTransfer, ParameterTransferIterator};
+pub use self::parameter_writer::ParameterWriter;
+pub use self::preset_swap::{PresetRead, PresetSwap};
+pub use self::time_info_cache::TimeInfoCache;
+pub use self::vendor_specific::VendorSpecificHandlers;
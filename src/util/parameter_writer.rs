@@ -0,0 +1,115 @@
+use api::AutomationState;
+use host::Host;
+
+/// Notifies the host of parameter changes made in the editor, unless the host is playing back
+/// automation.
+///
+/// While the host's automation state is `Read`, it sets the automated parameters itself. An
+/// editor that keeps reporting its own changes with `Host::automate` then fights the host, with
+/// the knob jumping between the two values. `ParameterWriter` checks the automation state before
+/// each notification and drops `begin_edit`, `automate` and `end_edit` while the host is
+/// reading. In every other state (including hosts that don't report one) the calls are passed
+/// on as usual.
+///
+/// The writer only notifies the host. The editor still sets the parameter on its parameter
+/// object, so the change is audible right away.
+pub struct ParameterWriter<H: Host> {
+    host: H,
+}
+
+impl<H: Host> ParameterWriter<H> {
+    /// Create a writer notifying `host`.
+    pub fn new(host: H) -> ParameterWriter<H> {
+        ParameterWriter { host }
+    }
+
+    /// Whether changes are currently passed on to the host.
+    pub fn is_writable(&self) -> bool {
+        self.host.get_automation_state() != AutomationState::Read
+    }
+
+    /// Signal the start of an edit gesture of parameter `index`, see `Host::begin_edit`.
+    pub fn begin_edit(&self, index: i32) {
+        if self.is_writable() {
+            self.host.begin_edit(index);
+        }
+    }
+
+    /// Report a new value of parameter `index`, see `Host::automate`.
+    ///
+    /// Returns `false` if the change was not passed on because the host is reading automation.
+    pub fn automate(&self, index: i32, value: f32) -> bool {
+        let writable = self.is_writable();
+        if writable {
+            self.host.automate(index, value);
+        }
+        writable
+    }
+
+    /// Signal the end of an edit gesture of parameter `index`, see `Host::end_edit`.
+    pub fn end_edit(&self, index: i32) {
+        if self.is_writable() {
+            self.host.end_edit(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use api::AutomationState;
+    use host::Host;
+    use util::ParameterWriter;
+
+    struct AutomatingHost {
+        state: Cell<AutomationState>,
+        calls: RefCell<Vec<&'static str>>,
+    }
+
+    impl<'a> Host for &'a AutomatingHost {
+        fn automate(&self, _index: i32, _value: f32) {
+            self.calls.borrow_mut().push("automate");
+        }
+
+        fn begin_edit(&self, _index: i32) {
+            self.calls.borrow_mut().push("begin");
+        }
+
+        fn end_edit(&self, _index: i32) {
+            self.calls.borrow_mut().push("end");
+        }
+
+        fn get_automation_state(&self) -> AutomationState {
+            self.state.get()
+        }
+    }
+
+    #[test]
+    fn parameter_writer() {
+        let host = AutomatingHost {
+            state: Cell::new(AutomationState::Read),
+            calls: RefCell::new(Vec::new()),
+        };
+        let writer = ParameterWriter::new(&host);
+
+        writer.begin_edit(0);
+        assert!(!writer.automate(0, 0.5));
+        writer.end_edit(0);
+        assert!(host.calls.borrow().is_empty());
+
+        for &state in &[
+            AutomationState::Unsupported,
+            AutomationState::Write,
+            AutomationState::ReadWrite,
+        ] {
+            host.state.set(state);
+            host.calls.borrow_mut().clear();
+
+            writer.begin_edit(0);
+            assert!(writer.automate(0, 0.5));
+            writer.end_edit(0);
+            assert_eq!(*host.calls.borrow(), vec!["begin", "automate", "end"]);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::thread;
+
+/// A double buffered set of parameters for switching between presets without the processing
+/// thread ever seeing a half loaded preset.
+///
+/// There are two banks of parameter values. The processing thread reads the *active* bank, while
+/// a preset is loaded into the other one between [`begin_set_preset`](#method.begin_set_preset)
+/// and [`end_set_preset`](#method.end_set_preset), which then makes it the active bank in a single
+/// atomic step. Outside of a preset change, `set_parameter` writes to the active bank directly,
+/// so automation works as usual.
+///
+/// Hosts bracket loading a preset with the `BeginSetPreset` and `EndSetPreset` opcodes, which
+/// call `PluginParameters::begin_set_preset` and `end_set_preset`. Forward them to this struct,
+/// and do the same around changing the values in `change_preset` and `load_preset_data`. See the
+/// `preset_swap` example.
+///
+/// To keep a preset change from modifying the values halfway through a block on the processing
+/// thread, use [`read`](#method.read) instead of calling `get_parameter` for every parameter.
+/// Automation outside of a preset change still updates single values during a block. A preset change
+/// which starts while a block still reads the previous bank waits for that block to finish (on
+/// the thread changing the preset, never on the processing thread). Only one thread may change
+/// presets at a time.
+pub struct PresetSwap {
+    banks: [Vec<AtomicU32>; 2],
+    readers: [AtomicUsize; 2],
+    active: AtomicUsize,
+    /// Nesting depth of `begin_set_preset` calls, 0 outside of a preset change.
+    depth: AtomicUsize,
+}
+
+/// The values of one bank of a `PresetSwap`, which no preset change writes to while this guard is
+/// alive. `set_parameter` outside of a preset change still writes to it, so single values may
+/// change during the read. Returned by [`read`](struct.PresetSwap.html#method.read).
+pub struct PresetRead<'a> {
+    swap: &'a PresetSwap,
+    bank: usize,
+}
+
+impl PresetSwap {
+    /// Create a new set of `parameter_count` parameters, all set to 0.
+    pub fn new(parameter_count: usize) -> PresetSwap {
+        let bank = || (0..parameter_count).map(|_| AtomicU32::new(0)).collect();
+        PresetSwap {
+            banks: [bank(), bank()],
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            active: AtomicUsize::new(0),
+            depth: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the current value of the parameter with index `index`.
+    pub fn get_parameter(&self, index: usize) -> f32 {
+        let bank = self.active.load(Ordering::SeqCst);
+        f32::from_bits(self.banks[bank][index].load(Ordering::Relaxed))
+    }
+
+    /// Set the value of the parameter with index `index`.
+    ///
+    /// Between `begin_set_preset` and `end_set_preset` the value only becomes visible at the end
+    /// of the preset change.
+    pub fn set_parameter(&self, index: usize, value: f32) {
+        let active = self.active.load(Ordering::SeqCst);
+        let bank = if self.depth.load(Ordering::SeqCst) > 0 {
+            1 - active
+        } else {
+            active
+        };
+        self.banks[bank][index].store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Start loading a preset. Until `end_set_preset`, all changes go to the inactive bank, which
+    /// starts out as a copy of the current values.
+    ///
+    /// Calls nest: inside a preset change already in progress, e.g. a `change_preset` inside the
+    /// host's `BeginSetPreset` and `EndSetPreset` calls, this only increases the nesting depth,
+    /// and the banks are switched by the outermost `end_set_preset`.
+    pub fn begin_set_preset(&self) {
+        if self.depth.load(Ordering::SeqCst) > 0 {
+            self.depth.fetch_add(1, Ordering::SeqCst);
+            return;
+        }
+        let inactive = 1 - self.active.load(Ordering::SeqCst);
+
+        // A block which started before the last preset change may still read this bank.
+        while self.readers[inactive].load(Ordering::SeqCst) != 0 {
+            thread::yield_now();
+        }
+
+        for (pending, current) in self.banks[inactive].iter().zip(&self.banks[1 - inactive]) {
+            pending.store(current.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+        self.depth.store(1, Ordering::SeqCst);
+    }
+
+    /// Finish loading a preset, making all changes since the outermost `begin_set_preset`
+    /// visible at once.
+    ///
+    /// Does nothing if no preset change was started.
+    pub fn end_set_preset(&self) {
+        match self.depth.load(Ordering::SeqCst) {
+            0 => {}
+            1 => {
+                self.depth.store(0, Ordering::SeqCst);
+                self.active.fetch_xor(1, Ordering::SeqCst);
+            }
+            depth => self.depth.store(depth - 1, Ordering::SeqCst),
+        }
+    }
+
+    /// Read the values of the active bank, which no preset change writes to until the returned
+    /// guard is dropped. Parameter changes outside of a preset change still show up in it. Meant
+    /// for the processing thread, e.g. once per block.
+    pub fn read(&self) -> PresetRead<'_> {
+        loop {
+            let bank = self.active.load(Ordering::SeqCst);
+            self.readers[bank].fetch_add(1, Ordering::SeqCst);
+            // The banks may have been swapped in the meantime.
+            if self.active.load(Ordering::SeqCst) == bank {
+                return PresetRead { swap: self, bank };
+            }
+            self.readers[bank].fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl<'a> PresetRead<'a> {
+    /// Get the value of the parameter with index `index`.
+    pub fn get_parameter(&self, index: usize) -> f32 {
+        f32::from_bits(self.swap.banks[self.bank][index].load(Ordering::Relaxed))
+    }
+}
+
+impl<'a> Drop for PresetRead<'a> {
+    fn drop(&mut self) {
+        self.swap.readers[self.bank].fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use util::PresetSwap;
+
+    #[test]
+    fn preset_swap() {
+        let swap = PresetSwap::new(2);
+        swap.set_parameter(0, 0.5);
+        assert_eq!(swap.get_parameter(0), 0.5);
+
+        swap.begin_set_preset();
+        swap.set_parameter(1, 1.0);
+        // Not visible before the preset change is finished.
+        assert_eq!(swap.get_parameter(1), 0.0);
+        {
+            let read = swap.read();
+            swap.end_set_preset();
+            // A running block keeps its values.
+            assert_eq!(read.get_parameter(1), 0.0);
+        }
+        assert_eq!(swap.get_parameter(0), 0.5);
+        assert_eq!(swap.get_parameter(1), 1.0);
+
+        // Without a preset change, values are set directly.
+        swap.end_set_preset();
+        swap.set_parameter(0, 0.25);
+        assert_eq!(swap.get_parameter(0), 0.25);
+        assert_eq!(swap.get_parameter(1), 1.0);
+    }
+
+    #[test]
+    fn nested_preset_change() {
+        let swap = PresetSwap::new(2);
+
+        // The host brackets a `change_preset` which brackets its own changes, then keeps setting
+        // parameters until its `EndSetPreset`.
+        swap.begin_set_preset();
+        swap.set_parameter(0, 0.5);
+        swap.begin_set_preset();
+        swap.set_parameter(1, 0.5);
+        swap.end_set_preset();
+        assert_eq!((swap.get_parameter(0), swap.get_parameter(1)), (0.0, 0.0));
+        swap.set_parameter(0, 1.0);
+        assert_eq!(swap.get_parameter(0), 0.0);
+        swap.end_set_preset();
+        assert_eq!((swap.get_parameter(0), swap.get_parameter(1)), (1.0, 0.5));
+
+        // Unbalanced ends are ignored.
+        swap.end_set_preset();
+        swap.set_parameter(1, 0.25);
+        assert_eq!(swap.get_parameter(1), 0.25);
+    }
+
+    #[test]
+    fn preset_swap_consistent_reads() {
+        const PARAMETERS: usize = 16;
+        let swap = Arc::new(PresetSwap::new(PARAMETERS));
+
+        let writer = {
+            let swap = Arc::clone(&swap);
+            thread::spawn(move || {
+                for preset in 1..200 {
+                    swap.begin_set_preset();
+                    for index in 0..PARAMETERS {
+                        swap.set_parameter(index, preset as f32);
+                    }
+                    swap.end_set_preset();
+                }
+            })
+        };
+
+        // Every parameter of a block always belongs to the same preset.
+        for _ in 0..2000 {
+            let read = swap.read();
+            let first = read.get_parameter(0);
+            assert!((1..PARAMETERS).all(|index| read.get_parameter(index) == first));
+        }
+        writer.join().unwrap();
+    }
+}
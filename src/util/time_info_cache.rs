@@ -0,0 +1,128 @@
+use std::cell::Cell;
+
+use api::{TimeInfo, TimeInfoFlags};
+use host::Host;
+
+/// Per-block cache of the host's time information.
+///
+/// Requesting time information from the host can be expensive, so plugins which read tempo or
+/// transport state in several places should only do so once per block. The first getter called
+/// after [`invalidate`](#method.invalidate) requests the time information from the host using
+/// the mask given in [`new`](#method.new); all other calls return the cached values.
+///
+/// **`invalidate` must be called at the start of every `process` / `process_f64` call**, otherwise
+/// the values of a previous block are returned.
+///
+/// Getters return `None` if the host did not provide time information or the corresponding
+/// `TimeInfoFlags` bit is not set.
+pub struct TimeInfoCache<H: Host> {
+    host: H,
+    mask: i32,
+    valid: Cell<bool>,
+    time_info: Cell<Option<TimeInfo>>,
+}
+
+impl<H: Host> TimeInfoCache<H> {
+    /// Create a cache which requests time information from `host` using `mask`.
+    ///
+    /// `mask` is a combination of `TimeInfoFlags`, e.g. `TimeInfoFlags::TEMPO_VALID.bits()`.
+    pub fn new(host: H, mask: i32) -> TimeInfoCache<H> {
+        TimeInfoCache {
+            host,
+            mask,
+            valid: Cell::new(false),
+            time_info: Cell::new(None),
+        }
+    }
+
+    /// Mark the cached values as stale. Call this at the start of every process call.
+    pub fn invalidate(&self) {
+        self.valid.set(false);
+    }
+
+    /// Get the full time information for the current block.
+    pub fn get(&self) -> Option<TimeInfo> {
+        if !self.valid.get() {
+            self.time_info.set(self.host.get_time_info(self.mask));
+            self.valid.set(true);
+        }
+        self.time_info.get()
+    }
+
+    fn get_if(&self, flag: TimeInfoFlags) -> Option<TimeInfo> {
+        self.get()
+            .filter(|t| TimeInfoFlags::from_bits_truncate(t.flags).contains(flag))
+    }
+
+    /// Current position in samples.
+    pub fn sample_pos(&self) -> Option<f64> {
+        self.get().map(|t| t.sample_pos)
+    }
+
+    /// Current sample rate in Hz.
+    pub fn sample_rate(&self) -> Option<f64> {
+        self.get().map(|t| t.sample_rate)
+    }
+
+    /// Current tempo in BPM.
+    pub fn tempo(&self) -> Option<f64> {
+        self.get_if(TimeInfoFlags::TEMPO_VALID).map(|t| t.tempo)
+    }
+
+    /// Current musical position in quarter notes.
+    pub fn ppq_pos(&self) -> Option<f64> {
+        self.get_if(TimeInfoFlags::PPQ_POS_VALID).map(|t| t.ppq_pos)
+    }
+
+    /// Current time signature as `(numerator, denominator)`.
+    pub fn time_signature(&self) -> Option<(i32, i32)> {
+        self.get_if(TimeInfoFlags::TIME_SIG_VALID)
+            .map(|t| (t.time_sig_numerator, t.time_sig_denominator))
+    }
+
+    /// Whether the host transport is playing.
+    pub fn is_playing(&self) -> Option<bool> {
+        self.get()
+            .map(|t| TimeInfoFlags::from_bits_truncate(t.flags).contains(TimeInfoFlags::TRANSPORT_PLAYING))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use api::{TimeInfo, TimeInfoFlags};
+    use host::Host;
+    use util::TimeInfoCache;
+
+    struct CountingHost {
+        requests: Cell<usize>,
+    }
+
+    impl<'a> Host for &'a CountingHost {
+        fn get_time_info(&self, mask: i32) -> Option<TimeInfo> {
+            self.requests.set(self.requests.get() + 1);
+            Some(TimeInfo {
+                tempo: 120.0 + self.requests.get() as f64,
+                flags: mask & TimeInfoFlags::TEMPO_VALID.bits(),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn time_info_cache() {
+        let host = CountingHost { requests: Cell::new(0) };
+        let cache = TimeInfoCache::new(&host, TimeInfoFlags::TEMPO_VALID.bits());
+
+        // Only fetched once per block.
+        assert_eq!(cache.tempo(), Some(121.0));
+        assert_eq!(cache.tempo(), Some(121.0));
+        assert_eq!(cache.ppq_pos(), None);
+        assert_eq!(host.requests.get(), 1);
+
+        cache.invalidate();
+        assert_eq!(cache.tempo(), Some(122.0));
+        assert_eq!(host.requests.get(), 2);
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+use std::os::raw::c_void;
+
+type Handler = Box<dyn FnMut(isize, *mut c_void, f32) -> isize + Send>;
+
+/// Typed handlers for vendor specific messages from the host, e.g. host extensions identified by
+/// magic `index` (and `value`) numbers.
+///
+/// Keep this in the plugin struct and forward `Plugin::vendor_specific` to
+/// [`dispatch`](#method.dispatch), falling back to raw handling for unknown messages:
+///
+/// ```
+/// # extern crate vst;
+/// # use std::os::raw::c_void;
+/// # use vst::plugin::{HostCallback, Info, Plugin};
+/// # use vst::util::VendorSpecificHandlers;
+/// struct ExamplePlugin {
+///     vendor_specific: VendorSpecificHandlers,
+/// }
+///
+/// impl Plugin for ExamplePlugin {
+///     fn new(_host: HostCallback) -> Self {
+///         let mut vendor_specific = VendorSpecificHandlers::new();
+///         // Answer a made up host extension with 1.
+///         vendor_specific.register(0x1234, None, |_value, _ptr, _opt| 1);
+///         ExamplePlugin { vendor_specific }
+///     }
+///
+///     fn vendor_specific(&mut self, index: i32, value: isize, ptr: *mut c_void, opt: f32) -> isize {
+///         self.vendor_specific.dispatch(index, value, ptr, opt).unwrap_or(0)
+///     }
+///
+///     // ...
+/// #     fn get_info(&self) -> Info { Default::default() }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// A handler registered for an exact `(index, value)` pair takes precedence over one registered
+/// for `index` with any value. Messages without a matching handler return `None` from
+/// `dispatch`.
+#[derive(Default)]
+pub struct VendorSpecificHandlers {
+    handlers: Vec<(i32, Option<isize>, Handler)>,
+}
+
+impl VendorSpecificHandlers {
+    /// Create an empty set of handlers.
+    pub fn new() -> VendorSpecificHandlers {
+        Default::default()
+    }
+
+    /// Register `handler` for messages with `index`, and `value` if it is `Some`.
+    ///
+    /// The handler is passed the `value`, `ptr` and `opt` arguments of the message, and its
+    /// result is returned to the host. Registering a handler for the same key again replaces it.
+    pub fn register<F>(&mut self, index: i32, value: Option<isize>, handler: F)
+    where
+        F: FnMut(isize, *mut c_void, f32) -> isize + Send + 'static,
+    {
+        self.handlers.retain(|&(i, v, _)| (i, v) != (index, value));
+        self.handlers.push((index, value, Box::new(handler)));
+    }
+
+    /// Call the handler matching `index` and `value`, returning its result.
+    pub fn dispatch(&mut self, index: i32, value: isize, ptr: *mut c_void, opt: f32) -> Option<isize> {
+        let position = self
+            .handlers
+            .iter()
+            .position(|&(i, v, _)| i == index && v == Some(value))
+            .or_else(|| self.handlers.iter().position(|&(i, v, _)| i == index && v.is_none()))?;
+        Some((self.handlers[position].2)(value, ptr, opt))
+    }
+}
+
+impl fmt::Debug for VendorSpecificHandlers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let keys: Vec<_> = self.handlers.iter().map(|&(index, value, _)| (index, value)).collect();
+        f.debug_struct("VendorSpecificHandlers")
+            .field("handlers", &keys)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::VendorSpecificHandlers;
+
+    #[test]
+    fn precedence() {
+        let mut handlers = VendorSpecificHandlers::new();
+        handlers.register(1, None, |value, _, _| value * 10);
+        handlers.register(1, Some(2), |_, _, _| -2);
+        handlers.register(3, Some(4), |_, _, opt| opt as isize);
+
+        assert_eq!(handlers.dispatch(1, 2, ptr::null_mut(), 0.0), Some(-2));
+        assert_eq!(handlers.dispatch(1, 5, ptr::null_mut(), 0.0), Some(50));
+        assert_eq!(handlers.dispatch(3, 4, ptr::null_mut(), 7.0), Some(7));
+        assert_eq!(handlers.dispatch(3, 5, ptr::null_mut(), 7.0), None);
+
+        // Registering the same key again replaces the handler.
+        handlers.register(1, Some(2), |_, _, _| 2);
+        assert_eq!(handlers.dispatch(1, 2, ptr::null_mut(), 0.0), Some(2));
+    }
+}